@@ -0,0 +1,65 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use ac_power::batch;
+use ac_power::number::Float;
+use ac_power::trig::{cos_sin, Theta};
+use ac_power::{Abc, Dq};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const LEN: usize = 4096;
+
+fn abc_to_dq(criterion: &mut Criterion) {
+    let abc: Vec<Abc<Float>> = (0..LEN)
+        .map(|n| Abc::from_polar(100.0, Theta::from_degrees(n as Float)))
+        .collect();
+    let theta: Vec<Theta> = (0..LEN).map(|n| Theta::from_degrees(n as Float)).collect();
+    let a: Vec<Float> = abc.iter().map(|abc| abc.a).collect();
+    let b: Vec<Float> = abc.iter().map(|abc| abc.b).collect();
+    let c: Vec<Float> = abc.iter().map(|abc| abc.c).collect();
+    let mut dq = vec![Dq::zero(); LEN];
+    let (mut d, mut q) = (vec![0.0; LEN], vec![0.0; LEN]);
+
+    let mut group = criterion.benchmark_group("abc_to_dq");
+    group.bench_function("per_sample", |bencher| {
+        bencher.iter(|| {
+            for ((abc, theta), dq) in abc.iter().zip(theta.iter()).zip(dq.iter_mut()) {
+                let (cos, sin) = cos_sin(*theta);
+                *dq = black_box(abc).to_dq(cos, sin);
+            }
+        })
+    });
+    group.bench_function("batch", |bencher| {
+        bencher.iter(|| batch::abc_to_dq(black_box(&abc), &theta, &mut dq))
+    });
+    group.bench_function("batch_accumulate", |bencher| {
+        bencher.iter(|| {
+            let mut phase = Theta::from(0);
+            batch::abc_to_dq_accumulate(
+                black_box(&abc),
+                &mut phase,
+                Theta::from_degrees(1.0),
+                &mut dq,
+            )
+        })
+    });
+    group.bench_function("batch_soa", |bencher| {
+        bencher.iter(|| batch::abc_to_dq_soa(black_box(&a), &b, &c, &theta, &mut d, &mut q))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, abc_to_dq);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use ac_power::number::Float;
+use ac_power::trig::{Backend, Idsp, Libm, Table, Theta};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const LEN: usize = 4096;
+
+fn cos_sin(criterion: &mut Criterion) {
+    let theta: Vec<Theta> = (0..LEN)
+        .map(|n| Theta::from_degrees(0.1 * n as Float))
+        .collect();
+
+    fn bench<B: Backend>(criterion: &mut Criterion, name: &str, theta: &[Theta]) {
+        criterion.bench_function(name, |bencher| {
+            bencher.iter(|| {
+                for theta in theta.iter() {
+                    black_box(B::cos_sin(black_box(*theta)));
+                }
+            })
+        });
+    }
+    bench::<Idsp>(criterion, "cos_sin/idsp", &theta);
+    bench::<Libm>(criterion, "cos_sin/libm", &theta);
+    bench::<Table>(criterion, "cos_sin/table", &theta);
+}
+
+fn atan2(criterion: &mut Criterion) {
+    let xy: Vec<(Float, Float)> = (0..LEN)
+        .map(|n| {
+            let radians = 0.002 * n as Float;
+            (100.0 * radians.cos(), 100.0 * radians.sin())
+        })
+        .collect();
+
+    fn bench<B: Backend>(criterion: &mut Criterion, name: &str, xy: &[(Float, Float)]) {
+        criterion.bench_function(name, |bencher| {
+            bencher.iter(|| {
+                for (x, y) in xy.iter() {
+                    black_box(B::atan2(black_box(*y), black_box(*x)));
+                }
+            })
+        });
+    }
+    bench::<Idsp>(criterion, "atan2/idsp", &xy);
+    bench::<Libm>(criterion, "atan2/libm", &xy);
+    bench::<Table>(criterion, "atan2/table", &xy);
+}
+
+criterion_group!(benches, cos_sin, atan2);
+criterion_main!(benches);
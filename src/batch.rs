@@ -0,0 +1,346 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Slice versions of the reference frame transforms, for post-processing sample buffers
+
+The per-sample transforms recalculate the 120 degree shifted cos/sin pairs for every sample. These
+go through the Clarke transform first, which needs no trig, and then a Park transform with a single
+cos/sin pair.  Buffers are processed in chunks, with the trig for the whole chunk calculated before
+the arithmetic, so the arithmetic loops are free of dependencies and can be auto-vectorized.
+
+```rust
+use ac_power::batch;
+use ac_power::trig::Theta;
+use ac_power::{Abc, Dq};
+
+let abc = [Abc { a: 1.0, b: -0.5, c: -0.5 }; 64];
+let mut dq = [Dq::zero(); 64];
+
+// the phase advances 1 degree per sample
+let mut theta = Theta::from(0);
+batch::abc_to_dq_accumulate(&abc, &mut theta, Theta::from_degrees(1.0), &mut dq);
+```
+
+There are also structure-of-arrays versions of the transforms (i.e. [clarke_soa], [park_soa],
+and [abc_to_dq_soa]), for buffers with a separate slice for each signal.
+
+All of the functions panic if the slices are not the same length.
+*/
+
+use crate::number::Num;
+use crate::trig::{cos_sin, Cos, Sin, Theta};
+use crate::{Abc, AlphaBeta, Dq};
+
+const CHUNK: usize = 32;
+
+// calculate the trig for a chunk of angles
+fn cos_sin_chunk(theta: &[Theta], cos: &mut [Cos; CHUNK], sin: &mut [Sin; CHUNK]) {
+    for ((theta, cos), sin) in theta.iter().zip(cos.iter_mut()).zip(sin.iter_mut()) {
+        (*cos, *sin) = cos_sin(*theta);
+    }
+}
+
+// calculate the trig for a chunk of an accumulating phase
+fn cos_sin_accumulate(
+    theta: &mut Theta,
+    step: Theta,
+    len: usize,
+    cos: &mut [Cos; CHUNK],
+    sin: &mut [Sin; CHUNK],
+) {
+    for (cos, sin) in cos.iter_mut().zip(sin.iter_mut()).take(len) {
+        (*cos, *sin) = cos_sin(*theta);
+        *theta += step;
+    }
+}
+
+/// Clarke transform of a buffer
+pub fn abc_to_alpha_beta<T: Num>(abc: &[Abc<T>], alpha_beta: &mut [AlphaBeta<T>]) {
+    assert_eq!(abc.len(), alpha_beta.len());
+    for (abc, alpha_beta) in abc.iter().zip(alpha_beta.iter_mut()) {
+        *alpha_beta = AlphaBeta::from(*abc);
+    }
+}
+
+/// Park transform of a buffer, with an angle for each sample
+pub fn alpha_beta_to_dq<T: Num>(alpha_beta: &[AlphaBeta<T>], theta: &[Theta], dq: &mut [Dq<T>]) {
+    assert_eq!(alpha_beta.len(), theta.len());
+    assert_eq!(alpha_beta.len(), dq.len());
+
+    let mut cos = [Cos::from(1.0); CHUNK];
+    let mut sin = [Sin::from(0.0); CHUNK];
+    for ((alpha_beta, theta), dq) in alpha_beta
+        .chunks(CHUNK)
+        .zip(theta.chunks(CHUNK))
+        .zip(dq.chunks_mut(CHUNK))
+    {
+        cos_sin_chunk(theta, &mut cos, &mut sin);
+        for (((alpha_beta, dq), cos), sin) in
+            alpha_beta.iter().zip(dq).zip(cos.iter()).zip(sin.iter())
+        {
+            *dq = alpha_beta.to_dq(*cos, *sin);
+        }
+    }
+}
+
+/// Transforms a buffer to the rotating reference frame, with an angle for each sample
+pub fn abc_to_dq<T: Num>(abc: &[Abc<T>], theta: &[Theta], dq: &mut [Dq<T>]) {
+    assert_eq!(abc.len(), theta.len());
+    assert_eq!(abc.len(), dq.len());
+
+    let mut cos = [Cos::from(1.0); CHUNK];
+    let mut sin = [Sin::from(0.0); CHUNK];
+    for ((abc, theta), dq) in abc
+        .chunks(CHUNK)
+        .zip(theta.chunks(CHUNK))
+        .zip(dq.chunks_mut(CHUNK))
+    {
+        cos_sin_chunk(theta, &mut cos, &mut sin);
+        for (((abc, dq), cos), sin) in abc.iter().zip(dq).zip(cos.iter()).zip(sin.iter()) {
+            *dq = AlphaBeta::from(*abc).to_dq(*cos, *sin);
+        }
+    }
+}
+
+/// Transforms a buffer to the rotating reference frame, with a phase that advances by `step`
+/// every sample
+///
+/// `theta` is the phase of the first sample, and is left at the phase of the sample after the
+/// buffer, so consecutive buffers can be processed seamlessly.
+pub fn abc_to_dq_accumulate<T: Num>(
+    abc: &[Abc<T>],
+    theta: &mut Theta,
+    step: Theta,
+    dq: &mut [Dq<T>],
+) {
+    assert_eq!(abc.len(), dq.len());
+
+    let mut cos = [Cos::from(1.0); CHUNK];
+    let mut sin = [Sin::from(0.0); CHUNK];
+    for (abc, dq) in abc.chunks(CHUNK).zip(dq.chunks_mut(CHUNK)) {
+        cos_sin_accumulate(theta, step, abc.len(), &mut cos, &mut sin);
+        for (((abc, dq), cos), sin) in abc.iter().zip(dq).zip(cos.iter()).zip(sin.iter()) {
+            *dq = AlphaBeta::from(*abc).to_dq(*cos, *sin);
+        }
+    }
+}
+
+/// Transforms a buffer from the rotating reference frame, with an angle for each sample
+pub fn dq_to_abc<T: Num>(dq: &[Dq<T>], theta: &[Theta], abc: &mut [Abc<T>]) {
+    assert_eq!(dq.len(), theta.len());
+    assert_eq!(dq.len(), abc.len());
+
+    let mut cos = [Cos::from(1.0); CHUNK];
+    let mut sin = [Sin::from(0.0); CHUNK];
+    for ((dq, theta), abc) in dq
+        .chunks(CHUNK)
+        .zip(theta.chunks(CHUNK))
+        .zip(abc.chunks_mut(CHUNK))
+    {
+        cos_sin_chunk(theta, &mut cos, &mut sin);
+        for (((dq, abc), cos), sin) in dq.iter().zip(abc).zip(cos.iter()).zip(sin.iter()) {
+            *abc = Abc::from(dq.to_alpha_beta(*cos, *sin));
+        }
+    }
+}
+
+/// Clarke transform of a structure-of-arrays buffer
+pub fn clarke_soa<T: Num>(a: &[T], b: &[T], c: &[T], alpha: &mut [T], beta: &mut [T]) {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), c.len());
+    assert_eq!(a.len(), alpha.len());
+    assert_eq!(a.len(), beta.len());
+
+    for ((((a, b), c), alpha), beta) in a.iter().zip(b).zip(c).zip(alpha).zip(beta) {
+        let alpha_beta = AlphaBeta::from(Abc {
+            a: *a,
+            b: *b,
+            c: *c,
+        });
+        (*alpha, *beta) = (alpha_beta.alpha, alpha_beta.beta);
+    }
+}
+
+/// Park transform of a structure-of-arrays buffer
+pub fn park_soa<T: Num>(alpha: &[T], beta: &[T], theta: &[Theta], d: &mut [T], q: &mut [T]) {
+    assert_eq!(alpha.len(), beta.len());
+    assert_eq!(alpha.len(), theta.len());
+    assert_eq!(alpha.len(), d.len());
+    assert_eq!(alpha.len(), q.len());
+
+    let mut cos = [Cos::from(1.0); CHUNK];
+    let mut sin = [Sin::from(0.0); CHUNK];
+    for ((((alpha, beta), theta), d), q) in alpha
+        .chunks(CHUNK)
+        .zip(beta.chunks(CHUNK))
+        .zip(theta.chunks(CHUNK))
+        .zip(d.chunks_mut(CHUNK))
+        .zip(q.chunks_mut(CHUNK))
+    {
+        cos_sin_chunk(theta, &mut cos, &mut sin);
+        for (((((alpha, beta), d), q), cos), sin) in alpha
+            .iter()
+            .zip(beta)
+            .zip(d)
+            .zip(q)
+            .zip(cos.iter())
+            .zip(sin.iter())
+        {
+            *d = (*alpha * *sin) - (*beta * *cos);
+            *q = (*alpha * *cos) + (*beta * *sin);
+        }
+    }
+}
+
+/// Transforms a structure-of-arrays buffer to the rotating reference frame
+pub fn abc_to_dq_soa<T: Num>(a: &[T], b: &[T], c: &[T], theta: &[Theta], d: &mut [T], q: &mut [T]) {
+    assert_eq!(a.len(), theta.len());
+
+    // use the outputs as scratch for the clarke transform
+    clarke_soa(a, b, c, d, q);
+
+    let mut cos = [Cos::from(1.0); CHUNK];
+    let mut sin = [Sin::from(0.0); CHUNK];
+    for ((theta, d), q) in theta
+        .chunks(CHUNK)
+        .zip(d.chunks_mut(CHUNK))
+        .zip(q.chunks_mut(CHUNK))
+    {
+        cos_sin_chunk(theta, &mut cos, &mut sin);
+        for (((d, q), cos), sin) in d.iter_mut().zip(q).zip(cos.iter()).zip(sin.iter()) {
+            let (alpha, beta) = (*d, *q);
+            *d = (alpha * *sin) - (beta * *cos);
+            *q = (alpha * *cos) + (beta * *sin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::number::Float;
+    use approx::assert_abs_diff_eq;
+
+    const LEN: usize = 100;
+
+    fn buffers() -> (Vec<Abc<Float>>, Vec<Theta>) {
+        let abc = (0..LEN)
+            .map(|n| {
+                let x = n as Float;
+                Abc {
+                    a: 100.0 * (0.1 * x).sin() + 3.0,
+                    b: 90.0 * (0.1 * x - 2.0).sin(),
+                    c: 110.0 * (0.1 * x + 2.0).sin() - 1.0,
+                }
+            })
+            .collect();
+        let step = Theta::from_degrees(7.0);
+        let theta = (0..LEN)
+            .scan(Theta::from(0), |theta, _| {
+                let current = *theta;
+                *theta += step;
+                Some(current)
+            })
+            .collect();
+        (abc, theta)
+    }
+
+    fn check_dq(dq0: &Dq<Float>, dq1: &Dq<Float>) {
+        assert_abs_diff_eq!(dq0.d, dq1.d, epsilon = 0.0001);
+        assert_abs_diff_eq!(dq0.q, dq1.q, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn matches_per_sample() {
+        let (abc, theta) = buffers();
+        let expected: Vec<Dq<Float>> = abc
+            .iter()
+            .zip(theta.iter())
+            .map(|(abc, theta)| {
+                let (cos, sin) = cos_sin(*theta);
+                AlphaBeta::from(*abc).to_dq(cos, sin)
+            })
+            .collect();
+
+        let mut dq = vec![Dq::zero(); LEN];
+        abc_to_dq(&abc, &theta, &mut dq);
+        for (dq, expected) in dq.iter().zip(expected.iter()) {
+            check_dq(dq, expected);
+        }
+
+        let mut alpha_beta = vec![AlphaBeta::zero(); LEN];
+        abc_to_alpha_beta(&abc, &mut alpha_beta);
+        alpha_beta_to_dq(&alpha_beta, &theta, &mut dq);
+        for (dq, expected) in dq.iter().zip(expected.iter()) {
+            check_dq(dq, expected);
+        }
+
+        let mut phase = Theta::from(0);
+        abc_to_dq_accumulate(&abc, &mut phase, Theta::from_degrees(7.0), &mut dq);
+        for (dq, expected) in dq.iter().zip(expected.iter()) {
+            check_dq(dq, expected);
+        }
+        assert_abs_diff_eq!(
+            phase.to_degrees(),
+            Theta::from_degrees(7.0 * LEN as Float).to_degrees(),
+            epsilon = 0.001
+        );
+
+        let mut round_trip = vec![Abc::zero(); LEN];
+        let dq: Vec<Dq<Float>> = expected;
+        dq_to_abc(&dq, &theta, &mut round_trip);
+        for ((dq, theta), round_trip) in dq.iter().zip(theta.iter()).zip(round_trip.iter()) {
+            let (cos, sin) = cos_sin(*theta);
+            let abc = dq.to_abc(cos, sin);
+            assert_abs_diff_eq!(abc.a, round_trip.a, epsilon = 0.001);
+            assert_abs_diff_eq!(abc.b, round_trip.b, epsilon = 0.001);
+            assert_abs_diff_eq!(abc.c, round_trip.c, epsilon = 0.001);
+        }
+    }
+
+    #[test]
+    fn structure_of_arrays() {
+        let (abc, theta) = buffers();
+        let a: Vec<Float> = abc.iter().map(|abc| abc.a).collect();
+        let b: Vec<Float> = abc.iter().map(|abc| abc.b).collect();
+        let c: Vec<Float> = abc.iter().map(|abc| abc.c).collect();
+
+        let mut expected = vec![Dq::zero(); LEN];
+        abc_to_dq(&abc, &theta, &mut expected);
+
+        let (mut d, mut q) = (vec![0.0; LEN], vec![0.0; LEN]);
+        abc_to_dq_soa(&a, &b, &c, &theta, &mut d, &mut q);
+        for ((d, q), expected) in d.iter().zip(q.iter()).zip(expected.iter()) {
+            check_dq(&Dq { d: *d, q: *q }, expected);
+        }
+
+        let (mut alpha, mut beta) = (vec![0.0; LEN], vec![0.0; LEN]);
+        clarke_soa(&a, &b, &c, &mut alpha, &mut beta);
+        park_soa(&alpha, &beta, &theta, &mut d, &mut q);
+        for ((d, q), expected) in d.iter().zip(q.iter()).zip(expected.iter()) {
+            check_dq(&Dq { d: *d, q: *q }, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn length_mismatch() {
+        let (abc, theta) = buffers();
+        let mut dq = vec![Dq::zero(); LEN - 1];
+        abc_to_dq(&abc, &theta, &mut dq);
+    }
+}
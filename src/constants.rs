@@ -17,10 +17,14 @@
 Common constants used in instantaneos power theory
 */
 
-pub const ONE_THIRD: f32 = 0.3333333333333333;
-pub const TWO_THIRDS: f32 = 0.6666666666666666;
-pub const SQRT_3_OVER_3: f32 = 0.5773502691896257;
-pub const ONE_HALF: f32 = 0.5;
-pub const SQRT_3_OVER_2: f32 = 0.8660254037844386;
-pub const PI: f32 = core::f32::consts::PI;
-pub const FRAC_1_SQRT_3: f32 = 0.577350269189625764509148780501957456_f32;
+use crate::number::Float;
+
+pub const ONE_THIRD: Float = 0.3333333333333333_f64 as Float;
+pub const TWO_THIRDS: Float = 0.6666666666666666_f64 as Float;
+pub const SQRT_3_OVER_3: Float = 0.5773502691896257_f64 as Float;
+pub const ONE_HALF: Float = 0.5;
+pub const SQRT_3_OVER_2: Float = 0.8660254037844386_f64 as Float;
+pub const SQRT_2: Float = core::f64::consts::SQRT_2 as Float;
+pub const FRAC_1_SQRT_2: Float = core::f64::consts::FRAC_1_SQRT_2 as Float;
+pub const PI: Float = core::f64::consts::PI as Float;
+pub const FRAC_1_SQRT_3: Float = 0.5773502691896257_f64 as Float;
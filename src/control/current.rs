@@ -0,0 +1,267 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use super::DqPi;
+use crate::constants::PI;
+use crate::newtypes::{Current, Frequency, Impedance, Voltage};
+use crate::number::Float;
+use crate::reference_frames::{AlphaBeta, Dq};
+use crate::trig::{cos_sin, Theta};
+
+/// A synchronous frame current controller for an inductive filter
+///
+/// The output voltage is `v = pi(i* - i) + jωL·i + e`: the PI regulators act on the current error, the ωL cross
+/// coupling of the filter inductance is decoupled, and the grid voltage `e` is fed forward (optionally through a
+/// first order filter).  The output is transformed to [AlphaBeta] at an angle advanced by the computation and
+/// modulation delay, 1.5 samples by default.
+#[derive(Debug, Copy, Clone)]
+pub struct CurrentController {
+    pi: DqPi<Voltage>,
+    inductance: Float,
+    ts: Float,
+    delay: Float,
+    alpha: Float,
+    feedforward: Option<Dq<Float>>,
+}
+
+impl CurrentController {
+    /// Creates a controller with a PI regulator for a filter inductance (in henries), updated every `ts` seconds
+    pub fn new(pi: DqPi<Voltage>, inductance: Float, ts: Float) -> Self {
+        Self {
+            pi,
+            inductance,
+            ts,
+            delay: 1.5,
+            alpha: 1.0,
+            feedforward: None,
+        }
+    }
+
+    /// Filters the grid voltage feedforward with a time constant (in seconds)
+    pub fn with_feedforward_filter(mut self, time_constant: Float) -> Self {
+        self.alpha = self.ts / (time_constant + self.ts);
+        self
+    }
+
+    /// Sets the delay compensated (in samples), 1.5 by default for one sample of computation and half a sample of
+    /// modulation
+    pub fn with_delay(mut self, samples: Float) -> Self {
+        self.delay = samples;
+        self
+    }
+
+    /// Calculates the output voltage reference for a current reference, the measured current and grid voltage, and the
+    /// angle and frequency of the rotating frame
+    pub fn update(
+        &mut self,
+        reference: Dq<Current>,
+        current: Dq<Current>,
+        grid: Dq<Voltage>,
+        theta: Theta,
+        frequency: Frequency,
+    ) -> AlphaBeta<Voltage> {
+        let w = 2.0 * PI * Float::from(frequency);
+        let reactance = Impedance::from(w * self.inductance);
+
+        let grid: Dq<Float> = Dq {
+            d: grid.d.into(),
+            q: grid.q.into(),
+        };
+        let feedforward = match self.feedforward {
+            Some(previous) => Dq {
+                d: previous.d + (grid.d - previous.d) * self.alpha,
+                q: previous.q + (grid.q - previous.q) * self.alpha,
+            },
+            None => grid,
+        };
+        self.feedforward = Some(feedforward);
+
+        let pi = self.pi.update(reference, current);
+        let output = Dq {
+            d: pi.d - reactance * current.q + Voltage::from(feedforward.d),
+            q: pi.q + reactance * current.d + Voltage::from(feedforward.q),
+        };
+
+        // advance the angle by the delay
+        let (cos, sin) = cos_sin(Theta::from_radians(self.delay * self.ts * w));
+        let output = output.rotate(cos, sin);
+        let (cos, sin) = cos_sin(theta);
+        output.to_alpha_beta(cos, sin)
+    }
+
+    /// Clears the PI integrators and the feedforward filter
+    pub fn reset(&mut self) {
+        self.pi.reset();
+        self.feedforward = None;
+    }
+
+    pub fn pi(&self) -> &DqPi<Voltage> {
+        &self.pi
+    }
+
+    pub fn pi_mut(&mut self) -> &mut DqPi<Voltage> {
+        &mut self.pi
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::reference_frames::Abc;
+    use crate::tests::current;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+    const L: Float = 2e-3;
+    const R: Float = 0.05;
+    const F: Float = 50.0;
+    const AMPLITUDE: Float = 325.0;
+
+    // an inductive filter to a stiff grid, with the voltage reference applied a sample after it's calculated, and
+    // integrated in steps within each sample
+    struct Grid {
+        current: AlphaBeta<Float>,
+        applied: AlphaBeta<Float>,
+        time: Float,
+    }
+
+    impl Grid {
+        fn new() -> Self {
+            Self {
+                current: AlphaBeta::zero(),
+                applied: AlphaBeta::zero(),
+                time: 0.0,
+            }
+        }
+
+        fn theta(&self) -> Theta {
+            Theta::from_radians(2.0 * PI * F * self.time)
+        }
+
+        fn voltage(&self) -> AlphaBeta<Float> {
+            AlphaBeta::from(Abc::from_polar(AMPLITUDE, self.theta()))
+        }
+
+        fn step(&mut self, voltage: AlphaBeta<Voltage>) {
+            const STEPS: usize = 20;
+            let h = TS / (STEPS as Float);
+            for _ in 0..STEPS {
+                let e = self.voltage();
+                self.current.alpha +=
+                    h * (self.applied.alpha - R * self.current.alpha - e.alpha) / L;
+                self.current.beta += h * (self.applied.beta - R * self.current.beta - e.beta) / L;
+                self.time += h;
+            }
+            self.applied = AlphaBeta {
+                alpha: voltage.alpha.into(),
+                beta: voltage.beta.into(),
+            };
+        }
+    }
+
+    fn controller() -> CurrentController {
+        let bandwidth = 2.0 * PI * 300.0;
+        CurrentController::new(DqPi::new(L * bandwidth, R * bandwidth, TS), L, TS)
+    }
+
+    // runs the loop, returning the measured current in the grid's frame, and the largest deviation of the current
+    // from the reference on the q axis
+    fn run(
+        controller: &mut CurrentController,
+        grid: &mut Grid,
+        reference: Dq<Current>,
+        samples: usize,
+    ) -> (Dq<Float>, Float) {
+        let mut current = Dq::zero();
+        let mut deviation: Float = 0.0;
+        for _ in 0..samples {
+            let theta = grid.theta();
+            let (cos, sin) = cos_sin(theta);
+            current = grid.current.to_dq(cos, sin);
+            let e = grid.voltage().to_dq(cos, sin);
+            let voltage = controller.update(
+                reference,
+                Dq {
+                    d: current.d.into(),
+                    q: current.q.into(),
+                },
+                Dq {
+                    d: e.d.into(),
+                    q: e.q.into(),
+                },
+                theta,
+                F.into(),
+            );
+            deviation = deviation.max(Float::abs(current.q - Float::from(reference.q)));
+            grid.step(voltage);
+        }
+        (current, deviation)
+    }
+
+    #[test]
+    fn steady_state() {
+        let mut controller = controller().with_feedforward_filter(1e-3);
+        let mut grid = Grid::new();
+        let (i, _) = run(&mut controller, &mut grid, current(20.0, -10.0), 2000);
+        assert_abs_diff_eq!(i.d, 20.0, epsilon = 0.05);
+        assert_abs_diff_eq!(i.q, -10.0, epsilon = 0.05);
+    }
+
+    #[test]
+    fn decoupling() {
+        // compensating the delay halves the disturbance of the q axis from a step on the d axis, and settles faster
+        let mut results = [(Dq::zero(), 0.0); 2];
+        for (result, &delay) in results.iter_mut().zip([0.0, 1.5].iter()) {
+            let mut controller = controller().with_delay(delay);
+            let mut grid = Grid::new();
+            run(&mut controller, &mut grid, current(0.0, 0.0), 2000);
+            *result = run(&mut controller, &mut grid, current(40.0, 0.0), 200);
+        }
+        let (uncompensated, compensated) = (results[0], results[1]);
+        assert_abs_diff_eq!(compensated.0.d, 40.0, epsilon = 0.01);
+        assert!(Float::abs(uncompensated.0.d - 40.0) > 0.1);
+        assert!(compensated.1 < 1.1);
+        assert!(compensated.1 < 0.6 * uncompensated.1);
+    }
+
+    #[test]
+    fn delay_rotation() {
+        // the output is the decoupled voltage, at the angle 1.5 samples ahead
+        let mut controller = CurrentController::new(DqPi::new(0.0, 0.0, TS), L, TS);
+        let theta = Theta::from_degrees(30.0);
+        let output = controller.update(
+            current(0.0, 0.0),
+            current(10.0, 0.0),
+            Dq {
+                d: Voltage::from(0.0),
+                q: Voltage::from(100.0),
+            },
+            theta,
+            F.into(),
+        );
+
+        let w = 2.0 * PI * F;
+        let expected = Dq {
+            d: 0.0,
+            q: 100.0 + w * L * 10.0,
+        };
+        let advanced = theta + Theta::from_radians(1.5 * TS * w);
+        let (cos, sin) = cos_sin(advanced);
+        let expected = expected.to_alpha_beta(cos, sin);
+        assert_abs_diff_eq!(Float::from(output.alpha), expected.alpha, epsilon = 0.01);
+        assert_abs_diff_eq!(Float::from(output.beta), expected.beta, epsilon = 0.01);
+    }
+}
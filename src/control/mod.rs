@@ -0,0 +1,60 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Discrete controllers for vector quantities
+
+1. [DqPi] - A PI controller on a [Dq](crate::Dq) vector, with the output limited by its magnitude, clamping or
+   back-calculation anti-windup, and tracking for bumpless transfer
+2. [AlphaBetaPr] - A proportional-resonant controller on an [AlphaBeta](crate::AlphaBeta) vector, with a resonant term
+   for each harmonic, discretized with the Tustin transform prewarped to each resonant frequency
+3. [CurrentController] - A synchronous frame current controller, with [DqPi] regulators, ωL decoupling, grid voltage
+   feedforward and computation delay compensation, producing an [AlphaBeta](crate::AlphaBeta) voltage reference
+
+The controllers are generic over their input and output types, e.g. a current controller takes [Current](crate::Current)
+and produces [Voltage](crate::Voltage), with gains in Float.
+
+```rust
+use ac_power::constants::PI;
+use ac_power::control::DqPi;
+use ac_power::number::Float;
+use ac_power::{Current, Dq, Voltage};
+use approx::assert_abs_diff_eq;
+
+// a PI controller for an RL load (1mH and 0.1Ω), with a 500Hz bandwidth, limited to 400V
+let (l, r, ts) = (1e-3, 0.1, 1e-4);
+let bandwidth = 2.0 * PI * 500.0;
+let mut pi: DqPi<Voltage> = DqPi::new(l * bandwidth, r * bandwidth, ts).with_limit(Voltage::from(400.0));
+
+let reference = Dq { d: Current::from(10.0), q: Current::from(-5.0) };
+let mut current = Dq { d: 0.0, q: 0.0 };
+for _ in 0..1000 {
+    let feedback = Dq { d: Current::from(current.d), q: Current::from(current.q) };
+    let voltage = pi.update(reference, feedback);
+    current.d += ts * (Float::from(voltage.d) - r * current.d) / l;
+    current.q += ts * (Float::from(voltage.q) - r * current.q) / l;
+}
+assert_abs_diff_eq!(current.d, 10.0, epsilon = 1e-3);
+assert_abs_diff_eq!(current.q, -5.0, epsilon = 1e-3);
+```
+*/
+
+mod current;
+mod pi;
+mod pr;
+
+pub use current::CurrentController;
+pub use pi::{AntiWindup, DqPi};
+pub use pr::AlphaBetaPr;
@@ -0,0 +1,292 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::number::{Float, Num};
+use crate::reference_frames::Dq;
+use core::marker::PhantomData;
+
+/// How the integrator is kept from winding up while the output is limited
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AntiWindup {
+    /// Stops integrating while the output is limited and the error would increase it
+    Clamping,
+    /// Feeds the amount the output is limited by back to the integrator, with a gain (per second)
+    BackCalculation(Float),
+}
+
+/// A PI controller on a [Dq] vector
+///
+/// The integrator is updated with forward Euler, so the output is `kp * e[n] + ki * ts * (e[0] + ... + e[n-1])`.
+#[derive(Debug, Copy, Clone)]
+pub struct DqPi<T> {
+    kp: Float,
+    ki: Float,
+    ts: Float,
+    limit: Option<Float>,
+    anti_windup: AntiWindup,
+    integral: Dq<Float>,
+    output: PhantomData<T>,
+}
+
+impl<T: Num> DqPi<T> {
+    /// Creates a controller for proportional and integral gains, updated every `ts` seconds
+    pub fn new(kp: Float, ki: Float, ts: Float) -> Self {
+        Self {
+            kp,
+            ki,
+            ts,
+            limit: None,
+            anti_windup: AntiWindup::Clamping,
+            integral: Dq::zero(),
+            output: PhantomData,
+        }
+    }
+
+    /// Limits the magnitude of the output vector
+    pub fn with_limit(mut self, limit: T) -> Self {
+        self.limit = Some(limit.into());
+        self
+    }
+
+    /// Sets the anti-windup method (clamping by default)
+    pub fn with_anti_windup(mut self, anti_windup: AntiWindup) -> Self {
+        self.anti_windup = anti_windup;
+        self
+    }
+
+    /// Calculates the output for a reference and feedback
+    pub fn update<I: Num>(&mut self, reference: Dq<I>, feedback: Dq<I>) -> Dq<T> {
+        let error = Self::error(reference, feedback);
+        let output = Dq {
+            d: self.kp * error.d + self.integral.d,
+            q: self.kp * error.q + self.integral.q,
+        };
+        let limited = self.limit(output);
+
+        let (d, q) = match self.anti_windup {
+            AntiWindup::Clamping => {
+                // the error increases the output when they're aligned
+                let saturated = limited != output;
+                if saturated && error.d * output.d + error.q * output.q > 0.0 {
+                    (0.0, 0.0)
+                } else {
+                    (self.ki * error.d, self.ki * error.q)
+                }
+            }
+            AntiWindup::BackCalculation(gain) => (
+                self.ki * error.d + gain * (limited.d - output.d),
+                self.ki * error.q + gain * (limited.q - output.q),
+            ),
+        };
+        self.integral.d += self.ts * d;
+        self.integral.q += self.ts * q;
+
+        Dq {
+            d: limited.d.into(),
+            q: limited.q.into(),
+        }
+    }
+
+    /// Sets the integrator so that the output for a reference and feedback is `output`
+    ///
+    /// Calling this every sample while another controller is in use makes the transfer to this controller bumpless.
+    pub fn track<I: Num>(&mut self, output: Dq<T>, reference: Dq<I>, feedback: Dq<I>) {
+        let error = Self::error(reference, feedback);
+        self.integral = Dq {
+            d: output.d.into() - self.kp * error.d,
+            q: output.q.into() - self.kp * error.q,
+        };
+    }
+
+    /// Clears the integrator
+    pub fn reset(&mut self) {
+        self.integral = Dq::zero();
+    }
+
+    /// The integral term of the output
+    pub fn integral(&self) -> Dq<T> {
+        Dq {
+            d: self.integral.d.into(),
+            q: self.integral.q.into(),
+        }
+    }
+
+    fn error<I: Num>(reference: Dq<I>, feedback: Dq<I>) -> Dq<Float> {
+        Dq {
+            d: reference.d.into() - feedback.d.into(),
+            q: reference.q.into() - feedback.q.into(),
+        }
+    }
+
+    // limits the magnitude, keeping the direction
+    fn limit(&self, output: Dq<Float>) -> Dq<Float> {
+        match self.limit {
+            Some(limit) => {
+                let magnitude = libm::Libm::<Float>::hypot(output.d, output.q);
+                if magnitude > limit {
+                    let scale = limit / magnitude;
+                    Dq {
+                        d: output.d * scale,
+                        q: output.q * scale,
+                    }
+                } else {
+                    output
+                }
+            }
+            None => output,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::newtypes::{Current, Voltage};
+    use crate::tests::current;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+    const L: Float = 1e-3;
+    const R: Float = 0.1;
+
+    // an RL load in the rotating frame, without cross-coupling
+    struct Load {
+        current: Dq<Float>,
+    }
+
+    impl Load {
+        fn step(&mut self, voltage: Dq<Voltage>) -> Dq<Current> {
+            let (d, q): (Float, Float) = (voltage.d.into(), voltage.q.into());
+            self.current.d += TS * (d - R * self.current.d) / L;
+            self.current.q += TS * (q - R * self.current.q) / L;
+            self.feedback()
+        }
+
+        fn feedback(&self) -> Dq<Current> {
+            Dq {
+                d: self.current.d.into(),
+                q: self.current.q.into(),
+            }
+        }
+    }
+
+    fn controller() -> DqPi<Voltage> {
+        let bandwidth = 2.0 * crate::constants::PI * 500.0;
+        DqPi::new(L * bandwidth, R * bandwidth, TS)
+    }
+
+    // runs the loop, returning the largest magnitude of the output
+    fn run(
+        pi: &mut DqPi<Voltage>,
+        load: &mut Load,
+        reference: Dq<Current>,
+        samples: usize,
+    ) -> Float {
+        let mut largest: Float = 0.0;
+        for _ in 0..samples {
+            let voltage = pi.update(reference, load.feedback());
+            let (d, q): (Float, Float) = (voltage.d.into(), voltage.q.into());
+            largest = largest.max(libm::Libm::<Float>::hypot(d, q));
+            load.step(voltage);
+        }
+        largest
+    }
+
+    #[test]
+    fn step_response() {
+        let mut pi = controller();
+        let mut load = Load {
+            current: Dq::zero(),
+        };
+
+        // the proportional gain cancels the inductance, so the loop is first order, closing kp * ts / L of the error
+        // each sample
+        run(&mut pi, &mut load, current(10.0, -5.0), 3);
+        let closed = 1.0 - libm::Libm::<Float>::pow(1.0 - 0.1 * crate::constants::PI, 3.0);
+        assert_abs_diff_eq!(load.current.d, 10.0 * closed, epsilon = 0.01);
+        assert_abs_diff_eq!(load.current.q, -5.0 * closed, epsilon = 0.01);
+
+        run(&mut pi, &mut load, current(10.0, -5.0), 100);
+        assert_abs_diff_eq!(load.current.d, 10.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(load.current.q, -5.0, epsilon = 1e-4);
+
+        // the integral supplies the resistive drop
+        let integral = pi.integral();
+        assert_abs_diff_eq!(Float::from(integral.d), 1.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(Float::from(integral.q), -0.5, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn limit() {
+        for &anti_windup in [AntiWindup::Clamping, AntiWindup::BackCalculation(2000.0)].iter() {
+            let mut pi = controller()
+                .with_limit(Voltage::from(5.0))
+                .with_anti_windup(anti_windup);
+            let mut load = Load {
+                current: Dq::zero(),
+            };
+
+            // the reference needs 10V, so the output is limited to 5V in the same direction
+            let largest = run(&mut pi, &mut load, current(60.0, 80.0), 10000);
+            assert_abs_diff_eq!(largest, 5.0, epsilon = 1e-4);
+            assert_abs_diff_eq!(load.current.d, 30.0, epsilon = 0.01);
+            assert_abs_diff_eq!(load.current.q, 40.0, epsilon = 0.01);
+
+            // the integrator hasn't wound up, so a reachable reference is settled to quickly
+            run(&mut pi, &mut load, current(10.0, 0.0), 1000);
+            assert_abs_diff_eq!(load.current.d, 10.0, epsilon = 0.05);
+            assert_abs_diff_eq!(load.current.q, 0.0, epsilon = 0.05);
+        }
+
+        // which an unprotected integrator would have
+        let mut pi = controller()
+            .with_limit(Voltage::from(5.0))
+            .with_anti_windup(AntiWindup::BackCalculation(0.0));
+        let mut load = Load {
+            current: Dq::zero(),
+        };
+        run(&mut pi, &mut load, current(60.0, 80.0), 10000);
+        run(&mut pi, &mut load, current(10.0, 0.0), 1000);
+        assert!(load.current.d > 20.0);
+    }
+
+    #[test]
+    fn bumpless_transfer() {
+        let mut pi = controller();
+        let mut load = Load {
+            current: Dq::zero(),
+        };
+
+        // open-loop operation at 2V, with the controller tracking
+        let open_loop = Dq {
+            d: Voltage::from(2.0),
+            q: Voltage::from(0.5),
+        };
+        let reference = current(15.0, 2.0);
+        for _ in 0..100 {
+            load.step(open_loop);
+            pi.track(open_loop, reference, load.feedback());
+        }
+
+        // and the controller takes over in the last sample
+        let voltage = pi.update(reference, load.feedback());
+        assert_abs_diff_eq!(Float::from(voltage.d), 2.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(voltage.q), 0.5, epsilon = 1e-4);
+
+        pi.reset();
+        assert_eq!(pi.integral(), Dq::zero());
+    }
+}
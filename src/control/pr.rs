@@ -0,0 +1,220 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::constants::PI;
+use crate::newtypes::Frequency;
+use crate::number::{Float, Num};
+use crate::reference_frames::AlphaBeta;
+use core::marker::PhantomData;
+
+// a resonant term kr * s / (s^2 + w^2), as a biquad (with b1 = 0 and a2 = 1) in transposed direct form II
+#[derive(Debug, Copy, Clone)]
+struct Resonant {
+    order: usize,
+    kr: Float,
+    b0: Float,
+    a1: Float,
+    alpha: [Float; 2],
+    beta: [Float; 2],
+}
+
+impl Resonant {
+    fn tune(&mut self, fundamental: Float, ts: Float) {
+        // the Tustin transform, prewarped so the poles are exactly at the resonant frequency
+        let w = 2.0 * PI * fundamental * (self.order as Float);
+        let k = w / libm::Libm::<Float>::tan(w * ts / 2.0);
+        let a0 = k * k + w * w;
+        self.b0 = self.kr * k / a0;
+        self.a1 = 2.0 * (w * w - k * k) / a0;
+    }
+
+    fn filter(b0: Float, a1: Float, state: &mut [Float; 2], x: Float) -> Float {
+        let y = b0 * x + state[0];
+        state[0] = state[1] - a1 * y;
+        state[1] = -b0 * x - y;
+        y
+    }
+
+    fn update(&mut self, error: AlphaBeta<Float>) -> AlphaBeta<Float> {
+        AlphaBeta {
+            alpha: Self::filter(self.b0, self.a1, &mut self.alpha, error.alpha),
+            beta: Self::filter(self.b0, self.a1, &mut self.beta, error.beta),
+        }
+    }
+}
+
+/// A proportional-resonant controller on an [AlphaBeta] vector, with `H` resonant terms
+///
+/// Each term is `kr * s / (s^2 + (h * w)^2)` for a harmonic order `h`, so has infinite gain for both sequences at its
+/// frequency.  The resonant frequencies must be below the Nyquist frequency.
+#[derive(Debug, Copy, Clone)]
+pub struct AlphaBetaPr<T, const H: usize> {
+    kp: Float,
+    ts: Float,
+    terms: [Resonant; H],
+    output: PhantomData<T>,
+}
+
+impl<T: Num, const H: usize> AlphaBetaPr<T, H> {
+    /// Creates a controller for a proportional gain, and (harmonic order, resonant gain) terms, tuned to a fundamental
+    /// frequency, and updated every `ts` seconds
+    pub fn new(kp: Float, terms: [(usize, Float); H], frequency: Frequency, ts: Float) -> Self {
+        let mut resonant = [Resonant {
+            order: 0,
+            kr: 0.0,
+            b0: 0.0,
+            a1: 0.0,
+            alpha: [0.0; 2],
+            beta: [0.0; 2],
+        }; H];
+        for (term, &(order, kr)) in resonant.iter_mut().zip(terms.iter()) {
+            term.order = order;
+            term.kr = kr;
+        }
+        let mut pr = Self {
+            kp,
+            ts,
+            terms: resonant,
+            output: PhantomData,
+        };
+        pr.set_frequency(frequency);
+        pr
+    }
+
+    /// Retunes the resonant terms to a fundamental frequency (keeping their state)
+    pub fn set_frequency(&mut self, frequency: Frequency) {
+        let fundamental = Float::from(frequency);
+        for term in self.terms.iter_mut() {
+            term.tune(fundamental, self.ts);
+        }
+    }
+
+    /// Calculates the output for a reference and feedback
+    pub fn update<I: Num>(
+        &mut self,
+        reference: AlphaBeta<I>,
+        feedback: AlphaBeta<I>,
+    ) -> AlphaBeta<T> {
+        let error = AlphaBeta {
+            alpha: reference.alpha.into() - feedback.alpha.into(),
+            beta: reference.beta.into() - feedback.beta.into(),
+        };
+        let mut output = AlphaBeta {
+            alpha: self.kp * error.alpha,
+            beta: self.kp * error.beta,
+        };
+        for term in self.terms.iter_mut() {
+            let y = term.update(error);
+            output.alpha += y.alpha;
+            output.beta += y.beta;
+        }
+        AlphaBeta {
+            alpha: output.alpha.into(),
+            beta: output.beta.into(),
+        }
+    }
+
+    /// Clears the state of the resonant terms
+    pub fn reset(&mut self) {
+        for term in self.terms.iter_mut() {
+            term.alpha = [0.0; 2];
+            term.beta = [0.0; 2];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::newtypes::{Current, Voltage};
+    use crate::reference_frames::Abc;
+    use crate::trig::Theta;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+    const L: Float = 1e-3;
+    const R: Float = 0.1;
+
+    #[test]
+    fn prewarping() {
+        // the poles are on the unit circle at the resonant frequency, e^(+-jwT), so a1 = -2cos(wT)
+        let pr: AlphaBetaPr<Voltage, 2> =
+            AlphaBetaPr::new(1.0, [(1, 100.0), (5, 10.0)], 60.0.into(), TS);
+        for term in pr.terms.iter() {
+            let w = 2.0 * PI * 60.0 * (term.order as Float);
+            assert_abs_diff_eq!(
+                term.a1,
+                -2.0 * libm::Libm::<Float>::cos(w * TS),
+                epsilon = 1e-6
+            );
+        }
+    }
+
+    // tracks a fundamental and fifth harmonic (negative sequence) current in an RL load, returning the largest error
+    // over the last cycle
+    fn track(pr: &mut AlphaBetaPr<Voltage, 2>, frequency: Float) -> Float {
+        let mut current: AlphaBeta<Float> = AlphaBeta::zero();
+        let mut theta = Theta::from(0);
+        let step = Theta::from_degrees(360.0 * frequency * TS);
+        let cycle = (1.0 / (frequency * TS)) as usize;
+        let mut largest: Float = 0.0;
+        for i in 0..(50 * cycle) {
+            let fundamental = AlphaBeta::from(Abc::from_polar(10.0, theta));
+            let mut fifth = AlphaBeta::from(Abc::from_polar(
+                1.0,
+                Theta::from(i32::from(theta).wrapping_mul(5)),
+            ));
+            fifth.beta = -fifth.beta;
+            let reference: AlphaBeta<Current> = AlphaBeta {
+                alpha: (fundamental.alpha + fifth.alpha).into(),
+                beta: (fundamental.beta + fifth.beta).into(),
+            };
+            let feedback: AlphaBeta<Current> = AlphaBeta {
+                alpha: current.alpha.into(),
+                beta: current.beta.into(),
+            };
+            if i >= 49 * cycle {
+                let error = reference.alpha - feedback.alpha;
+                largest = largest.max(Float::abs(error.into()));
+            }
+
+            let voltage = pr.update(reference, feedback);
+            current.alpha += TS * (Float::from(voltage.alpha) - R * current.alpha) / L;
+            current.beta += TS * (Float::from(voltage.beta) - R * current.beta) / L;
+            theta += step;
+        }
+        largest
+    }
+
+    #[test]
+    fn harmonic_tracking() {
+        let kp = L * 2.0 * PI * 500.0;
+
+        // proportional control alone leaves an error
+        let mut p: AlphaBetaPr<Voltage, 2> =
+            AlphaBetaPr::new(kp, [(1, 0.0), (5, 0.0)], 60.0.into(), TS);
+        assert!(track(&mut p, 60.0) > 0.5);
+
+        let mut pr: AlphaBetaPr<Voltage, 2> =
+            AlphaBetaPr::new(kp, [(1, 200.0), (5, 200.0)], 60.0.into(), TS);
+        assert!(track(&mut pr, 60.0) < 0.01);
+
+        // retuned to 50Hz
+        pr.reset();
+        pr.set_frequency(50.0.into());
+        assert!(track(&mut pr, 50.0) < 0.01);
+    }
+}
@@ -0,0 +1,364 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Fixed-point number types for processors without a floating point unit
+
+[Q15] and [Q31] represent per-unit values in the range [-1, 1).  Both implement [Num](crate::number::Num), so they
+can be used with all of the reference frames.  Addition and subtraction saturate, and multiplication rounds to
+nearest.
+
+The constants of the Clarke transforms (i.e. 2/3 or sqrt(3)/2) are [Q31] for both types (see
+[Scale]), so the Clarke transforms do not use any floating point.  For the Park transforms, use
+[cos_sin] from this module, which returns the cos and sin of theta as [Q31] directly from the fixed-point
+[idsp] implementation.
+
+```rust
+use ac_power::fixed::{cos_sin, Q31};
+use ac_power::trig::Theta;
+use ac_power::{Abc, AlphaBeta, Dq};
+
+let abc = Abc {
+    a: Q31::from(0.5),
+    b: Q31::from(-0.25),
+    c: Q31::from(-0.25),
+};
+
+// Clarke and Park transforms
+let alpha_beta = AlphaBeta::from(abc);
+let (cos, sin) = cos_sin(Theta::from_degrees(90.0));
+let dq: Dq<Q31> = alpha_beta.to_dq(cos, sin);
+
+// power is per-unit of 3/2 * Vbase * Ibase
+let pq = dq * dq;
+```
+*/
+
+use crate::number::{Float, Scale};
+use crate::trig::{rotate, Cos, Sin, Theta, Trig};
+use core::ops::{Add, AddAssign, Mul, Neg, Sub};
+use derive_more::{From, Into};
+
+/// A fixed-point number with 15 fractional bits (wraps i16)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, From, Into)]
+pub struct Q15(i16);
+
+/// A fixed-point number with 31 fractional bits (wraps i32)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default, From, Into)]
+pub struct Q31(i32);
+
+// multiplies two fixed-point numbers, rounding to nearest and saturating to the range of the left hand side
+macro_rules! impl_mul {
+    ($t:ident, $base:ty, $rhs:ident, $frac:expr) => {
+        impl Mul<$rhs> for $t {
+            #[inline]
+            fn mul(self, rhs: $rhs) -> $t {
+                let product = ((self.0 as i64) * (rhs.0 as i64) + (1 << ($frac - 1))) >> $frac;
+                $t(product.clamp(<$base>::MIN as i64, <$base>::MAX as i64) as $base)
+            }
+            type Output = $t;
+        }
+    };
+}
+
+macro_rules! impl_fixed {
+    ($t:ident, $base:ty, $frac:expr) => {
+        impl $t {
+            pub const MIN: $t = $t(<$base>::MIN);
+            pub const MAX: $t = $t(<$base>::MAX);
+            pub const ZERO: $t = $t(0);
+        }
+
+        impl From<Float> for $t {
+            #[inline]
+            fn from(x: Float) -> Self {
+                // round to nearest, the cast saturates values outside of [-1, 1)
+                let scaled = x * ((1u64 << $frac) as Float);
+                let rounded = if scaled >= 0.0 {
+                    scaled + 0.5
+                } else {
+                    scaled - 0.5
+                };
+                $t(rounded as $base)
+            }
+        }
+
+        impl From<$t> for Float {
+            #[inline]
+            fn from(x: $t) -> Float {
+                (x.0 as Float) / ((1u64 << $frac) as Float)
+            }
+        }
+
+        impl Add for $t {
+            #[inline]
+            fn add(self, rhs: $t) -> $t {
+                $t(self.0.saturating_add(rhs.0))
+            }
+            type Output = $t;
+        }
+
+        impl AddAssign for $t {
+            #[inline]
+            fn add_assign(&mut self, rhs: $t) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl Sub for $t {
+            #[inline]
+            fn sub(self, rhs: $t) -> $t {
+                $t(self.0.saturating_sub(rhs.0))
+            }
+            type Output = $t;
+        }
+
+        impl Neg for $t {
+            #[inline]
+            fn neg(self) -> $t {
+                $t(self.0.saturating_neg())
+            }
+            type Output = $t;
+        }
+
+        impl_mul!($t, $base, Q15, 15);
+        impl_mul!($t, $base, Q31, 31);
+
+        impl Scale for $t {
+            type Factor = Q31;
+
+            const ONE_THIRD: Q31 = Q31(715827883);
+            const TWO_THIRDS: Q31 = Q31(1431655765);
+            const SQRT_3_OVER_3: Q31 = Q31(1239850262);
+            const ONE_HALF: Q31 = ONE_HALF;
+            const SQRT_3_OVER_2: Q31 = SQRT_3_OVER_2;
+
+            #[inline]
+            fn scale(self, factor: Q31) -> $t {
+                self * factor
+            }
+        }
+
+        impl Mul<Float> for $t {
+            #[inline]
+            fn mul(self, rhs: Float) -> $t {
+                if (-1.0..1.0).contains(&rhs) {
+                    // keeps the precision of the fixed-point type for factors in its range
+                    self * Q31::from(rhs)
+                } else {
+                    $t::from(Float::from(self) * rhs)
+                }
+            }
+            type Output = $t;
+        }
+
+        impl Mul<Cos> for $t {
+            fn mul(self, rhs: Cos) -> $t {
+                self * Float::from(rhs)
+            }
+            type Output = $t;
+        }
+
+        impl Mul<Sin> for $t {
+            fn mul(self, rhs: Sin) -> $t {
+                self * Float::from(rhs)
+            }
+            type Output = $t;
+        }
+    };
+}
+
+impl_fixed!(Q15, i16, 15);
+impl_fixed!(Q31, i32, 31);
+
+const ONE_HALF: Q31 = Q31(1 << 30);
+const SQRT_3_OVER_2: Q31 = Q31(1859775393);
+
+impl Trig for Q31 {
+    type Sin = Q31;
+
+    fn shift_left_120(self, sin: Q31) -> (Q31, Q31) {
+        rotate(self, sin, -ONE_HALF, -SQRT_3_OVER_2)
+    }
+
+    fn shift_right_120(self, sin: Q31) -> (Q31, Q31) {
+        rotate(self, sin, -ONE_HALF, SQRT_3_OVER_2)
+    }
+}
+
+/// Calculates cos and sin of theta in fixed-point
+///
+/// # Examples
+///
+/// ```
+/// use ac_power::fixed::cos_sin;
+/// use ac_power::number::Float;
+/// use ac_power::trig::Theta;
+/// use approx::assert_abs_diff_eq;
+///
+/// let (cos, sin) = cos_sin(Theta::from_degrees(30.0));
+/// assert_abs_diff_eq!(Float::from(cos), 0.866, epsilon = 0.001);
+/// assert_abs_diff_eq!(Float::from(sin), 0.5, epsilon = 0.001);
+/// ```
+pub fn cos_sin(theta: Theta) -> (Q31, Q31) {
+    let (cos, sin) = idsp::cossin(theta.into());
+    (Q31(cos), Q31(sin))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::number::Num;
+    use crate::reference_frames::{Abc, AlphaBeta, Dq};
+    use crate::trig::{Backend, Idsp};
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn saturation() {
+        assert_eq!(Q31::MAX + Q31::from(0.5), Q31::MAX);
+        assert_eq!(Q31::MIN - Q31::from(0.5), Q31::MIN);
+        assert_eq!(-Q31::MIN, Q31::MAX);
+        assert_eq!(Q15::from(1.5), Q15::MAX);
+        assert_eq!(Q15::from(-1.5), Q15::MIN);
+        assert_eq!(Q31::MIN * Q31::MIN, Q31::MAX);
+        assert_eq!(Q15::from(0.75) * 2.0, Q15::MAX);
+    }
+
+    #[test]
+    fn rounding() {
+        // 3 * 0.5 lsb rounds up to 2 lsb
+        assert_eq!(Q15(3) * Q15(1 << 14), Q15(2));
+        assert_eq!(Q15::from(0.25) * Q15::from(0.5), Q15::from(0.125));
+        assert_eq!(Q31::from(0.25) * Q31::from(0.5), Q31::from(0.125));
+        assert_eq!(Q15::from(1.6 / 32768.0), Q15(2));
+        assert_eq!(Q15::from(-1.6 / 32768.0), Q15(-2));
+        assert_abs_diff_eq!(Float::from(Q15::from(0.3) * 0.5), 0.15, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn transform_constants() {
+        // the nearest Q31 values, calculated in f64 regardless of the precision of Float
+        let q31 = |x: f64| Q31((x * (1u64 << 31) as f64).round() as i32);
+        let sqrt_3 = libm::Libm::<f64>::sqrt(3.0);
+        assert_eq!(<Q31 as Scale>::ONE_THIRD, q31(1.0 / 3.0));
+        assert_eq!(<Q31 as Scale>::TWO_THIRDS, q31(2.0 / 3.0));
+        assert_eq!(<Q15 as Scale>::SQRT_3_OVER_3, q31(sqrt_3 / 3.0));
+        assert_eq!(<Q15 as Scale>::ONE_HALF, q31(0.5));
+        assert_eq!(<Q15 as Scale>::SQRT_3_OVER_2, q31(sqrt_3 / 2.0));
+    }
+
+    #[test]
+    fn fixed_cos_sin() {
+        for degrees in (-180..180).step_by(5) {
+            let theta = Theta::from_degrees(degrees as Float);
+            let (cos, sin) = cos_sin(theta);
+            let (cos_ref, sin_ref) = Idsp::cos_sin(theta);
+            assert_abs_diff_eq!(Float::from(cos), Float::from(cos_ref), epsilon = 0.00001);
+            assert_abs_diff_eq!(Float::from(sin), Float::from(sin_ref), epsilon = 0.00001);
+
+            let (cos_m, sin_m) = cos.shift_left_120(sin);
+            let (cos_m_ref, sin_m_ref) = cos_ref.shift_left_120(sin_ref);
+            assert_abs_diff_eq!(
+                Float::from(cos_m),
+                Float::from(cos_m_ref),
+                epsilon = 0.00001
+            );
+            assert_abs_diff_eq!(
+                Float::from(sin_m),
+                Float::from(sin_m_ref),
+                epsilon = 0.00001
+            );
+
+            let (cos_p, sin_p) = cos.shift_right_120(sin);
+            let (cos_p_ref, sin_p_ref) = cos_ref.shift_right_120(sin_ref);
+            assert_abs_diff_eq!(
+                Float::from(cos_p),
+                Float::from(cos_p_ref),
+                epsilon = 0.00001
+            );
+            assert_abs_diff_eq!(
+                Float::from(sin_p),
+                Float::from(sin_p_ref),
+                epsilon = 0.00001
+            );
+        }
+    }
+
+    // runs the Clarke/Park/power pipeline in fixed-point and compares it with the float path (using the same idsp trig)
+    fn check_pipeline<T: Num + Num<Q31>>(epsilon: Float)
+    where
+        Dq<T>: Mul<Dq<T>, Output = crate::Pq<T>>,
+        Float: From<T>,
+    {
+        let v_theta = Theta::from_degrees(20.0);
+        for degrees in (-180..180).step_by(10) {
+            let i_theta = Theta::from_degrees(degrees as Float);
+            let v_ref: Abc<Float> = Abc::from_polar(0.9, v_theta);
+            let i_ref: Abc<Float> = Abc::from_polar(0.6, i_theta) + 0.05;
+            let (cos_ref, sin_ref) = Idsp::cos_sin(v_theta);
+            let v_dq_ref = AlphaBeta::from(v_ref).to_dq(cos_ref, sin_ref);
+            let i_dq_ref = AlphaBeta::from(i_ref).to_dq(cos_ref, sin_ref);
+
+            let fixed = |abc: Abc<Float>| Abc {
+                a: T::from(abc.a),
+                b: T::from(abc.b),
+                c: T::from(abc.c),
+            };
+            let (cos, sin) = cos_sin(v_theta);
+            let v_dq = AlphaBeta::from(fixed(v_ref)).to_dq(cos, sin);
+            let i_dq = fixed(i_ref).to_dq(cos, sin);
+            assert_abs_diff_eq!(Float::from(v_dq.d), v_dq_ref.d, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(v_dq.q), v_dq_ref.q, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(i_dq.d), i_dq_ref.d, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(i_dq.q), i_dq_ref.q, epsilon = epsilon);
+
+            // 0.9 pu straight from abc, without going through the Clarke transform
+            let v_abc_dq = fixed(v_ref).to_dq(cos, sin);
+            assert_abs_diff_eq!(Float::from(v_abc_dq.d), v_dq_ref.d, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(v_abc_dq.q), v_dq_ref.q, epsilon = epsilon);
+            let v_dq0 = fixed(v_ref).to_dq0(cos, sin);
+            assert_abs_diff_eq!(Float::from(v_dq0.d), v_dq_ref.d, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(v_dq0.q), v_dq_ref.q, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(v_dq0.zero), 0.0, epsilon = epsilon);
+            let i_dq0 = fixed(i_ref).to_dq0(cos, sin);
+            assert_abs_diff_eq!(Float::from(i_dq0.d), i_dq_ref.d, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(i_dq0.q), i_dq_ref.q, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(i_dq0.zero), 0.05, epsilon = epsilon);
+
+            let pq = v_dq * i_dq;
+            let p = v_dq_ref.d * i_dq_ref.d + v_dq_ref.q * i_dq_ref.q;
+            let q = v_dq_ref.q * i_dq_ref.d - v_dq_ref.d * i_dq_ref.q;
+            assert_abs_diff_eq!(Float::from(pq.p), p, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(pq.q), q, epsilon = epsilon);
+
+            let abc = v_dq.to_abc(cos, sin);
+            let abc_ref = v_dq_ref.to_abc(cos_ref, sin_ref);
+            assert_abs_diff_eq!(Float::from(abc.a), abc_ref.a, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(abc.b), abc_ref.b, epsilon = epsilon);
+            assert_abs_diff_eq!(Float::from(abc.c), abc_ref.c, epsilon = epsilon);
+        }
+    }
+
+    #[test]
+    fn q31_pipeline() {
+        check_pipeline::<Q31>(0.00002);
+    }
+
+    #[test]
+    fn q15_pipeline() {
+        check_pipeline::<Q15>(0.0005);
+    }
+}
@@ -0,0 +1,476 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Frequency and rate of change of frequency (ROCOF) estimators
+
+These are independent of any control loop PLL, for protection functions which need an estimate with a known latency.
+Each averages over a window of `N` measurements, and calculates ROCOF from the change in frequency over the window.
+
+1. [ZeroCrossing] - Interpolates the rising zero crossings of each phase, and averages the last `N` periods
+2. [AngleDifference] - Differentiates the angle of an [AlphaBeta] vector, averaged over `N` samples
+3. [PhasorRotation] - Demodulates an [AlphaBeta] vector by the nominal frequency, and measures the rotation of the
+   phasor (averaged over `N` samples) across the window
+
+A window of a whole number of cycles rejects negative sequence and harmonics in the angle based estimators.
+
+```rust
+use ac_power::frequency::AngleDifference;
+use ac_power::number::Float;
+use ac_power::trig::Theta;
+use ac_power::{Abc, AlphaBeta};
+use approx::assert_abs_diff_eq;
+
+// one cycle of 50Hz at 10kHz
+let mut estimator: AngleDifference<200> = AngleDifference::new(50.0, 1e-4);
+
+let step = Theta::from_degrees(360.0 * 50.2 * 1e-4);
+let mut theta = Theta::from(0);
+for _ in 0..1000 {
+    estimator.update(AlphaBeta::from(Abc::from_polar(1.0, theta)));
+    theta += step;
+}
+
+assert_abs_diff_eq!(Float::from(estimator.frequency()), 50.2, epsilon = 0.001);
+assert_abs_diff_eq!(Float::from(estimator.rocof()), 0.0, epsilon = 0.01);
+```
+*/
+
+use crate::constants::PI;
+use crate::newtypes::{Frequency, Rocof};
+use crate::number::{Float, Num};
+use crate::reference_frames::{Abc, AlphaBeta, Dq};
+use crate::trig::{atan2, cos_sin, Theta};
+
+// resolution of Theta
+const FULL_CIRCLE: Float = 4294967296.0;
+
+// a point in time, as a sample index and a fraction of a sample
+#[derive(Debug, Copy, Clone, Default)]
+struct Instant {
+    sample: u64,
+    fraction: Float,
+}
+
+impl Instant {
+    // samples since an earlier instant
+    fn since(self, earlier: Instant) -> Float {
+        (self.sample - earlier.sample) as Float + self.fraction - earlier.fraction
+    }
+}
+
+/// Estimates frequency from the interpolated zero crossings of each phase
+///
+/// Each phase measures a period at every rising zero crossing, so `N` periods span `N/3` cycles.  The frequency is the
+/// inverse of the mean period, referenced to the middle of the periods, and the ROCOF is the change from the estimate
+/// `N` periods earlier.  Crossings less than half a nominal cycle after the previous one are ignored, to reject noise.
+pub struct ZeroCrossing<const N: usize> {
+    ts: Float,
+    min_period: Float,
+    sample: u64,
+    previous: Abc<Float>,
+    crossings: [Option<Instant>; 3],
+    periods: [(Float, Instant); N],
+    history: [(Float, Instant); N],
+    index: usize,
+    count: usize,
+    frequency: Float,
+    rocof: Float,
+}
+
+impl<const N: usize> ZeroCrossing<N> {
+    /// Creates an estimator for a nominal frequency (in Hz) and sample period (in seconds)
+    pub fn new(nominal: Float, ts: Float) -> Self {
+        Self {
+            ts,
+            min_period: 0.5 / (nominal * ts),
+            sample: 0,
+            previous: Abc::zero(),
+            crossings: [None; 3],
+            periods: [(0.0, Instant::default()); N],
+            history: [(0.0, Instant::default()); N],
+            index: 0,
+            count: 0,
+            frequency: nominal,
+            rocof: 0.0,
+        }
+    }
+
+    /// Adds a sample
+    pub fn update<T: Num>(&mut self, abc: Abc<T>) {
+        let abc: Abc<Float> = Abc {
+            a: abc.a.into(),
+            b: abc.b.into(),
+            c: abc.c.into(),
+        };
+        let phases = [
+            (self.previous.a, abc.a),
+            (self.previous.b, abc.b),
+            (self.previous.c, abc.c),
+        ];
+        for (phase, &(previous, value)) in phases.iter().enumerate() {
+            if self.sample > 0 && previous < 0.0 && value >= 0.0 {
+                // interpolate between the samples either side of the crossing
+                let crossing = Instant {
+                    sample: self.sample - 1,
+                    fraction: previous / (previous - value),
+                };
+                match self.crossings[phase] {
+                    Some(last) => {
+                        let period = crossing.since(last);
+                        if period >= self.min_period {
+                            self.crossings[phase] = Some(crossing);
+                            self.add_period(period, crossing);
+                        }
+                    }
+                    None => self.crossings[phase] = Some(crossing),
+                }
+            }
+        }
+        self.previous = abc;
+        self.sample += 1;
+    }
+
+    fn add_period(&mut self, period: Float, end: Instant) {
+        self.periods[self.index] = (period, end);
+        self.count += 1;
+        if self.count >= N {
+            // mean period, and the mean middle of the periods relative to the latest crossing
+            let mut total = 0.0;
+            let mut offset = 0.0;
+            for (period, instant) in self.periods.iter() {
+                total += period;
+                offset -= end.since(*instant) + 0.5 * period;
+            }
+            let frequency = (N as Float) / (total * self.ts);
+            let centre = Instant {
+                sample: end.sample,
+                fraction: end.fraction + offset / (N as Float),
+            };
+
+            if self.count >= 2 * N {
+                let (previous, then) = self.history[self.index];
+                self.rocof = (frequency - previous) / (centre.since(then) * self.ts);
+            }
+            self.history[self.index] = (frequency, centre);
+            self.frequency = frequency;
+        }
+        self.index = (self.index + 1) % N;
+    }
+
+    pub fn frequency(&self) -> Frequency {
+        self.frequency.into()
+    }
+
+    pub fn rocof(&self) -> Rocof {
+        self.rocof.into()
+    }
+}
+
+/// Estimates frequency by differentiating the angle of an [AlphaBeta] vector
+///
+/// The angle steps of the last `N` samples are averaged, so the frequency has a latency of `N/2` samples, and the
+/// ROCOF (from the change in frequency over the window) has a latency of `N` samples.
+pub struct AngleDifference<const N: usize> {
+    ts: Float,
+    previous: Option<Theta>,
+    steps: [i32; N],
+    sum: i64,
+    frequencies: [Float; N],
+    index: usize,
+    count: usize,
+    frequency: Float,
+    rocof: Float,
+}
+
+impl<const N: usize> AngleDifference<N> {
+    /// Creates an estimator for a nominal frequency (in Hz) and sample period (in seconds)
+    pub fn new(nominal: Float, ts: Float) -> Self {
+        Self {
+            ts,
+            previous: None,
+            steps: [0; N],
+            sum: 0,
+            frequencies: [0.0; N],
+            index: 0,
+            count: 0,
+            frequency: nominal,
+            rocof: 0.0,
+        }
+    }
+
+    /// Adds a sample
+    pub fn update<T: Num>(&mut self, alpha_beta: AlphaBeta<T>) {
+        let theta = atan2(alpha_beta.beta.into(), alpha_beta.alpha.into());
+        let previous = self.previous.replace(theta);
+        let Some(previous) = previous else {
+            return;
+        };
+
+        // the wrapping difference is the angle step, even across the +/-180 degree boundary
+        let step = i32::from(theta - previous);
+        self.sum += step as i64 - self.steps[self.index] as i64;
+        self.steps[self.index] = step;
+        self.count += 1;
+
+        if self.count >= N {
+            let frequency = (self.sum as Float) / (FULL_CIRCLE * (N as Float) * self.ts);
+            if self.count >= 2 * N {
+                self.rocof = (frequency - self.frequencies[self.index]) / ((N as Float) * self.ts);
+            }
+            self.frequencies[self.index] = frequency;
+            self.frequency = frequency;
+        }
+        self.index = (self.index + 1) % N;
+    }
+
+    pub fn frequency(&self) -> Frequency {
+        self.frequency.into()
+    }
+
+    pub fn rocof(&self) -> Rocof {
+        self.rocof.into()
+    }
+}
+
+/// Estimates frequency from the rotation of an [AlphaBeta] phasor, demodulated by the nominal frequency
+///
+/// The demodulated phasor is averaged over the last `N` samples, and the frequency deviation is its rotation over `N`
+/// samples, so the frequency has a latency of `N` samples, and the ROCOF has a latency of `3N/2` samples.  The
+/// deviation from nominal must be less than half of `1/(N*ts)`.
+pub struct PhasorRotation<const N: usize> {
+    ts: Float,
+    nominal: Float,
+    step: Theta,
+    theta: Theta,
+    samples: [Dq<Float>; N],
+    sum: Dq<Float>,
+    angles: [Theta; N],
+    deviations: [Float; N],
+    index: usize,
+    count: usize,
+    frequency: Float,
+    rocof: Float,
+}
+
+impl<const N: usize> PhasorRotation<N> {
+    /// Creates an estimator for a nominal frequency (in Hz) and sample period (in seconds)
+    pub fn new(nominal: Float, ts: Float) -> Self {
+        // round the demodulation step, and use the frequency it actually represents as the nominal
+        #[allow(clippy::unnecessary_cast)]
+        let step =
+            libm::Libm::<f64>::round((nominal as f64) * (ts as f64) * 4294967296.0) as i64 as i32;
+        Self {
+            ts,
+            nominal: (step as Float) / (FULL_CIRCLE * ts),
+            step: Theta::from(step),
+            theta: Theta::from(0),
+            samples: [Dq::zero(); N],
+            sum: Dq::zero(),
+            angles: [Theta::from(0); N],
+            deviations: [0.0; N],
+            index: 0,
+            count: 0,
+            frequency: nominal,
+            rocof: 0.0,
+        }
+    }
+
+    /// Adds a sample
+    pub fn update<T: Num>(&mut self, alpha_beta: AlphaBeta<T>) {
+        let (alpha, beta): (Float, Float) = (alpha_beta.alpha.into(), alpha_beta.beta.into());
+
+        // rotate the vector back by the nominal angle (the real part in d, the imaginary part in q)
+        let (cos, sin) = cos_sin(self.theta);
+        let (cos, sin) = (Float::from(cos), Float::from(sin));
+        let phasor = Dq {
+            d: alpha * cos + beta * sin,
+            q: beta * cos - alpha * sin,
+        };
+        self.theta += self.step;
+
+        self.sum = self.sum + phasor - self.samples[self.index];
+        self.samples[self.index] = phasor;
+        self.count += 1;
+
+        if self.count >= N {
+            let angle = atan2(self.sum.q, self.sum.d);
+            if self.count >= 2 * N {
+                let rotation = (angle - self.angles[self.index]).to_radians();
+                let deviation = rotation / (2.0 * PI * (N as Float) * self.ts);
+                if self.count >= 3 * N {
+                    self.rocof =
+                        (deviation - self.deviations[self.index]) / ((N as Float) * self.ts);
+                }
+                self.deviations[self.index] = deviation;
+                self.frequency = self.nominal + deviation;
+            }
+            self.angles[self.index] = angle;
+        }
+        self.index = (self.index + 1) % N;
+    }
+
+    pub fn frequency(&self) -> Frequency {
+        self.frequency.into()
+    }
+
+    pub fn rocof(&self) -> Rocof {
+        self.rocof.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::wavegen::{Event, EventKind, Magnitude, Scenario, Sequence, Waveform};
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+
+    fn waveform(negative: Float) -> Waveform<Float, 1> {
+        Waveform::new()
+            .with_harmonic(
+                1,
+                Sequence::Positive,
+                Magnitude::Absolute(1.0),
+                Theta::from(0),
+            )
+            .with_harmonic(
+                1,
+                Sequence::Negative,
+                Magnitude::Percent(negative),
+                Theta::from(0),
+            )
+    }
+
+    const N_ZC: usize = 6;
+
+    struct Estimators {
+        zero_crossing: ZeroCrossing<N_ZC>,
+        angle_difference: AngleDifference<200>,
+        phasor_rotation: PhasorRotation<200>,
+    }
+
+    impl Estimators {
+        fn new() -> Self {
+            Self {
+                zero_crossing: ZeroCrossing::new(50.0, TS),
+                angle_difference: AngleDifference::new(50.0, TS),
+                phasor_rotation: PhasorRotation::new(50.0, TS),
+            }
+        }
+
+        fn update(&mut self, abc: Abc<Float>) {
+            self.zero_crossing.update(abc);
+            self.angle_difference.update(AlphaBeta::from(abc));
+            self.phasor_rotation.update(AlphaBeta::from(abc));
+        }
+
+        fn check(&self, frequency: [Float; 3], rocof: Float, epsilon: (Float, Float)) {
+            let estimates = [
+                (self.zero_crossing.frequency(), self.zero_crossing.rocof()),
+                (
+                    self.angle_difference.frequency(),
+                    self.angle_difference.rocof(),
+                ),
+                (
+                    self.phasor_rotation.frequency(),
+                    self.phasor_rotation.rocof(),
+                ),
+            ];
+            for (&(f, r), &expected) in estimates.iter().zip(frequency.iter()) {
+                assert_abs_diff_eq!(Float::from(f), expected, epsilon = epsilon.0);
+                assert_abs_diff_eq!(Float::from(r), rocof, epsilon = epsilon.1);
+            }
+        }
+    }
+
+    #[test]
+    fn initial() {
+        // the estimates are nominal until the windows fill
+        let mut estimators = Estimators::new();
+        for abc in Scenario::new(waveform(0.0), 50.0, TS, &[]).take(100) {
+            estimators.update(abc);
+        }
+        estimators.check([50.0; 3], 0.0, (1e-4, 1e-4));
+    }
+
+    #[test]
+    fn steady_state() {
+        for deviation in [-2.0, 0.0, 0.3, 5.0] {
+            let events = [Event::new(0.0, None, EventKind::FrequencyStep(deviation))];
+            let mut estimators = Estimators::new();
+            for abc in Scenario::new(waveform(0.0), 50.0, TS, &events).take(5000) {
+                estimators.update(abc);
+            }
+            let frequency = 50.0 + deviation;
+            estimators.check([frequency; 3], 0.0, (0.001, 0.02));
+        }
+    }
+
+    #[test]
+    fn unbalanced() {
+        let mut estimators = Estimators::new();
+        for abc in Scenario::new(waveform(5.0), 50.0, TS, &[]).take(5000) {
+            estimators.update(abc);
+        }
+        estimators.check([50.0; 3], 0.0, (0.001, 0.02));
+    }
+
+    #[test]
+    fn ramp() {
+        let events = [Event::new(0.1, None, EventKind::FrequencyRamp(2.0))];
+        let mut estimators = Estimators::new();
+        for abc in Scenario::new(waveform(0.0), 50.0, TS, &events).take(10000) {
+            estimators.update(abc);
+        }
+
+        // each estimate lags the frequency at the end of the last sample by its latency
+        let frequency = |latency: Float| 50.0 + 2.0 * (0.9999 - 0.1 - latency);
+        let zero_crossing = {
+            let estimator = &estimators.zero_crossing;
+            let (_, centre) = estimator.history[(estimator.index + N_ZC - 1) % N_ZC];
+            (9999.0 - centre.sample as Float - centre.fraction) * TS
+        };
+        estimators.check(
+            [
+                frequency(zero_crossing),
+                frequency(100.0 * TS),
+                frequency(200.0 * TS),
+            ],
+            2.0,
+            (0.002, 0.05),
+        );
+    }
+
+    #[test]
+    fn zero_crossing_noise() {
+        // a glitch just after each rising crossing of phase a makes a second crossing, which is rejected
+        let mut estimator: ZeroCrossing<3> = ZeroCrossing::new(50.0, TS);
+        for (n, abc) in Scenario::new(waveform(0.0), 50.0, TS, &[])
+            .take(2000)
+            .enumerate()
+        {
+            let abc = if n >= 200 && n % 200 == 3 {
+                Abc { a: -abc.a, ..abc }
+            } else {
+                abc
+            };
+            estimator.update(abc);
+        }
+        assert_abs_diff_eq!(Float::from(estimator.frequency()), 50.0, epsilon = 0.001);
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use super::Reference;
+use crate::constants::PI;
+use crate::newtypes::{Frequency, Power, Voltage};
+use crate::number::Float;
+use crate::pq::Pq;
+use crate::trig::PhaseAccumulator;
+
+/// P-f and Q-V droop control
+///
+/// `f = f0 + Δf - mp (P - P0)` and `V = V0 + ΔV - mq (Q - Q0)`, where P and Q are the low-pass filtered measurements,
+/// P0 and Q0 the setpoints, and Δf and ΔV the secondary restoration.  The droop gains are set in per-unit: the
+/// fraction of the nominal frequency (or voltage) for the rated power.
+#[derive(Debug, Copy, Clone)]
+pub struct Droop {
+    rating: Float,
+    nominal_frequency: Float,
+    nominal_voltage: Float,
+    ts: Float,
+    mp: Float,
+    mq: Float,
+    alpha: Float,
+    setpoint: Pq<Float>,
+    restoration: (Float, Float),
+    filtered: Option<Pq<Float>>,
+    phase: PhaseAccumulator,
+}
+
+impl Droop {
+    /// Creates a droop controller for a rated apparent power, nominal frequency and voltage, updated every `ts` seconds
+    ///
+    /// The droop defaults to 1% for frequency and 5% for voltage, and the power is filtered with a 5Hz cutoff.
+    pub fn new(
+        rating: Power,
+        nominal_frequency: Frequency,
+        nominal_voltage: Voltage,
+        ts: Float,
+    ) -> Self {
+        let mut droop = Self {
+            rating: rating.into(),
+            nominal_frequency: nominal_frequency.into(),
+            nominal_voltage: nominal_voltage.into(),
+            ts,
+            mp: 0.0,
+            mq: 0.0,
+            alpha: 0.0,
+            setpoint: Pq { p: 0.0, q: 0.0 },
+            restoration: (0.0, 0.0),
+            filtered: None,
+            phase: PhaseAccumulator::new(ts),
+        };
+        droop.set_droop(0.01, 0.05);
+        droop.set_filter(Frequency::from(5.0));
+        droop
+    }
+
+    /// Sets the per-unit frequency and voltage droop
+    pub fn with_droop(mut self, frequency: Float, voltage: Float) -> Self {
+        self.set_droop(frequency, voltage);
+        self
+    }
+
+    /// Sets the cutoff frequency of the power measurement filter
+    pub fn with_filter(mut self, cutoff: Frequency) -> Self {
+        self.set_filter(cutoff);
+        self
+    }
+
+    pub fn set_droop(&mut self, frequency: Float, voltage: Float) {
+        self.mp = frequency * self.nominal_frequency / self.rating;
+        self.mq = voltage * self.nominal_voltage / self.rating;
+    }
+
+    pub fn set_filter(&mut self, cutoff: Frequency) {
+        let w = 2.0 * PI * Float::from(cutoff);
+        self.alpha = 1.0 - libm::Libm::<Float>::exp(-w * self.ts);
+    }
+
+    /// Sets the active and reactive power delivered at the nominal (plus restoration) frequency and voltage
+    pub fn set_setpoint(&mut self, setpoint: Pq) {
+        self.setpoint = Pq {
+            p: setpoint.p.into(),
+            q: setpoint.q.into(),
+        };
+    }
+
+    /// Sets the secondary restoration offsets of the frequency and voltage
+    pub fn set_restoration(&mut self, frequency: Frequency, voltage: Voltage) {
+        self.restoration = (frequency.into(), voltage.into());
+    }
+
+    /// Calculates the voltage reference for this sample from the measured power
+    pub fn update(&mut self, measured: Pq) -> Reference {
+        let (p, q): (Float, Float) = (measured.p.into(), measured.q.into());
+        let filtered = match self.filtered {
+            Some(filtered) => Pq {
+                p: filtered.p + (p - filtered.p) * self.alpha,
+                q: filtered.q + (q - filtered.q) * self.alpha,
+            },
+            None => Pq { p, q },
+        };
+        self.filtered = Some(filtered);
+
+        let frequency = Frequency::from(
+            self.nominal_frequency + self.restoration.0 - self.mp * (filtered.p - self.setpoint.p),
+        );
+        let voltage = Voltage::from(
+            self.nominal_voltage + self.restoration.1 - self.mq * (filtered.q - self.setpoint.q),
+        );
+        Reference {
+            theta: self.phase.update(frequency),
+            frequency,
+            voltage,
+        }
+    }
+
+    /// The filtered power measurement
+    pub fn power(&self) -> Pq {
+        let filtered = self.filtered.unwrap_or(self.setpoint);
+        Pq {
+            p: filtered.p.into(),
+            q: filtered.q.into(),
+        }
+    }
+
+    pub fn phase(&self) -> &PhaseAccumulator {
+        &self.phase
+    }
+
+    pub fn phase_mut(&mut self) -> &mut PhaseAccumulator {
+        &mut self.phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::power;
+    use crate::trig::Theta;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+
+    fn droop() -> Droop {
+        Droop::new(
+            Power::from(10e3),
+            Frequency::from(60.0),
+            Voltage::from(240.0),
+            TS,
+        )
+    }
+
+    fn run(droop: &mut Droop, measured: Pq, samples: usize) -> Reference {
+        (0..samples).map(|_| droop.update(measured)).last().unwrap()
+    }
+
+    #[test]
+    fn characteristics() {
+        let mut droop = droop().with_droop(0.02, 0.04);
+        let reference = run(&mut droop, power(2.5e3, -5e3), 10000);
+        assert_abs_diff_eq!(Float::from(reference.frequency), 60.0 - 0.3, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(reference.voltage), 240.0 + 4.8, epsilon = 1e-3);
+
+        // at the setpoints, plus the restoration
+        droop.set_setpoint(power(2.5e3, -5e3));
+        droop.set_restoration(Frequency::from(0.1), Voltage::from(-2.0));
+        let reference = run(&mut droop, power(2.5e3, -5e3), 10);
+        assert_abs_diff_eq!(Float::from(reference.frequency), 60.1, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(reference.voltage), 238.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn filter() {
+        let mut droop = droop().with_filter(Frequency::from(10.0));
+        run(&mut droop, power(0.0, 0.0), 10);
+
+        // 63% of a step in a time constant
+        let samples = (1.0 / (2.0 * PI * 10.0 * TS) + 0.5) as usize;
+        let reference = run(&mut droop, power(10e3, 0.0), samples);
+        assert_abs_diff_eq!(Float::from(droop.power().p), 6321.0, epsilon = 5.0);
+        assert_abs_diff_eq!(
+            Float::from(reference.frequency),
+            60.0 - 0.6 * 0.6321,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn phase() {
+        let mut droop = droop();
+
+        // the angle starts at zero, and advances by f * ts each sample
+        let reference = droop.update(power(0.0, 0.0));
+        assert_eq!(reference.theta, Theta::from(0));
+        let reference = run(&mut droop, power(0.0, 0.0), 40);
+        assert_abs_diff_eq!(
+            reference.theta.to_degrees(),
+            40.0 * 360.0 * 60.0 * TS,
+            epsilon = 1e-3
+        );
+
+        // and at 59.4Hz
+        run(&mut droop, power(10e3, 0.0), 10000);
+        droop.phase_mut().set(Theta::from(0));
+        let reference = run(&mut droop, power(10e3, 0.0), 41);
+        assert_abs_diff_eq!(Float::from(reference.frequency), 59.4, epsilon = 1e-4);
+        assert_abs_diff_eq!(
+            reference.theta.to_degrees(),
+            40.0 * 360.0 * 59.4 * TS,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn load_sharing() {
+        // 10kW and 20kW inverters with the same per-unit droop, through equal inductive lines to a 15kW load
+        let mut droops = [
+            droop(),
+            Droop::new(
+                Power::from(20e3),
+                Frequency::from(60.0),
+                Voltage::from(240.0),
+                TS,
+            ),
+        ];
+        // power flow per radian of angle difference, V^2 / X
+        let k = 240.0 * 240.0 / 0.5;
+        let load = 15e3;
+
+        let mut power = [0.0; 2];
+        let mut frequency = [0.0; 2];
+        for _ in 0..50000 {
+            let references = [
+                droops[0].update(self::power(power[0], 0.0)),
+                droops[1].update(self::power(power[1], 0.0)),
+            ];
+            // the angle of the load bus balances the load, relative to inverter 0 to avoid wrapping
+            let difference = (references[1].theta - references[0].theta).to_radians();
+            let bus = (k * difference - load) / (2.0 * k);
+            power = [k * (0.0 - bus), k * (difference - bus)];
+            frequency = [
+                references[0].frequency.into(),
+                references[1].frequency.into(),
+            ];
+        }
+
+        assert_abs_diff_eq!(frequency[0], frequency[1], epsilon = 1e-4);
+        assert_abs_diff_eq!(frequency[0], 60.0 - 0.5 * 0.6, epsilon = 1e-3);
+        assert_abs_diff_eq!(power[0], 5e3, epsilon = 1.0);
+        assert_abs_diff_eq!(power[1], 10e3, epsilon = 1.0);
+    }
+}
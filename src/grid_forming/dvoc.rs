@@ -0,0 +1,350 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::constants::PI;
+use crate::newtypes::{Current, Frequency, Power, Voltage};
+use crate::number::Float;
+use crate::pq::Pq;
+use crate::reference_frames::{Abc, AlphaBeta};
+use crate::trig::{cos_sin, Theta};
+
+/// Dispatchable virtual oscillator control
+///
+/// The voltage reference is the state of an oscillator in the [AlphaBeta] frame, in per-unit of the nominal voltage
+/// and rated power:
+///
+/// `dv/dt = ω0 J v + η (K v - R(κ) i + α (v*² - |v|²) / v*² v)`, where `K = R(κ) [p* q*; -q* p*] / v*²`
+///
+/// J rotates by 90°, R(κ) by the impedance angle of the network (90° for inductive lines), and p*, q* and v* are
+/// the setpoints.  For inductive lines this behaves as P-f and Q-V droop: the frequency drops by `η / ω0` per-unit
+/// for the rated power, and the voltage by `1 / 2α` per-unit for the rated reactive power.  The oscillators of units
+/// on the same network synchronize if η is small relative to the damping (R/L) of the lines between them.  The
+/// equation is integrated with forward Euler in a frame rotating at ω0, so the rotation doesn't grow the oscillator.
+#[derive(Debug, Copy, Clone)]
+pub struct Dvoc {
+    rating: Float,
+    nominal_voltage: Float,
+    nominal_frequency: Float,
+    ts: Float,
+    eta: Float,
+    alpha: Float,
+    kappa: (Float, Float),
+    rotation: (Float, Float),
+    setpoint: Pq<Float>,
+    voltage: Float,
+    state: AlphaBeta<Float>,
+    frequency: Float,
+}
+
+impl Dvoc {
+    /// Creates a controller for a rated apparent power, nominal voltage (the peak of the phase voltage), and nominal
+    /// frequency, updated every `ts` seconds
+    ///
+    /// The oscillator starts at the nominal voltage and zero angle, with 1% frequency and 5% voltage droop, and an
+    /// impedance angle of 90°.
+    pub fn new(
+        rating: Power,
+        nominal_voltage: Voltage,
+        nominal_frequency: Frequency,
+        ts: Float,
+    ) -> Self {
+        let w = 2.0 * PI * Float::from(nominal_frequency);
+        let mut dvoc = Self {
+            rating: rating.into(),
+            nominal_voltage: nominal_voltage.into(),
+            nominal_frequency: nominal_frequency.into(),
+            ts,
+            eta: 0.0,
+            alpha: 0.0,
+            kappa: (0.0, 1.0),
+            rotation: (
+                libm::Libm::<Float>::cos(w * ts),
+                libm::Libm::<Float>::sin(w * ts),
+            ),
+            setpoint: Pq { p: 0.0, q: 0.0 },
+            voltage: 1.0,
+            state: AlphaBeta::zero(),
+            frequency: nominal_frequency.into(),
+        };
+        dvoc.set_droop(0.01, 0.05);
+        dvoc.set_theta(Theta::from(0));
+        dvoc
+    }
+
+    /// Sets the per-unit frequency and voltage droop
+    pub fn with_droop(mut self, frequency: Float, voltage: Float) -> Self {
+        self.set_droop(frequency, voltage);
+        self
+    }
+
+    /// Sets the impedance angle of the network, atan(X/R)
+    pub fn with_impedance_angle(mut self, kappa: Theta) -> Self {
+        let (cos, sin) = cos_sin(kappa);
+        self.kappa = (cos.into(), sin.into());
+        self
+    }
+
+    pub fn set_droop(&mut self, frequency: Float, voltage: Float) {
+        self.eta = frequency * 2.0 * PI * self.nominal_frequency;
+        self.alpha = 1.0 / (2.0 * voltage);
+    }
+
+    /// Sets the active and reactive power setpoints
+    pub fn set_setpoint(&mut self, setpoint: Pq) {
+        self.setpoint = Pq {
+            p: Float::from(setpoint.p) / self.rating,
+            q: Float::from(setpoint.q) / self.rating,
+        };
+    }
+
+    /// Sets the voltage setpoint, as the peak of the phase voltage
+    pub fn set_voltage(&mut self, voltage: Voltage) {
+        self.voltage = Float::from(voltage) / self.nominal_voltage;
+    }
+
+    /// Sets the angle of the oscillator, with the magnitude at the voltage setpoint
+    pub fn set_theta(&mut self, theta: Theta) {
+        self.state = AlphaBeta::from(Abc::from_polar(self.voltage, theta));
+    }
+
+    /// Returns the voltage reference for this sample, and integrates the oscillator to the next sample with the
+    /// measured current
+    pub fn update(&mut self, current: AlphaBeta<Current>) -> AlphaBeta<Voltage> {
+        let v = self.state;
+        let output = AlphaBeta {
+            alpha: Voltage::from(v.alpha * self.nominal_voltage),
+            beta: Voltage::from(v.beta * self.nominal_voltage),
+        };
+
+        // the current in per-unit of the base current, so that p = v·i
+        let base = 2.0 * self.rating / (3.0 * self.nominal_voltage);
+        let i = AlphaBeta {
+            alpha: Float::from(current.alpha) / base,
+            beta: Float::from(current.beta) / base,
+        };
+
+        let squared = self.voltage * self.voltage;
+        let magnitude = v.alpha * v.alpha + v.beta * v.beta;
+        let (p, q) = (self.setpoint.p / squared, self.setpoint.q / squared);
+        let (cos, sin) = self.kappa;
+
+        // K v - R(κ) i, with [p q; -q p] v rotated by κ
+        let kv = AlphaBeta {
+            alpha: p * v.alpha + q * v.beta,
+            beta: p * v.beta - q * v.alpha,
+        };
+        let error = AlphaBeta {
+            alpha: kv.alpha - i.alpha,
+            beta: kv.beta - i.beta,
+        };
+        let synchronization = AlphaBeta {
+            alpha: cos * error.alpha - sin * error.beta,
+            beta: sin * error.alpha + cos * error.beta,
+        };
+        let regulation = self.alpha * (squared - magnitude) / squared;
+        let derivative = AlphaBeta {
+            alpha: self.eta * (synchronization.alpha + regulation * v.alpha),
+            beta: self.eta * (synchronization.beta + regulation * v.beta),
+        };
+
+        // the instantaneous frequency is the rate of change of the angle
+        let w = (v.alpha * derivative.beta - v.beta * derivative.alpha) / magnitude;
+        self.frequency = self.nominal_frequency + w / (2.0 * PI);
+
+        // step in the frame rotating at ω0, then rotate
+        let v = AlphaBeta {
+            alpha: v.alpha + self.ts * derivative.alpha,
+            beta: v.beta + self.ts * derivative.beta,
+        };
+        let (cos, sin) = self.rotation;
+        self.state = AlphaBeta {
+            alpha: cos * v.alpha - sin * v.beta,
+            beta: sin * v.alpha + cos * v.beta,
+        };
+        output
+    }
+
+    /// Angle of the oscillator for the next sample
+    pub fn theta(&self) -> Theta {
+        // the angle of Abc::from_polar, where alpha = sin(theta) and beta = -cos(theta)
+        Theta::from_radians(libm::Libm::<Float>::atan2(
+            self.state.alpha,
+            -self.state.beta,
+        ))
+    }
+
+    /// Frequency of the oscillator over the last sample
+    pub fn frequency(&self) -> Frequency {
+        Frequency::from(self.frequency)
+    }
+
+    /// Magnitude of the oscillator for the next sample, as the peak of the phase voltage
+    pub fn voltage(&self) -> Voltage {
+        let magnitude = libm::Libm::<Float>::hypot(self.state.alpha, self.state.beta);
+        Voltage::from(magnitude * self.nominal_voltage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+    const V: Float = 340.0;
+    const F: Float = 60.0;
+
+    fn dvoc(rating: Float) -> Dvoc {
+        Dvoc::new(
+            Power::from(rating),
+            Voltage::from(V),
+            Frequency::from(F),
+            TS,
+        )
+    }
+
+    #[test]
+    fn unloaded() {
+        // oscillates at the nominal frequency and setpoint voltage, from any angle
+        let mut dvoc = dvoc(10e3);
+        dvoc.set_voltage(Voltage::from(350.0));
+        dvoc.set_theta(Theta::from_degrees(45.0));
+        for _ in 0..10000 {
+            dvoc.update(AlphaBeta::zero());
+        }
+        assert_abs_diff_eq!(Float::from(dvoc.frequency()), F, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(dvoc.voltage()), 350.0, epsilon = 1e-2);
+
+        // one second at 60Hz returns to the same angle
+        assert_abs_diff_eq!(dvoc.theta().to_degrees(), 45.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn droop() {
+        // delivering half its rating as active power, and a fifth as reactive power
+        let mut dvoc = dvoc(10e3);
+        let base = 2.0 * 10e3 / (3.0 * V);
+        for _ in 0..20000 {
+            // the current leads by the power factor angle, in per-unit of the voltage
+            let theta = dvoc.theta();
+            let magnitude = Float::from(dvoc.voltage()) / V;
+            let (p, q) = (0.5, 0.2);
+            let amplitude = libm::Libm::<Float>::hypot(p, q) / magnitude * base;
+            let angle = Theta::from_radians(libm::Libm::<Float>::atan2(-q, p));
+            let i = AlphaBeta::from(Abc::from_polar(amplitude, theta + angle));
+            dvoc.update(AlphaBeta {
+                alpha: i.alpha.into(),
+                beta: i.beta.into(),
+            });
+        }
+        // in steady state the magnitude balances q / |v|^2 = α (1 - |v|^2), i.e. |v|^2 = 0.9796 with 5% droop
+        let squared = (1.0 + libm::Libm::<Float>::sqrt(1.0 - 4.0 * 0.2 * 2.0 * 0.05)) / 2.0;
+        assert_abs_diff_eq!(
+            Float::from(dvoc.voltage()),
+            V * libm::Libm::<Float>::sqrt(squared),
+            epsilon = 0.05
+        );
+
+        // and the frequency drops by 1% for the rated current (rather than power) at that magnitude
+        assert_abs_diff_eq!(
+            Float::from(dvoc.frequency()),
+            60.0 - 0.5 * 0.6 / squared,
+            epsilon = 1e-3
+        );
+    }
+
+    // two units connected through RL lines (inversely proportional to their ratings) to a resistive load,
+    // integrated in steps within each sample
+    struct Network {
+        currents: [AlphaBeta<Float>; 2],
+        lines: [(Float, Float); 2],
+        load: Float,
+    }
+
+    impl Network {
+        fn step(&mut self, voltages: [AlphaBeta<Voltage>; 2]) {
+            const STEPS: usize = 20;
+            let h = TS / (STEPS as Float);
+            for _ in 0..STEPS {
+                let bus = AlphaBeta {
+                    alpha: self.load * (self.currents[0].alpha + self.currents[1].alpha),
+                    beta: self.load * (self.currents[0].beta + self.currents[1].beta),
+                };
+                for ((current, &(r, l)), v) in self
+                    .currents
+                    .iter_mut()
+                    .zip(self.lines.iter())
+                    .zip(voltages.iter())
+                {
+                    current.alpha += h * (Float::from(v.alpha) - r * current.alpha - bus.alpha) / l;
+                    current.beta += h * (Float::from(v.beta) - r * current.beta - bus.beta) / l;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn synchronization() {
+        // 10kW and 20kW units, starting 60 degrees apart, sharing a 15kW load
+        let lines = [(0.85, 4.5e-3), (0.425, 2.25e-3)];
+        let kappa = Theta::from_radians(libm::Libm::<Float>::atan2(2.0 * PI * F * 4.5e-3, 0.85));
+        let mut units = [
+            dvoc(10e3).with_impedance_angle(kappa),
+            dvoc(20e3).with_impedance_angle(kappa),
+        ];
+        units[1].set_theta(Theta::from_degrees(60.0));
+        let mut network = Network {
+            currents: [AlphaBeta::zero(); 2],
+            lines,
+            load: 1.5 * V * V / 15e3,
+        };
+
+        let mut power = [Pq { p: 0.0, q: 0.0 }; 2];
+        for _ in 0..20000 {
+            let currents = [
+                AlphaBeta {
+                    alpha: Current::from(network.currents[0].alpha),
+                    beta: Current::from(network.currents[0].beta),
+                },
+                AlphaBeta {
+                    alpha: Current::from(network.currents[1].alpha),
+                    beta: Current::from(network.currents[1].beta),
+                },
+            ];
+            let voltages = [units[0].update(currents[0]), units[1].update(currents[1])];
+            for (power, (&v, &i)) in power.iter_mut().zip(voltages.iter().zip(currents.iter())) {
+                let pq = v * i;
+                *power = Pq {
+                    p: pq.p.into(),
+                    q: pq.q.into(),
+                };
+            }
+            network.step(voltages);
+        }
+
+        // the oscillators lock at the same frequency, nearly in phase
+        let frequency: [Float; 2] = [units[0].frequency().into(), units[1].frequency().into()];
+        assert_abs_diff_eq!(frequency[0], frequency[1], epsilon = 1e-4);
+        let difference = (units[1].theta() - units[0].theta()).to_degrees();
+        assert!(Float::abs(difference) < 0.01);
+
+        // and share the load in proportion to their ratings
+        assert_abs_diff_eq!(power[1].p / power[0].p, 2.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(power[1].q / power[0].q, 2.0, epsilon = 1e-3);
+        assert!(power[0].p + power[1].p > 14e3);
+        assert!(frequency[0] < 60.0);
+    }
+}
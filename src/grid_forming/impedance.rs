@@ -0,0 +1,205 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::constants::PI;
+use crate::newtypes::{Current, Frequency, Impedance, Voltage};
+use crate::number::Float;
+use crate::reference_frames::{AlphaBeta, Dq};
+
+// the additional impedance above a current threshold
+#[derive(Debug, Copy, Clone)]
+struct Threshold {
+    current: Float,
+    gain: Float,
+    ratio: Float,
+}
+
+/// A virtual impedance `R + jωL`, subtracted from a grid-forming controller's voltage reference
+///
+/// The drop is quasi-static, i.e. the steady state drop across the impedance at the frequency of the rotating frame,
+/// without differentiating the current.  With a threshold, the impedance increases with the current above it, by
+/// `ΔR = kR (|i| - Ith)` and `ΔX = (X/R) ΔR`, limiting the fault current while the controller stays in synchronism.
+#[derive(Debug, Copy, Clone)]
+pub struct VirtualImpedance {
+    resistance: Float,
+    inductance: Float,
+    threshold: Option<Threshold>,
+}
+
+impl VirtualImpedance {
+    /// Creates a virtual impedance from a resistance and inductance (in henries)
+    pub fn new(resistance: Impedance, inductance: Float) -> Self {
+        Self {
+            resistance: resistance.into(),
+            inductance,
+            threshold: None,
+        }
+    }
+
+    /// Adds the threshold impedance above a current magnitude, with a resistance gain (in ohms per amp) and X/R
+    /// ratio
+    pub fn with_threshold(mut self, current: Current, gain: Float, ratio: Float) -> Self {
+        self.threshold = Some(Threshold {
+            current: current.into(),
+            gain,
+            ratio,
+        });
+        self
+    }
+
+    /// The resistance and reactance for a current magnitude and frequency
+    pub fn impedance(&self, current: Current, frequency: Frequency) -> (Impedance, Impedance) {
+        let mut resistance = self.resistance;
+        let mut reactance = 2.0 * PI * Float::from(frequency) * self.inductance;
+        if let Some(threshold) = self.threshold {
+            let excess = Float::from(current) - threshold.current;
+            if excess > 0.0 {
+                resistance += threshold.gain * excess;
+                reactance += threshold.ratio * threshold.gain * excess;
+            }
+        }
+        (resistance.into(), reactance.into())
+    }
+
+    /// Calculates the voltage drop for a [Dq] current, in a frame rotating at a frequency
+    pub fn voltage_drop(&self, current: Dq<Current>, frequency: Frequency) -> Dq<Voltage> {
+        let magnitude = libm::Libm::<Float>::hypot(current.d.into(), current.q.into());
+        let (resistance, reactance) = self.impedance(magnitude.into(), frequency);
+        Dq {
+            d: resistance * current.d - reactance * current.q,
+            q: resistance * current.q + reactance * current.d,
+        }
+    }
+
+    /// Calculates the voltage drop for a positive sequence [AlphaBeta] current at a frequency
+    pub fn voltage_drop_alpha_beta(
+        &self,
+        current: AlphaBeta<Current>,
+        frequency: Frequency,
+    ) -> AlphaBeta<Voltage> {
+        let magnitude = libm::Libm::<Float>::hypot(current.alpha.into(), current.beta.into());
+        let (resistance, reactance) = self.impedance(magnitude.into(), frequency);
+        // jX leads the current by 90 degrees
+        AlphaBeta {
+            alpha: resistance * current.alpha - reactance * current.beta,
+            beta: resistance * current.beta + reactance * current.alpha,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::reference_frames::Abc;
+    use crate::tests::current;
+    use crate::trig::{cos_sin, Theta};
+    use approx::assert_abs_diff_eq;
+
+    const F: Float = 60.0;
+
+    #[test]
+    fn coupling() {
+        let impedance = VirtualImpedance::new(0.1.into(), 1e-3);
+        let x = 2.0 * PI * F * 1e-3;
+
+        let drop = impedance.voltage_drop(current(10.0, 0.0), F.into());
+        assert_abs_diff_eq!(Float::from(drop.d), 1.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(drop.q), 10.0 * x, epsilon = 1e-4);
+
+        let drop = impedance.voltage_drop(current(0.0, 10.0), F.into());
+        assert_abs_diff_eq!(Float::from(drop.d), -10.0 * x, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(drop.q), 1.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn alpha_beta() {
+        // the drop of a sinusoidal current is the same in either frame, and leads the current by the impedance angle
+        let impedance = VirtualImpedance::new(0.1.into(), 1e-3);
+        let theta = Theta::from_degrees(75.0);
+        let (cos, sin) = cos_sin(theta);
+        let i = AlphaBeta::from(Abc::from_polar(10.0, theta));
+        let i: AlphaBeta<Current> = AlphaBeta {
+            alpha: i.alpha.into(),
+            beta: i.beta.into(),
+        };
+        let i_dq = i.to_dq(cos, sin);
+
+        let drop = impedance.voltage_drop_alpha_beta(i, F.into());
+        let expected = impedance
+            .voltage_drop(i_dq, F.into())
+            .to_alpha_beta(cos, sin);
+        assert_abs_diff_eq!(
+            Float::from(drop.alpha),
+            Float::from(expected.alpha),
+            epsilon = 1e-4
+        );
+        assert_abs_diff_eq!(
+            Float::from(drop.beta),
+            Float::from(expected.beta),
+            epsilon = 1e-4
+        );
+
+        let x = 2.0 * PI * F * 1e-3;
+        let angle = Theta::from_radians(libm::Libm::<Float>::atan2(x, 0.1));
+        let expected = AlphaBeta::from(Abc::from_polar(
+            10.0 * libm::Libm::<Float>::hypot(0.1, x),
+            theta + angle,
+        ));
+        assert_abs_diff_eq!(Float::from(drop.alpha), expected.alpha, epsilon = 1e-3);
+        assert_abs_diff_eq!(Float::from(drop.beta), expected.beta, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn threshold() {
+        let impedance =
+            VirtualImpedance::new(0.0.into(), 0.0).with_threshold(Current::from(40.0), 0.2, 5.0);
+
+        let (r, x) = impedance.impedance(Current::from(30.0), F.into());
+        assert_eq!((Float::from(r), Float::from(x)), (0.0, 0.0));
+        let (r, x) = impedance.impedance(Current::from(50.0), F.into());
+        assert_abs_diff_eq!(Float::from(r), 2.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(Float::from(x), 10.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn fault_current() {
+        // a 340V EMF, through a 0.1Ω line, into a bolted fault
+        let emf = 340.0;
+        let line = 0.1;
+        let fault = |impedance: VirtualImpedance| {
+            // relax the current to where the EMF balances the drop across the line and the virtual impedance
+            let mut i: Float = 0.0;
+            for _ in 0..1000 {
+                let (r, x) = impedance.impedance(i.into(), F.into());
+                let z = libm::Libm::<Float>::hypot(Float::from(r) + line, x.into());
+                i += 0.1 * (emf / z - i);
+            }
+            i
+        };
+
+        let unlimited = fault(VirtualImpedance::new(0.0.into(), 0.0));
+        assert_abs_diff_eq!(unlimited, 3400.0, epsilon = 1.0);
+
+        // the threshold impedance holds the current near its threshold
+        let limited = fault(VirtualImpedance::new(0.0.into(), 0.0).with_threshold(
+            Current::from(40.0),
+            0.5,
+            5.0,
+        ));
+        assert!(limited > 40.0);
+        assert!(limited < 1.5 * 40.0);
+    }
+}
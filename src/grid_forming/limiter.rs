@@ -0,0 +1,122 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::newtypes::Current;
+use crate::number::Float;
+use crate::reference_frames::Dq;
+
+/// How a [CurrentLimiter] saturates a reference above its limit
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LimitPriority {
+    /// Scales the reference to the limit, preserving its angle
+    Angle,
+    /// Keeps the reactive (q axis) current, up to the limit, and reduces the active (d axis) current to fit
+    Reactive,
+}
+
+/// Saturates the magnitude of a [Dq] current reference
+///
+/// The d axis is assumed to be aligned with the voltage, so the q axis carries the reactive current.
+#[derive(Debug, Copy, Clone)]
+pub struct CurrentLimiter {
+    limit: Float,
+    priority: LimitPriority,
+}
+
+impl CurrentLimiter {
+    /// Creates a limiter for a current magnitude, preserving the angle of the reference
+    pub fn new(limit: Current) -> Self {
+        Self {
+            limit: limit.into(),
+            priority: LimitPriority::Angle,
+        }
+    }
+
+    pub fn with_priority(mut self, priority: LimitPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Limits a current reference, returning it unchanged if within the limit
+    pub fn limit(&self, reference: Dq<Current>) -> Dq<Current> {
+        let (d, q): (Float, Float) = (reference.d.into(), reference.q.into());
+        let magnitude = libm::Libm::<Float>::hypot(d, q);
+        if magnitude <= self.limit {
+            return reference;
+        }
+
+        let (d, q) = match self.priority {
+            LimitPriority::Angle => {
+                let scale = self.limit / magnitude;
+                (d * scale, q * scale)
+            }
+            LimitPriority::Reactive => {
+                let q = q.clamp(-self.limit, self.limit);
+                let d = libm::Libm::<Float>::sqrt(self.limit * self.limit - q * q);
+                (d.copysign(reference.d.into()), q)
+            }
+        };
+        Dq {
+            d: d.into(),
+            q: q.into(),
+        }
+    }
+
+    /// Whether a current reference exceeds the limit
+    pub fn limiting(&self, reference: Dq<Current>) -> bool {
+        libm::Libm::<Float>::hypot(reference.d.into(), reference.q.into()) > self.limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::current;
+    use approx::assert_abs_diff_eq;
+
+    fn check(limited: Dq<Current>, d: Float, q: Float) {
+        assert_abs_diff_eq!(Float::from(limited.d), d, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(limited.q), q, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn within_limit() {
+        for &priority in [LimitPriority::Angle, LimitPriority::Reactive].iter() {
+            let limiter = CurrentLimiter::new(Current::from(50.0)).with_priority(priority);
+            assert!(!limiter.limiting(current(30.0, -40.0)));
+            check(limiter.limit(current(30.0, -40.0)), 30.0, -40.0);
+        }
+    }
+
+    #[test]
+    fn angle() {
+        let limiter = CurrentLimiter::new(Current::from(50.0));
+        assert!(limiter.limiting(current(-60.0, 80.0)));
+        check(limiter.limit(current(-60.0, 80.0)), -30.0, 40.0);
+    }
+
+    #[test]
+    fn reactive() {
+        let limiter =
+            CurrentLimiter::new(Current::from(50.0)).with_priority(LimitPriority::Reactive);
+
+        // the reactive current is kept, and the active current reduced to fit
+        check(limiter.limit(current(-60.0, 40.0)), -30.0, 40.0);
+
+        // or all reactive if it alone exceeds the limit
+        check(limiter.limit(current(20.0, -70.0)), 0.0, -50.0);
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Grid-forming control
+
+Grid-forming controllers set the angle and magnitude of the inverter's voltage, from the power it delivers, rather
+than following the grid's voltage with a PLL.  Each produces a [Reference] every sample, with the angle integrated from
+the controller's frequency by a [PhaseAccumulator](crate::trig::PhaseAccumulator).
+
+1. [Droop] - P-f and Q-V droop characteristics, with filtered power measurements, setpoints and secondary restoration
+2. [Vsm] - A virtual synchronous machine, with inertia, governor droop, damping and an optional virtual excitation
+3. [VirtualImpedance] - A virtual impedance `R + jωL`, with a threshold impedance which increases above a current to
+   limit fault current
+4. [CurrentLimiter] - A vector limiter for [Dq] current references, preserving the angle or prioritizing
+   reactive current
+5. [Dvoc] - Dispatchable virtual oscillator control, integrating an oscillator in the [AlphaBeta](crate::AlphaBeta)
+   frame from the measured current, with per-unit droop and power and voltage setpoints
+
+```rust
+use ac_power::grid_forming::Droop;
+use ac_power::number::Float;
+use ac_power::{Frequency, Power, Pq, Voltage};
+use approx::assert_abs_diff_eq;
+
+// a 10kW inverter with 1% frequency and 5% voltage droop
+let mut droop = Droop::new(Power::from(10e3), Frequency::from(60.0), Voltage::from(240.0), 1e-4);
+
+// delivering half its rating slows it by 0.5% of nominal
+let measured = Pq { p: Power::from(5e3), q: Power::from(0.0) };
+let reference = (0..10000).map(|_| droop.update(measured)).last().unwrap();
+assert_abs_diff_eq!(Float::from(reference.frequency), 59.7, epsilon = 1e-3);
+assert_abs_diff_eq!(Float::from(reference.voltage), 240.0, epsilon = 1e-3);
+```
+*/
+
+use crate::newtypes::{Frequency, Voltage};
+use crate::reference_frames::Dq;
+use crate::trig::Theta;
+
+mod droop;
+mod dvoc;
+mod impedance;
+mod limiter;
+mod vsm;
+
+pub use droop::Droop;
+pub use dvoc::Dvoc;
+pub use impedance::VirtualImpedance;
+pub use limiter::{CurrentLimiter, LimitPriority};
+pub use vsm::Vsm;
+
+/// The voltage a grid-forming controller sets for a sample
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Reference {
+    /// Angle of the voltage for this sample
+    pub theta: Theta,
+    pub frequency: Frequency,
+    /// Magnitude of the voltage, in the units of the controller's nominal voltage
+    pub voltage: Voltage,
+}
+
+impl Reference {
+    /// The voltage as a [Dq] vector in the frame at theta, i.e. with the magnitude on the d axis
+    pub fn emf(&self) -> Dq<Voltage> {
+        Dq {
+            d: self.voltage,
+            q: Voltage::from(0.0),
+        }
+    }
+}
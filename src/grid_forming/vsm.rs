@@ -0,0 +1,319 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use super::Reference;
+use crate::newtypes::{Frequency, Power, Voltage};
+use crate::number::Float;
+use crate::pq::Pq;
+use crate::trig::PhaseAccumulator;
+
+// Q-V droop of the virtual excitation, on a filtered reactive power
+#[derive(Debug, Copy, Clone)]
+struct Excitation {
+    mq: Float,
+    alpha: Float,
+    filtered: Option<Float>,
+}
+
+/// A virtual synchronous machine
+///
+/// The speed follows the swing equation, in per-unit of the rating and nominal frequency:
+/// `2H dω/dt = P0 + (1 - ω) / R - P - D (ω - ωf)`, where H is the inertia constant (in seconds), R the governor
+/// droop, and D the damping acting on the deviation of the speed from its low-pass filtered value ωf, so it damps
+/// oscillations without changing the steady state set by the governor.  The internal EMF is the nominal voltage, or
+/// with the virtual excitation enabled, `E = V0 - mq (Q - Q0)` on the filtered reactive power.
+#[derive(Debug, Copy, Clone)]
+pub struct Vsm {
+    rating: Float,
+    nominal_frequency: Float,
+    nominal_voltage: Float,
+    ts: Float,
+    inertia: Float,
+    governor: Float,
+    damping: Float,
+    alpha: Float,
+    excitation: Option<Excitation>,
+    setpoint: Pq<Float>,
+    deviation: Float,
+    filtered_deviation: Float,
+    phase: PhaseAccumulator,
+}
+
+impl Vsm {
+    /// Creates a virtual synchronous machine for a rated apparent power, nominal frequency and voltage, updated every
+    /// `ts` seconds
+    ///
+    /// The inertia constant defaults to 2s and the governor droop to 1%, without damping or virtual excitation.
+    pub fn new(
+        rating: Power,
+        nominal_frequency: Frequency,
+        nominal_voltage: Voltage,
+        ts: Float,
+    ) -> Self {
+        Self {
+            rating: rating.into(),
+            nominal_frequency: nominal_frequency.into(),
+            nominal_voltage: nominal_voltage.into(),
+            ts,
+            inertia: 2.0,
+            governor: 1.0 / 0.01,
+            damping: 0.0,
+            alpha: 1.0,
+            excitation: None,
+            setpoint: Pq { p: 0.0, q: 0.0 },
+            deviation: 0.0,
+            filtered_deviation: 0.0,
+            phase: PhaseAccumulator::new(ts),
+        }
+    }
+
+    /// Sets the inertia constant (in seconds)
+    pub fn with_inertia(mut self, inertia: Float) -> Self {
+        self.inertia = inertia;
+        self
+    }
+
+    /// Sets the per-unit governor droop, or disables the governor if infinite
+    pub fn with_governor(mut self, droop: Float) -> Self {
+        self.governor = 1.0 / droop;
+        self
+    }
+
+    /// Sets the per-unit damping, and the time constant (in seconds) of the speed filter it acts relative to
+    pub fn with_damping(mut self, damping: Float, time_constant: Float) -> Self {
+        self.damping = damping;
+        self.alpha = self.ts / (time_constant + self.ts);
+        self
+    }
+
+    /// Enables the virtual excitation, with a per-unit Q-V droop, and the time constant (in seconds) of the reactive
+    /// power filter
+    pub fn with_excitation(mut self, droop: Float, time_constant: Float) -> Self {
+        self.excitation = Some(Excitation {
+            mq: droop * self.nominal_voltage / self.rating,
+            alpha: self.ts / (time_constant + self.ts),
+            filtered: None,
+        });
+        self
+    }
+
+    /// Sets the active and reactive power delivered at the nominal frequency and voltage
+    pub fn set_setpoint(&mut self, setpoint: Pq) {
+        self.setpoint = Pq {
+            p: setpoint.p.into(),
+            q: setpoint.q.into(),
+        };
+    }
+
+    /// Calculates the voltage reference for this sample from the measured power
+    pub fn update(&mut self, measured: Pq) -> Reference {
+        let (p, q): (Float, Float) = (measured.p.into(), measured.q.into());
+
+        // integrate the swing equation, on the deviation of the speed from nominal to keep its precision
+        let mechanical = self.setpoint.p / self.rating - self.governor * self.deviation;
+        let electrical = p / self.rating;
+        let damping = self.damping * (self.deviation - self.filtered_deviation);
+        self.filtered_deviation += (self.deviation - self.filtered_deviation) * self.alpha;
+        self.deviation += self.ts * (mechanical - electrical - damping) / (2.0 * self.inertia);
+
+        let voltage = match self.excitation.as_mut() {
+            Some(excitation) => {
+                let filtered = match excitation.filtered {
+                    Some(filtered) => filtered + (q - filtered) * excitation.alpha,
+                    None => q,
+                };
+                excitation.filtered = Some(filtered);
+                self.nominal_voltage - excitation.mq * (filtered - self.setpoint.q)
+            }
+            None => self.nominal_voltage,
+        };
+
+        let frequency = self.frequency();
+        Reference {
+            theta: self.phase.update(frequency),
+            frequency,
+            voltage: Voltage::from(voltage),
+        }
+    }
+
+    /// The frequency of the virtual rotor
+    pub fn frequency(&self) -> Frequency {
+        Frequency::from((1.0 + self.deviation) * self.nominal_frequency)
+    }
+
+    pub fn phase(&self) -> &PhaseAccumulator {
+        &self.phase
+    }
+
+    pub fn phase_mut(&mut self) -> &mut PhaseAccumulator {
+        &mut self.phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::reference_frames::{Abc, AlphaBeta};
+    use crate::tests::power;
+    use crate::trig::{cos_sin, Theta};
+    use crate::wavegen::{Event, EventKind, Magnitude, Scenario, Sequence, Waveform};
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+    const RATING: Float = 10e3;
+
+    fn vsm() -> Vsm {
+        Vsm::new(
+            Power::from(RATING),
+            Frequency::from(60.0),
+            Voltage::from(240.0),
+            TS,
+        )
+    }
+
+    fn run(vsm: &mut Vsm, measured: Pq, samples: usize) -> Reference {
+        (0..samples).map(|_| vsm.update(measured)).last().unwrap()
+    }
+
+    #[test]
+    fn swing() {
+        // without a governor, a 0.1pu power deficit accelerates the rotor at 0.1 / 2H pu/s
+        let mut vsm = vsm().with_inertia(2.0).with_governor(Float::INFINITY);
+        vsm.set_setpoint(power(1e3, 0.0));
+        let reference = run(&mut vsm, power(0.0, 0.0), 1000);
+        assert_abs_diff_eq!(
+            Float::from(reference.frequency),
+            60.0 * (1.0 + 0.1 * 0.1 / 4.0),
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn governor() {
+        // delivering half its rating slows it by 0.5% of nominal
+        let mut vsm = vsm().with_damping(20.0, 0.05);
+        let reference = run(&mut vsm, power(5e3, 0.0), 20000);
+        assert_abs_diff_eq!(Float::from(reference.frequency), 59.7, epsilon = 1e-3);
+        assert_abs_diff_eq!(Float::from(reference.voltage), 240.0, epsilon = 1e-3);
+
+        // at the setpoint
+        vsm.set_setpoint(power(5e3, 0.0));
+        run(&mut vsm, power(5e3, 0.0), 20000);
+        assert_abs_diff_eq!(Float::from(vsm.frequency()), 60.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn excitation() {
+        let mut vsm = vsm().with_excitation(0.05, 0.02);
+        let reference = run(&mut vsm, power(0.0, 2e3), 2000);
+        assert_abs_diff_eq!(
+            Float::from(reference.voltage),
+            240.0 - 0.05 * 240.0 * 0.2,
+            epsilon = 1e-3
+        );
+        let emf = reference.emf();
+        assert_abs_diff_eq!(Float::from(emf.d), 237.6, epsilon = 1e-3);
+        assert_abs_diff_eq!(Float::from(emf.q), 0.0);
+
+        // rotated by theta, the EMF is the voltage of the machine at its angle
+        let (cos, sin) = cos_sin(reference.theta);
+        let alpha_beta = emf.to_alpha_beta(cos, sin);
+        let expected = AlphaBeta::from(Abc::from_polar(reference.voltage, reference.theta));
+        assert_abs_diff_eq!(
+            Float::from(alpha_beta.alpha),
+            Float::from(expected.alpha),
+            epsilon = 1e-3
+        );
+        assert_abs_diff_eq!(
+            Float::from(alpha_beta.beta),
+            Float::from(expected.beta),
+            epsilon = 1e-3
+        );
+    }
+
+    struct Response {
+        frequency: Float,
+        power: Float,
+        largest_rocof: Float,
+        largest_power: Float,
+        lowest_frequency: Float,
+    }
+
+    // connects the machine through a reactance to a grid which steps from 60Hz to 59.8Hz at 0.5s, and runs for 3s
+    fn frequency_step(mut vsm: Vsm) -> Response {
+        let waveform: Waveform<Float, 1> = Waveform::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(1.0),
+            Theta::from(0),
+        );
+        let events = [Event::new(0.5, None, EventKind::FrequencyStep(-0.2))];
+        let mut grid = Scenario::new(waveform, 60.0, TS, &events);
+
+        // power flow per radian of angle difference, 10pu
+        let k = 10.0 * RATING;
+        let mut p = 0.0;
+        let mut frequency = 60.0;
+        let mut response = Response {
+            frequency,
+            power: p,
+            largest_rocof: 0.0,
+            largest_power: 0.0,
+            lowest_frequency: frequency,
+        };
+        for _ in 0..30000 {
+            let reference = vsm.update(power(p, 0.0));
+            let delta = (reference.theta - grid.theta()).to_radians();
+            grid.next();
+            p = k * libm::Libm::<Float>::sin(delta);
+
+            let rocof = (Float::from(reference.frequency) - frequency) / TS;
+            frequency = reference.frequency.into();
+            response = Response {
+                frequency,
+                power: p,
+                largest_rocof: response.largest_rocof.max(rocof.abs()),
+                largest_power: response.largest_power.max(p),
+                lowest_frequency: response.lowest_frequency.min(frequency),
+            };
+        }
+        response
+    }
+
+    #[test]
+    fn inertial_response() {
+        let light = frequency_step(vsm().with_inertia(1.0));
+        let heavy = frequency_step(vsm().with_inertia(5.0));
+
+        // both follow the grid, and settle at the governor's share of the step, 0.2 / 60 / 1%
+        for response in [&light, &heavy].iter() {
+            assert_abs_diff_eq!(response.frequency, 59.8, epsilon = 1e-3);
+            assert_abs_diff_eq!(response.power, RATING / 3.0, epsilon = 5.0);
+        }
+
+        // more inertia slows the change in frequency, so delivers more power while the grid's angle pulls away
+        assert!(heavy.largest_rocof < 0.7 * light.largest_rocof);
+        assert!(heavy.largest_power > light.largest_power);
+        assert!(heavy.largest_power > 1.2 * RATING / 3.0);
+
+        // damping opposes the change in speed, so reduces the overshoot of the frequency, without changing the steady
+        // state
+        let damped = frequency_step(vsm().with_inertia(5.0).with_damping(200.0, 0.1));
+        assert_abs_diff_eq!(damped.power, RATING / 3.0, epsilon = 5.0);
+        assert!(heavy.lowest_frequency < 59.75);
+        assert!(59.8 - damped.lowest_frequency < 0.7 * (59.8 - heavy.lowest_frequency));
+    }
+}
@@ -0,0 +1,452 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+IEEE 1547 grid support functions
+
+[GridSupport] turns a dispatched active power, the measured voltage and the frequency into a [Pq] setpoint, with one
+[ReactiveMode] (Volt-VAr, Watt-VAr, constant power factor or constant VAr) and optional Volt-Watt and frequency droop
+(frequency-Watt) active power functions.  The active and reactive targets each pass through an open-loop response time
+filter, and the setpoint is then limited to the rated apparent power, with either active or reactive power [Priority].
+
+Curves are per-unit: voltage of the nominal voltage, and active and reactive power of the rated apparent power.
+Positive reactive power is injected (over-excited), and negative is absorbed.
+
+```rust
+use ac_power::grid_support::{Curve, GridSupport, ReactiveMode};
+use ac_power::number::Float;
+use ac_power::{Frequency, Power, Voltage};
+use approx::assert_abs_diff_eq;
+
+let mut support = GridSupport::new(Power::from(1000.0), Voltage::from(240.0), Frequency::from(60.0), 0.01)
+    .with_reactive(ReactiveMode::VoltVar(Curve::volt_var()), 5.0);
+
+// absorb reactive power at high voltage
+let mut pq = support.update(Power::from(500.0), Power::from(800.0), Voltage::from(252.0), Frequency::from(60.0));
+for _ in 0..3000 {
+    pq = support.update(Power::from(500.0), Power::from(800.0), Voltage::from(252.0), Frequency::from(60.0));
+}
+assert_abs_diff_eq!(Float::from(pq.p), 500.0, epsilon = 0.1);
+assert_abs_diff_eq!(Float::from(pq.q), -220.0, epsilon = 0.1);
+```
+*/
+
+use crate::newtypes::{Frequency, Power, Voltage};
+use crate::number::Float;
+use crate::pq::Pq;
+
+/// A piecewise-linear curve through `N` points, held constant beyond the first and last points
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Curve<const N: usize> {
+    /// Points of the curve, in increasing order of x
+    pub points: [(Float, Float); N],
+}
+
+impl<const N: usize> Curve<N> {
+    pub fn new(points: [(Float, Float); N]) -> Self {
+        Self { points }
+    }
+
+    /// Interpolates the curve at x
+    pub fn evaluate(&self, x: Float) -> Float {
+        let first = self.points[0];
+        if x <= first.0 {
+            return first.1;
+        }
+        for window in self.points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+            if x <= x1 {
+                return if x1 > x0 {
+                    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+                } else {
+                    y1
+                };
+            }
+        }
+        self.points[N - 1].1
+    }
+}
+
+impl Curve<4> {
+    /// The default Category B Volt-VAr curve (reactive power against voltage)
+    pub fn volt_var() -> Self {
+        Self::new([(0.92, 0.44), (0.98, 0.0), (1.02, 0.0), (1.08, -0.44)])
+    }
+}
+
+impl Curve<3> {
+    /// The default Category B Watt-VAr curve (reactive power against active power)
+    pub fn watt_var() -> Self {
+        Self::new([(0.2, 0.0), (0.5, 0.0), (1.0, -0.44)])
+    }
+}
+
+impl Curve<2> {
+    /// The default Volt-Watt curve (active power limit against voltage)
+    pub fn volt_watt() -> Self {
+        Self::new([(1.06, 1.0), (1.10, 0.2)])
+    }
+}
+
+/// A first order filter, which reaches 90% of a step change at its response time
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ResponseFilter {
+    alpha: Float,
+    value: Option<Float>,
+}
+
+impl ResponseFilter {
+    /// Creates a filter for a response time and sample period (in seconds)
+    pub fn new(response_time: Float, ts: Float) -> Self {
+        // the time constant is the response time / ln(10)
+        let alpha = if response_time > 0.0 {
+            1.0 - libm::Libm::<Float>::exp(-ts * core::f64::consts::LN_10 as Float / response_time)
+        } else {
+            1.0
+        };
+        Self { alpha, value: None }
+    }
+
+    /// Filters a sample, starting from the first sample
+    pub fn update(&mut self, x: Float) -> Float {
+        let value = match self.value {
+            Some(value) => value + (x - value) * self.alpha,
+            None => x,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+/// Whether constant power factor operation injects or absorbs reactive power
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Excitation {
+    /// Over-excited
+    Injecting,
+    /// Under-excited
+    Absorbing,
+}
+
+/// Reactive power function
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReactiveMode {
+    /// No reactive power
+    Off,
+    /// A constant power factor
+    ConstantPowerFactor(Float, Excitation),
+    /// A constant reactive power (per-unit)
+    ConstantVar(Float),
+    /// Reactive power against voltage
+    VoltVar(Curve<4>),
+    /// Reactive power against active power
+    WattVar(Curve<3>),
+}
+
+/// Which power is kept when the setpoint exceeds the rated apparent power
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Priority {
+    Active,
+    Reactive,
+}
+
+/// Frequency droop (frequency-Watt) settings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FrequencyDroop {
+    /// Deadband either side of nominal (in Hz)
+    pub deadband: Float,
+    /// Per-unit frequency change for a per-unit power change
+    pub droop: Float,
+}
+
+impl Default for FrequencyDroop {
+    /// The default settings, a 0.036Hz deadband and 5% droop
+    fn default() -> Self {
+        Self {
+            deadband: 0.036,
+            droop: 0.05,
+        }
+    }
+}
+
+/// IEEE 1547 grid support functions
+pub struct GridSupport {
+    rating: Float,
+    nominal_voltage: Float,
+    nominal_frequency: Float,
+    ts: Float,
+    reactive: ReactiveMode,
+    volt_watt: Option<Curve<2>>,
+    frequency_droop: Option<FrequencyDroop>,
+    priority: Priority,
+    active_filter: ResponseFilter,
+    reactive_filter: ResponseFilter,
+}
+
+impl GridSupport {
+    /// Creates the grid support functions (all disabled) for a rated apparent power, nominal voltage and frequency,
+    /// updated every `ts` seconds
+    pub fn new(
+        rating: Power,
+        nominal_voltage: Voltage,
+        nominal_frequency: Frequency,
+        ts: Float,
+    ) -> Self {
+        Self {
+            rating: rating.into(),
+            nominal_voltage: nominal_voltage.into(),
+            nominal_frequency: nominal_frequency.into(),
+            ts,
+            reactive: ReactiveMode::Off,
+            volt_watt: None,
+            frequency_droop: None,
+            priority: Priority::Reactive,
+            active_filter: ResponseFilter::new(0.0, ts),
+            reactive_filter: ResponseFilter::new(0.0, ts),
+        }
+    }
+
+    /// Sets the reactive power function, and its open-loop response time (in seconds)
+    pub fn with_reactive(mut self, mode: ReactiveMode, response_time: Float) -> Self {
+        self.reactive = mode;
+        self.reactive_filter = ResponseFilter::new(response_time, self.ts);
+        self
+    }
+
+    /// Enables Volt-Watt, and sets the open-loop response time of the active power (in seconds)
+    pub fn with_volt_watt(mut self, curve: Curve<2>, response_time: Float) -> Self {
+        self.volt_watt = Some(curve);
+        self.active_filter = ResponseFilter::new(response_time, self.ts);
+        self
+    }
+
+    /// Enables frequency droop, and sets the open-loop response time of the active power (in seconds)
+    pub fn with_frequency_droop(mut self, droop: FrequencyDroop, response_time: Float) -> Self {
+        self.frequency_droop = Some(droop);
+        self.active_filter = ResponseFilter::new(response_time, self.ts);
+        self
+    }
+
+    /// Sets which power has priority at the apparent power limit (reactive by default)
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Calculates the power setpoint from the dispatched and available active power, the voltage and the frequency
+    pub fn update(
+        &mut self,
+        dispatch: Power,
+        available: Power,
+        voltage: Voltage,
+        frequency: Frequency,
+    ) -> Pq {
+        let dispatch = Float::from(dispatch) / self.rating;
+        let available = Float::from(available) / self.rating;
+        let voltage = Float::from(voltage) / self.nominal_voltage;
+        let frequency = Float::from(frequency);
+
+        let mut p = Float::min(dispatch, available);
+        if let Some(droop) = self.frequency_droop {
+            let high = self.nominal_frequency + droop.deadband;
+            let low = self.nominal_frequency - droop.deadband;
+            let gain = 1.0 / (self.nominal_frequency * droop.droop);
+            if frequency > high {
+                p = Float::max(p - (frequency - high) * gain, 0.0);
+            } else if frequency < low {
+                p = Float::min(p + (low - frequency) * gain, available);
+            }
+        }
+        if let Some(curve) = self.volt_watt {
+            p = Float::min(p, curve.evaluate(voltage));
+        }
+        let p = self.active_filter.update(p);
+
+        let q = match self.reactive {
+            ReactiveMode::Off => 0.0,
+            ReactiveMode::ConstantPowerFactor(pf, excitation) => {
+                let q = Float::abs(p) * libm::Libm::<Float>::sqrt(1.0 - pf * pf) / pf;
+                match excitation {
+                    Excitation::Injecting => q,
+                    Excitation::Absorbing => -q,
+                }
+            }
+            ReactiveMode::ConstantVar(q) => q,
+            ReactiveMode::VoltVar(curve) => curve.evaluate(voltage),
+            ReactiveMode::WattVar(curve) => curve.evaluate(p),
+        };
+        let q = self.reactive_filter.update(q);
+
+        let (p, q) = limit(p, q, self.priority);
+        Pq {
+            p: Power::from(p * self.rating),
+            q: Power::from(q * self.rating),
+        }
+    }
+}
+
+// limits per-unit powers to a per-unit apparent power, reducing the power without priority first
+fn limit(p: Float, q: Float, priority: Priority) -> (Float, Float) {
+    if p * p + q * q <= 1.0 {
+        return (p, q);
+    }
+    let remainder = |x: Float| libm::Libm::<Float>::sqrt(1.0 - x * x);
+    match priority {
+        Priority::Active => {
+            let p = Float::clamp(p, -1.0, 1.0);
+            (p, Float::copysign(remainder(p), q))
+        }
+        Priority::Reactive => {
+            let q = Float::clamp(q, -1.0, 1.0);
+            (Float::copysign(remainder(q), p), q)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 0.01;
+
+    fn support() -> GridSupport {
+        GridSupport::new(
+            Power::from(1000.0),
+            Voltage::from(240.0),
+            Frequency::from(60.0),
+            TS,
+        )
+    }
+
+    // runs the functions for a duration, returning the last setpoint
+    fn run(
+        support: &mut GridSupport,
+        dispatch: Float,
+        voltage: Float,
+        frequency: Float,
+        seconds: Float,
+    ) -> (Float, Float) {
+        let mut pq = support.update(
+            dispatch.into(),
+            1000.0.into(),
+            (voltage * 240.0).into(),
+            frequency.into(),
+        );
+        for _ in 1..((seconds / TS + 0.5) as usize) {
+            pq = support.update(
+                dispatch.into(),
+                1000.0.into(),
+                (voltage * 240.0).into(),
+                frequency.into(),
+            );
+        }
+        (pq.p.into(), pq.q.into())
+    }
+
+    #[test]
+    fn curve() {
+        let curve = Curve::volt_var();
+        assert_abs_diff_eq!(curve.evaluate(0.8), 0.44);
+        assert_abs_diff_eq!(curve.evaluate(0.95), 0.22, epsilon = 1e-6);
+        assert_abs_diff_eq!(curve.evaluate(1.0), 0.0);
+        assert_abs_diff_eq!(curve.evaluate(1.05), -0.22, epsilon = 1e-6);
+        assert_abs_diff_eq!(curve.evaluate(1.2), -0.44);
+
+        // a vertical step takes the upper value
+        let step = Curve::new([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+        assert_abs_diff_eq!(step.evaluate(1.0), 0.0);
+        assert_abs_diff_eq!(step.evaluate(1.001), 1.0);
+    }
+
+    #[test]
+    fn volt_var_response_time() {
+        let mut support = support().with_reactive(ReactiveMode::VoltVar(Curve::volt_var()), 5.0);
+        run(&mut support, 500.0, 1.0, 60.0, 1.0);
+
+        // a step to 1.05pu reaches 90% of its final value at the response time
+        let (_, q) = run(&mut support, 500.0, 1.05, 60.0, 5.0);
+        assert_abs_diff_eq!(q, -0.9 * 220.0, epsilon = 1.0);
+        let (p, q) = run(&mut support, 500.0, 1.05, 60.0, 30.0);
+        assert_abs_diff_eq!(q, -220.0, epsilon = 0.1);
+        assert_abs_diff_eq!(p, 500.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn watt_var() {
+        let mut support = support().with_reactive(ReactiveMode::WattVar(Curve::watt_var()), 0.0);
+        let (_, q) = run(&mut support, 300.0, 1.0, 60.0, 0.1);
+        assert_abs_diff_eq!(q, 0.0);
+        let (_, q) = run(&mut support, 750.0, 1.0, 60.0, 0.1);
+        assert_abs_diff_eq!(q, -220.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn constant_power_factor() {
+        let mut support = support().with_reactive(
+            ReactiveMode::ConstantPowerFactor(0.9, Excitation::Absorbing),
+            0.0,
+        );
+        let (p, q) = run(&mut support, 600.0, 1.0, 60.0, 0.1);
+        let pf = p / libm::Libm::<Float>::hypot(p, q);
+        assert_abs_diff_eq!(pf, 0.9, epsilon = 1e-5);
+        assert!(q < 0.0);
+    }
+
+    #[test]
+    fn volt_watt() {
+        let mut support = support().with_volt_watt(Curve::volt_watt(), 0.0);
+        let (p, _) = run(&mut support, 900.0, 1.05, 60.0, 0.1);
+        assert_abs_diff_eq!(p, 900.0, epsilon = 0.01);
+        let (p, _) = run(&mut support, 900.0, 1.08, 60.0, 0.1);
+        assert_abs_diff_eq!(p, 600.0, epsilon = 0.1);
+        let (p, _) = run(&mut support, 100.0, 1.08, 60.0, 0.1);
+        assert_abs_diff_eq!(p, 100.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn frequency_droop() {
+        let mut support = support().with_frequency_droop(FrequencyDroop::default(), 0.0);
+
+        // inside the deadband
+        let (p, _) = run(&mut support, 500.0, 1.0, 60.03, 0.1);
+        assert_abs_diff_eq!(p, 500.0, epsilon = 0.01);
+
+        // 0.5Hz beyond the deadband is 1/6 pu with 5% droop
+        let (p, _) = run(&mut support, 500.0, 1.0, 60.536, 0.1);
+        assert_abs_diff_eq!(p, 500.0 - 1000.0 / 6.0, epsilon = 0.5);
+
+        // under-frequency is limited by the available power
+        let (p, _) = run(&mut support, 500.0, 1.0, 59.464, 0.1);
+        assert_abs_diff_eq!(p, 500.0 + 1000.0 / 6.0, epsilon = 0.5);
+        let (p, _) = run(&mut support, 950.0, 1.0, 59.0, 0.1);
+        assert_abs_diff_eq!(p, 1000.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn apparent_power_limit() {
+        let mut support = support().with_reactive(ReactiveMode::ConstantVar(0.6), 0.0);
+        let (p, q) = run(&mut support, 1000.0, 1.0, 60.0, 0.1);
+        assert_abs_diff_eq!(p, 800.0, epsilon = 0.1);
+        assert_abs_diff_eq!(q, 600.0, epsilon = 0.1);
+
+        let mut support = self::support()
+            .with_reactive(ReactiveMode::ConstantVar(-0.6), 0.0)
+            .with_priority(Priority::Active);
+        let (p, q) = run(&mut support, 900.0, 1.0, 60.0, 0.1);
+        assert_abs_diff_eq!(p, 900.0, epsilon = 0.1);
+        assert_abs_diff_eq!(q, -435.9, epsilon = 0.1);
+    }
+}
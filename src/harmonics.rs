@@ -0,0 +1,479 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Harmonic analyzers for three-phase signals
+
+Both analyzers measure selected harmonic orders of a signal sampled synchronously with its fundamental (i.e. a whole
+number of fundamental cycles per window).
+
+1. [SlidingDft] - Updates every harmonic with every sample, over a window of the last `N` samples
+2. [Goertzel] - Calculates every harmonic once per block of samples, without storing the samples
+
+Each returns [Phasors] per harmonic order, which can be read per phase or per symmetrical [Sequence].  The phasors use
+the same convention as [Waveform](crate::wavegen::Waveform), so a waveform's harmonics can be recovered exactly.
+
+```rust
+use ac_power::harmonics::SlidingDft;
+use ac_power::number::Float;
+use ac_power::trig::Theta;
+use ac_power::wavegen::{Magnitude, Sequence, Waveform};
+use approx::assert_abs_diff_eq;
+
+// a fundamental with a 5% 5th harmonic, sampled 64 times per cycle
+let waveform: Waveform<Float, 5> = Waveform::new()
+    .with_harmonic(1, Sequence::Positive, Magnitude::Absolute(1.0), Theta::from(0))
+    .with_harmonic(5, Sequence::Negative, Magnitude::Percent(5.0), Theta::from(0));
+
+let mut dft: SlidingDft<Float, 64, 2> = SlidingDft::new([1, 5], 1);
+let mut theta = Theta::from(0);
+for _ in 0..64 {
+    dft.update(waveform.calculate(theta));
+    theta += Theta::from_degrees(360.0 / 64.0);
+}
+
+let fifth = dft.harmonics()[1].sequence(Sequence::Negative);
+assert_abs_diff_eq!(fifth.amplitude, 0.05, epsilon = 0.0001);
+```
+*/
+
+use crate::number::{Float, Num};
+use crate::reference_frames::{Abc, AlphaBeta, Dq, Polar};
+use crate::trig::{
+    chebyshev, cos_sin, shift_left_120, shift_right_120, Backend, Cos, Libm, Sin, Theta,
+};
+use crate::wavegen::Sequence;
+
+/// Phasors of one harmonic order of a three-phase signal
+///
+/// Each phasor holds its real part in d and its imaginary part in q, so a phase with an amplitude A and phase φ is
+/// A·sin(nθ + φ), the same as a [Waveform](crate::wavegen::Waveform) harmonic.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Phasors<T> {
+    pub order: usize,
+    pub a: Dq<T>,
+    pub b: Dq<T>,
+    pub c: Dq<T>,
+}
+
+impl<T: Num> Phasors<T> {
+    fn zero(order: usize) -> Self {
+        Self {
+            order,
+            a: Dq::zero(),
+            b: Dq::zero(),
+            c: Dq::zero(),
+        }
+    }
+
+    /// Amplitude and phase of each phase
+    pub fn polar(&self) -> Abc<Polar<T>> {
+        Abc {
+            a: Polar::from(self.a),
+            b: Polar::from(self.b),
+            c: Polar::from(self.c),
+        }
+    }
+
+    /// Symmetrical component of the harmonic, using the Fortescue transform
+    pub fn sequence(&self, sequence: Sequence) -> Polar<T> {
+        let (cos, sin) = (Cos::from(1.0), Sin::from(0.0));
+        let (b, c) = match sequence {
+            Sequence::Positive => {
+                let (cos_p, sin_p) = shift_right_120(cos, sin);
+                let (cos_m, sin_m) = shift_left_120(cos, sin);
+                (self.b.rotate(cos_p, sin_p), self.c.rotate(cos_m, sin_m))
+            }
+            Sequence::Negative => {
+                let (cos_p, sin_p) = shift_right_120(cos, sin);
+                let (cos_m, sin_m) = shift_left_120(cos, sin);
+                (self.b.rotate(cos_m, sin_m), self.c.rotate(cos_p, sin_p))
+            }
+            Sequence::Zero => (self.b, self.c),
+        };
+        let sum = self.a + b + c;
+        Polar::from(Dq {
+            d: sum.d * (1.0 / 3.0),
+            q: sum.q * (1.0 / 3.0),
+        })
+    }
+}
+
+// cos and sin of each order times theta, from the cos and sin of theta and the chebyshev recursion
+fn harmonic_cos_sin<const H: usize>(cos: Cos, sin: Sin, orders: &[usize; H]) -> [(Cos, Sin); H] {
+    let max = orders.iter().copied().max().unwrap_or(0);
+
+    let mut result = [(Cos::from(1.0), Sin::from(0.0)); H];
+    let (mut cos2, mut sin2) = (Cos::from(1.0), Sin::from(0.0));
+    let (mut cos1, mut sin1) = (cos, sin);
+    for n in 1..=max {
+        for (order, result) in orders.iter().zip(result.iter_mut()) {
+            if *order == n {
+                *result = (cos1, sin1);
+            }
+        }
+        let (cosn, sinn) = chebyshev(cos, cos1, sin1, cos2, sin2);
+        (cos2, sin2) = (cos1, sin1);
+        (cos1, sin1) = (cosn, sinn);
+    }
+    result
+}
+
+// phase of the fundamental at sample n of a window of `samples` covering `cycles` fundamental cycles
+fn window_theta(n: usize, cycles: usize, samples: usize) -> Theta {
+    let fraction = ((n * cycles) % samples) as u64;
+    Theta::from(((fraction << 32) / (samples as u64)) as u32 as i32)
+}
+
+// converts a dft bin (re, im) into a phasor, scaled to the peak amplitude and referenced to sin
+fn to_phasor<T: Num>(re: T, im: T, scale: Float) -> Dq<T> {
+    Dq {
+        d: -im * scale,
+        q: re * scale,
+    }
+}
+
+/// A sliding DFT over the last `N` samples, which updates `H` harmonic orders every sample
+///
+/// The window covers `cycles` fundamental cycles, so harmonic order n is DFT bin n·cycles.  The bins are accumulated
+/// against fixed twiddles (rather than rotated every sample), so rounding errors do not grow exponentially.
+pub struct SlidingDft<T, const N: usize, const H: usize> {
+    orders: [usize; H],
+    cycles: usize,
+    window: [Abc<T>; N],
+    index: usize,
+    bins: [Phasors<T>; H],
+}
+
+impl<T: Num, const N: usize, const H: usize> SlidingDft<T, N, H> {
+    pub fn new(orders: [usize; H], cycles: usize) -> Self {
+        assert!(
+            orders.iter().all(|order| *order > 0),
+            "harmonic order must be positive"
+        );
+        assert!(
+            orders.iter().all(|order| 2 * order * cycles < N),
+            "harmonic order above the nyquist frequency"
+        );
+
+        Self {
+            orders,
+            cycles,
+            window: [Abc::zero(); N],
+            index: 0,
+            bins: orders.map(Phasors::zero),
+        }
+    }
+
+    /// Adds a sample, replacing the oldest sample in the window
+    pub fn update(&mut self, abc: Abc<T>) {
+        let (cos, sin) = cos_sin(window_theta(self.index, self.cycles, N));
+        let twiddles = harmonic_cos_sin(cos, sin, &self.orders);
+        let delta = abc - self.window[self.index];
+        self.window[self.index] = abc;
+
+        // X += delta * e^(-j*n*theta), with d and q holding the real and imaginary parts
+        for (bin, (cos, sin)) in self.bins.iter_mut().zip(twiddles.iter()) {
+            bin.a.d += delta.a * *cos;
+            bin.a.q += -(delta.a * *sin);
+            bin.b.d += delta.b * *cos;
+            bin.b.q += -(delta.b * *sin);
+            bin.c.d += delta.c * *cos;
+            bin.c.q += -(delta.c * *sin);
+        }
+
+        self.index = (self.index + 1) % N;
+    }
+
+    /// Adds a sample of an alpha-beta signal
+    pub fn update_alpha_beta(&mut self, alpha_beta: AlphaBeta<T>) {
+        self.update(Abc::from(alpha_beta));
+    }
+
+    /// Phasors of each harmonic order, with the phase referenced to the next sample
+    pub fn harmonics(&self) -> [Phasors<T>; H] {
+        // the oldest sample in the window is a whole number of cycles before the next sample
+        let (cos, sin) = cos_sin(window_theta(self.index, self.cycles, N));
+        let twiddles = harmonic_cos_sin(cos, sin, &self.orders);
+        let scale = 2.0 / (N as Float);
+
+        let mut harmonics = self.bins;
+        for (harmonic, (cos, sin)) in harmonics.iter_mut().zip(twiddles.iter()) {
+            for phase in [&mut harmonic.a, &mut harmonic.b, &mut harmonic.c] {
+                let bin = phase.rotate(*cos, *sin);
+                *phase = to_phasor(bin.d, bin.q, scale);
+            }
+        }
+        harmonics
+    }
+}
+
+/// A bank of Goertzel filters, which calculates `H` harmonic orders over each block of samples
+///
+/// Each block is `samples` long and covers `cycles` fundamental cycles.  Unlike the [SlidingDft], the samples are not
+/// stored.
+pub struct Goertzel<T, const H: usize> {
+    orders: [usize; H],
+    samples: usize,
+    twiddles: [(Cos, Sin); H],
+    // (s[n-1], s[n-2]) for each phase
+    state: [Abc<(T, T)>; H],
+    count: usize,
+}
+
+impl<T: Num, const H: usize> Goertzel<T, H> {
+    pub fn new(orders: [usize; H], cycles: usize, samples: usize) -> Self {
+        assert!(
+            orders.iter().all(|order| *order > 0),
+            "harmonic order must be positive"
+        );
+        assert!(
+            orders.iter().all(|order| 2 * order * cycles < samples),
+            "harmonic order above the nyquist frequency"
+        );
+
+        // the filters are very sensitive to errors in the coefficients (~1e-5 with the default idsp trig), so use
+        // libm for the single cos_sin that sets them up
+        let (cos, sin) = Libm::cos_sin(window_theta(1, cycles, samples));
+
+        Self {
+            orders,
+            samples,
+            twiddles: harmonic_cos_sin(cos, sin, &orders),
+            state: [Self::zero_state(); H],
+            count: 0,
+        }
+    }
+
+    fn zero_state() -> Abc<(T, T)> {
+        let zero = (T::from(0.0), T::from(0.0));
+        Abc {
+            a: zero,
+            b: zero,
+            c: zero,
+        }
+    }
+
+    /// Adds a sample, returning the phasors of each harmonic order at the end of each block
+    ///
+    /// The phase is referenced to the first sample of the block (and so the sample after the block).
+    pub fn update(&mut self, abc: Abc<T>) -> Option<[Phasors<T>; H]> {
+        // s[n] = x[n] + 2cos(w)s[n-1] - s[n-2]
+        for (state, (cos, _)) in self.state.iter_mut().zip(self.twiddles.iter()) {
+            for (s, x) in [
+                (&mut state.a, abc.a),
+                (&mut state.b, abc.b),
+                (&mut state.c, abc.c),
+            ] {
+                *s = (x + s.0 * *cos * 2.0 - s.1, s.0);
+            }
+        }
+
+        self.count += 1;
+        if self.count < self.samples {
+            return None;
+        }
+
+        // X = e^(jw)s[N-1] - s[N-2]
+        let scale = 2.0 / (self.samples as Float);
+        let mut harmonics = self.orders.map(Phasors::zero);
+        for ((harmonic, state), (cos, sin)) in harmonics
+            .iter_mut()
+            .zip(self.state.iter())
+            .zip(self.twiddles.iter())
+        {
+            for (phase, s) in [
+                (&mut harmonic.a, state.a),
+                (&mut harmonic.b, state.b),
+                (&mut harmonic.c, state.c),
+            ] {
+                *phase = to_phasor(s.0 * *cos - s.1, s.0 * *sin, scale);
+            }
+        }
+
+        self.state = [Self::zero_state(); H];
+        self.count = 0;
+        Some(harmonics)
+    }
+
+    /// Adds a sample of an alpha-beta signal
+    pub fn update_alpha_beta(&mut self, alpha_beta: AlphaBeta<T>) -> Option<[Phasors<T>; H]> {
+        self.update(Abc::from(alpha_beta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::wavegen::{Magnitude, Waveform};
+    use approx::assert_abs_diff_eq;
+
+    const SAMPLES: usize = 128;
+
+    fn distorted() -> Waveform<Float, 7> {
+        Waveform::new()
+            .with_harmonic(
+                1,
+                Sequence::Positive,
+                Magnitude::Absolute(1.0),
+                Theta::from_degrees(10.0),
+            )
+            .with_harmonic(
+                1,
+                Sequence::Negative,
+                Magnitude::Percent(2.0),
+                Theta::from_degrees(-30.0),
+            )
+            .with_harmonic(
+                3,
+                Sequence::Zero,
+                Magnitude::Percent(3.0),
+                Theta::from_degrees(45.0),
+            )
+            .with_harmonic(
+                5,
+                Sequence::Negative,
+                Magnitude::Percent(5.0),
+                Theta::from_degrees(120.0),
+            )
+            .with_harmonic(
+                7,
+                Sequence::Positive,
+                Magnitude::Percent(4.0),
+                Theta::from_degrees(-100.0),
+            )
+    }
+
+    // compares phasors as vectors, since the phase of a small harmonic is sensitive to noise
+    fn check_polar(polar: Polar<Float>, expected: Polar<Float>) {
+        let (dq, expected) = (Dq::from(polar), Dq::from(expected));
+        assert_abs_diff_eq!(dq.d, expected.d, epsilon = 0.0002);
+        assert_abs_diff_eq!(dq.q, expected.q, epsilon = 0.0002);
+    }
+
+    fn check_harmonics(harmonics: &[Phasors<Float>], waveform: &Waveform<Float, 7>) {
+        for harmonic in harmonics {
+            for sequence in [Sequence::Positive, Sequence::Negative, Sequence::Zero] {
+                check_polar(
+                    harmonic.sequence(sequence),
+                    waveform.harmonic(harmonic.order, sequence),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sliding_dft() {
+        let waveform = distorted();
+        let step = Theta::from_degrees(360.0 / SAMPLES as Float);
+        let mut dft: SlidingDft<Float, SAMPLES, 5> = SlidingDft::new([1, 2, 3, 5, 7], 1);
+
+        // once the window is full, the phases are referenced to the next sample
+        let mut theta = Theta::from(0);
+        for n in 0..1000 * SAMPLES {
+            dft.update(waveform.calculate(theta));
+            theta += step;
+            if n >= SAMPLES && n % 37 == 0 {
+                for harmonic in dft.harmonics() {
+                    let shift = Theta::from(i32::from(theta).wrapping_mul(harmonic.order as i32));
+                    for sequence in [Sequence::Positive, Sequence::Negative, Sequence::Zero] {
+                        let expected = waveform.harmonic(harmonic.order, sequence);
+                        let expected = Polar {
+                            amplitude: expected.amplitude,
+                            theta: expected.theta + shift,
+                        };
+                        check_polar(harmonic.sequence(sequence), expected);
+                    }
+                }
+            }
+        }
+
+        // after a whole number of cycles the phase reference is back to theta = 0
+        check_harmonics(&dft.harmonics(), &waveform);
+    }
+
+    #[test]
+    fn goertzel() {
+        let waveform = distorted();
+        let step = Theta::from_degrees(360.0 / (2 * SAMPLES) as Float);
+        let mut goertzel: Goertzel<Float, 5> = Goertzel::new([1, 2, 3, 5, 7], 2, 2 * SAMPLES);
+
+        let mut theta = Theta::from(0);
+        let mut blocks = 0;
+        for _ in 0..3 {
+            for _ in 0..2 * SAMPLES {
+                if let Some(harmonics) = goertzel.update(waveform.calculate(theta)) {
+                    check_harmonics(&harmonics, &waveform);
+                    blocks += 1;
+                }
+                theta += step + step;
+            }
+        }
+        assert_eq!(blocks, 3);
+    }
+
+    #[test]
+    fn alpha_beta() {
+        let waveform = distorted();
+        let step = Theta::from_degrees(360.0 / SAMPLES as Float);
+        let mut dft: SlidingDft<Float, SAMPLES, 2> = SlidingDft::new([1, 5], 1);
+        let mut theta = Theta::from(0);
+        for _ in 0..SAMPLES {
+            dft.update_alpha_beta(AlphaBeta::from(waveform.calculate(theta)));
+            theta += step;
+        }
+        for harmonic in dft.harmonics() {
+            for sequence in [Sequence::Positive, Sequence::Negative] {
+                check_polar(
+                    harmonic.sequence(sequence),
+                    waveform.harmonic(harmonic.order, sequence),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn per_phase() {
+        let waveform: Waveform<Float, 1> = Waveform::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(2.0),
+            Theta::from_degrees(30.0),
+        );
+        let step = Theta::from_degrees(360.0 / SAMPLES as Float);
+        let mut goertzel: Goertzel<Float, 1> = Goertzel::new([1], 1, SAMPLES);
+        let mut theta = Theta::from(0);
+        let mut harmonics = None;
+        while harmonics.is_none() {
+            harmonics = goertzel.update(waveform.calculate(theta));
+            theta += step;
+        }
+        let polar = harmonics.unwrap()[0].polar();
+        let expected = |degrees| Polar {
+            amplitude: 2.0,
+            theta: Theta::from_degrees(degrees),
+        };
+        check_polar(polar.a, expected(30.0));
+        check_polar(polar.b, expected(-90.0));
+        check_polar(polar.c, expected(150.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn nyquist() {
+        let _: SlidingDft<Float, 16, 1> = SlidingDft::new([8], 1);
+    }
+}
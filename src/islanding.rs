@@ -0,0 +1,684 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Anti-islanding detection
+
+Passive detectors watch the point of common coupling for the changes that follow the loss of the grid.  Each implements
+[IslandingDetector], takes a [Measurement] (from the PLL and power quality measurements) every sample, and latches once
+an island is detected.
+
+1. [RocofDetector] - The rate of change of frequency exceeds a threshold for a duration
+2. [VectorShiftDetector] - The voltage angle jumps, measured against the angle advanced over the previous cycle
+3. [WindowDetector] - The voltage or frequency leaves a window for a duration
+4. [HarmonicChangeDetector] - The voltage THD rises above its recent baseline
+
+A load matched to the inverter's output leaves the voltage and frequency unchanged when the grid is lost, so the active
+methods perturb the inverter's output to drive an island out of the passive windows, while the grid holds them.
+
+1. [SandiaFrequencyShift] - Phase offset of the current reference, with positive feedback from frequency
+2. [SandiaVoltageShift] - Active power offset, with positive feedback from voltage
+3. [ReactivePerturbation] - Periodic reactive power offset
+
+```rust
+use ac_power::islanding::{IslandingDetector, Measurement, WindowDetector};
+use ac_power::trig::Theta;
+use ac_power::{Frequency, Rocof, Voltage};
+
+let mut detector = WindowDetector::new(Voltage::from(240.0), (0.88, 1.1), (59.3, 60.5), 0.1, 1e-3);
+
+let mut measurement = Measurement {
+    voltage: Voltage::from(240.0),
+    frequency: Frequency::from(60.0),
+    rocof: Rocof::from(0.0),
+    theta: Theta::from(0),
+    thd: 0.01,
+};
+assert!(!detector.update(&measurement));
+
+// the frequency of the island drifts out of the window
+measurement.frequency = Frequency::from(60.7);
+let detected = (0..100).map(|_| detector.update(&measurement)).last().unwrap();
+assert!(detected);
+```
+*/
+
+use crate::constants::PI;
+use crate::newtypes::{Frequency, Power, Rocof, Voltage};
+use crate::number::Float;
+use crate::pq::Pq;
+use crate::trig::Theta;
+
+/// Measurements at the point of common coupling for a sample
+#[derive(Debug, Copy, Clone)]
+pub struct Measurement {
+    /// rms voltage
+    pub voltage: Voltage,
+    pub frequency: Frequency,
+    pub rocof: Rocof,
+    /// Angle of the voltage
+    pub theta: Theta,
+    /// Voltage total harmonic distortion (as a fraction of the fundamental)
+    pub thd: Float,
+}
+
+/// A passive islanding detector
+pub trait IslandingDetector {
+    /// Updates the detector with a measurement, returning true once an island has been detected
+    fn update(&mut self, measurement: &Measurement) -> bool;
+
+    /// Whether an island has been detected
+    fn detected(&self) -> bool;
+
+    /// Clears a latched detection
+    fn reset(&mut self);
+}
+
+// counts the samples a condition has held for
+#[derive(Debug, Copy, Clone)]
+struct Timer {
+    duration: Float,
+    ts: Float,
+    count: u32,
+}
+
+impl Timer {
+    fn new(duration: Float, ts: Float) -> Self {
+        Self {
+            duration,
+            ts,
+            count: 0,
+        }
+    }
+
+    fn update(&mut self, condition: bool) -> bool {
+        if condition {
+            self.count = self.count.saturating_add(1);
+            (self.count as Float) * self.ts >= self.duration
+        } else {
+            self.count = 0;
+            false
+        }
+    }
+}
+
+/// Detects a rate of change of frequency beyond a threshold
+#[derive(Debug, Copy, Clone)]
+pub struct RocofDetector {
+    threshold: Float,
+    timer: Timer,
+    detected: bool,
+}
+
+impl RocofDetector {
+    /// Creates a detector for a ROCOF magnitude which must be exceeded for a duration (in seconds), updated every `ts`
+    /// seconds
+    pub fn new(threshold: Rocof, duration: Float, ts: Float) -> Self {
+        Self {
+            threshold: threshold.into(),
+            timer: Timer::new(duration, ts),
+            detected: false,
+        }
+    }
+}
+
+impl IslandingDetector for RocofDetector {
+    fn update(&mut self, measurement: &Measurement) -> bool {
+        let rocof = Float::from(measurement.rocof);
+        self.detected |= self.timer.update(Float::abs(rocof) > self.threshold);
+        self.detected
+    }
+
+    fn detected(&self) -> bool {
+        self.detected
+    }
+
+    fn reset(&mut self) {
+        self.timer.count = 0;
+        self.detected = false;
+    }
+}
+
+/// Detects a jump in the voltage angle, with `N` samples per nominal cycle
+///
+/// The angle advanced each sample is compared with its average over the previous cycle, so a steady frequency offset
+/// is ignored, and a jump is measured in the sample it occurs.
+#[derive(Debug, Copy, Clone)]
+pub struct VectorShiftDetector<const N: usize> {
+    threshold: u32,
+    nominal: Theta,
+    // deviations of the angle advanced from nominal, for the previous cycle
+    deviations: [Theta; N],
+    sum: Theta,
+    previous: Theta,
+    index: usize,
+    // samples seen, up to the cycle needed for a measurement
+    filled: usize,
+    shift: Theta,
+    detected: bool,
+}
+
+impl<const N: usize> VectorShiftDetector<N> {
+    /// Creates a detector for a vector shift magnitude
+    pub fn new(threshold: Theta) -> Self {
+        Self {
+            threshold: i32::from(threshold).unsigned_abs(),
+            // a full circle is 2^32, split into N steps rounded to the nearest count
+            nominal: Theta::from((((1u64 << 32) + N as u64 / 2) / N as u64) as i32),
+            deviations: [Theta::from(0); N],
+            sum: Theta::from(0),
+            previous: Theta::from(0),
+            index: 0,
+            filled: 0,
+            shift: Theta::from(0),
+            detected: false,
+        }
+    }
+
+    /// The last vector shift measured
+    pub fn shift(&self) -> Theta {
+        self.shift
+    }
+}
+
+impl<const N: usize> IslandingDetector for VectorShiftDetector<N> {
+    fn update(&mut self, measurement: &Measurement) -> bool {
+        let deviation = measurement.theta - self.previous - self.nominal;
+        let average = i32::from(self.sum) / N as i32;
+        self.shift = deviation - Theta::from(average);
+
+        self.previous = measurement.theta;
+        self.sum = self.sum - self.deviations[self.index] + deviation;
+        self.deviations[self.index] = deviation;
+        self.index = (self.index + 1) % N;
+
+        if self.filled <= N {
+            self.filled += 1;
+            self.shift = Theta::from(0);
+        } else if i32::from(self.shift).unsigned_abs() > self.threshold {
+            self.detected = true;
+        }
+        self.detected
+    }
+
+    fn detected(&self) -> bool {
+        self.detected
+    }
+
+    fn reset(&mut self) {
+        self.detected = false;
+    }
+}
+
+/// Detects the voltage or frequency leaving a window
+#[derive(Debug, Copy, Clone)]
+pub struct WindowDetector {
+    nominal: Float,
+    voltage: (Float, Float),
+    frequency: (Float, Float),
+    timer: Timer,
+    detected: bool,
+}
+
+impl WindowDetector {
+    /// Creates a detector for a nominal rms voltage, windows of (under, over) voltage in per-unit and frequency in Hz,
+    /// which must be left for a duration (in seconds), updated every `ts` seconds
+    pub fn new(
+        nominal: Voltage,
+        voltage: (Float, Float),
+        frequency: (Float, Float),
+        duration: Float,
+        ts: Float,
+    ) -> Self {
+        Self {
+            nominal: nominal.into(),
+            voltage,
+            frequency,
+            timer: Timer::new(duration, ts),
+            detected: false,
+        }
+    }
+}
+
+impl IslandingDetector for WindowDetector {
+    fn update(&mut self, measurement: &Measurement) -> bool {
+        let voltage = Float::from(measurement.voltage) / self.nominal;
+        let frequency = Float::from(measurement.frequency);
+        let outside = voltage < self.voltage.0
+            || voltage > self.voltage.1
+            || frequency < self.frequency.0
+            || frequency > self.frequency.1;
+        self.detected |= self.timer.update(outside);
+        self.detected
+    }
+
+    fn detected(&self) -> bool {
+        self.detected
+    }
+
+    fn reset(&mut self) {
+        self.timer.count = 0;
+        self.detected = false;
+    }
+}
+
+/// Detects a rise in voltage THD above a baseline, which follows the THD with a time constant
+#[derive(Debug, Copy, Clone)]
+pub struct HarmonicChangeDetector {
+    threshold: Float,
+    alpha: Float,
+    baseline: Option<Float>,
+    detected: bool,
+}
+
+impl HarmonicChangeDetector {
+    /// Creates a detector for a rise in THD, with the time constant of the baseline (in seconds), updated every `ts`
+    /// seconds
+    pub fn new(threshold: Float, time_constant: Float, ts: Float) -> Self {
+        Self {
+            threshold,
+            alpha: ts / (time_constant + ts),
+            baseline: None,
+            detected: false,
+        }
+    }
+}
+
+impl IslandingDetector for HarmonicChangeDetector {
+    fn update(&mut self, measurement: &Measurement) -> bool {
+        let thd = measurement.thd;
+        let baseline = self.baseline.unwrap_or(thd);
+        if thd - baseline > self.threshold {
+            self.detected = true;
+        }
+        self.baseline = Some(baseline + (thd - baseline) * self.alpha);
+        self.detected
+    }
+
+    fn detected(&self) -> bool {
+        self.detected
+    }
+
+    fn reset(&mut self) {
+        self.baseline = None;
+        self.detected = false;
+    }
+}
+
+/// Sandia frequency shift, which offsets the phase of the current reference by a chopping fraction
+/// `cf = cf0 + gain * (f - nominal)`
+#[derive(Debug, Copy, Clone)]
+pub struct SandiaFrequencyShift {
+    cf0: Float,
+    gain: Float,
+    nominal: Float,
+}
+
+impl SandiaFrequencyShift {
+    /// Creates the frequency shift for a chopping fraction at nominal frequency, and a gain (per Hz)
+    pub fn new(cf0: Float, gain: Float, nominal: Frequency) -> Self {
+        Self {
+            cf0,
+            gain,
+            nominal: nominal.into(),
+        }
+    }
+
+    /// Calculates the phase offset (a lead) of the current reference for the measured frequency
+    pub fn update(&self, frequency: Frequency) -> Theta {
+        let cf = self.cf0 + self.gain * (Float::from(frequency) - self.nominal);
+        let cf = Float::clamp(cf, -1.0, 1.0);
+        // the fundamental of a current chopped for a fraction of each half cycle is delayed by half the chopped time
+        Theta::from_radians(cf * PI / 2.0)
+    }
+}
+
+/// Sandia voltage shift, which offsets the active power by `gain * (V - nominal) / nominal` of the active power
+#[derive(Debug, Copy, Clone)]
+pub struct SandiaVoltageShift {
+    gain: Float,
+    nominal: Float,
+}
+
+impl SandiaVoltageShift {
+    /// Creates the voltage shift for a gain and nominal rms voltage
+    pub fn new(gain: Float, nominal: Voltage) -> Self {
+        Self {
+            gain,
+            nominal: nominal.into(),
+        }
+    }
+
+    /// Calculates the power offset for the measured rms voltage and active power
+    pub fn update(&self, voltage: Voltage, p: Power) -> Pq {
+        let deviation = (Float::from(voltage) - self.nominal) / self.nominal;
+        Pq {
+            p: p * (self.gain * deviation),
+            q: Power::from(0.0),
+        }
+    }
+}
+
+/// Reactive power perturbation, a square wave of reactive power
+#[derive(Debug, Copy, Clone)]
+pub struct ReactivePerturbation {
+    amplitude: Power,
+    half_period: u32,
+    count: u32,
+}
+
+impl ReactivePerturbation {
+    /// Creates the perturbation for an amplitude and period (in seconds), updated every `ts` seconds
+    pub fn new(amplitude: Power, period: Float, ts: Float) -> Self {
+        let half_period = ((period / (2.0 * ts)) + 0.5) as u32;
+        Self {
+            amplitude,
+            half_period: half_period.max(1),
+            count: 0,
+        }
+    }
+
+    /// Calculates the power offset for the next sample
+    pub fn update(&mut self) -> Pq {
+        let q = if self.count < self.half_period {
+            self.amplitude
+        } else {
+            -self.amplitude
+        };
+        self.count = (self.count + 1) % (2 * self.half_period);
+        Pq {
+            p: Power::from(0.0),
+            q,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::tests::power;
+    use approx::assert_abs_diff_eq;
+
+    // 100 samples per cycle
+    const TS: Float = 1.0 / 6000.0;
+    const NOMINAL: Float = 240.0;
+    const F0: Float = 60.0;
+    const CYCLE: usize = 100;
+
+    // a parallel RLC load, and an inverter current source, which islands at 0.5s
+    //
+    // This is a quasi-static (phasor) model: after islanding, the voltage and frequency relax towards the steady
+    // state of the load fed by the inverter's current, and the voltage angle jumps when the grid is lost.
+    struct Island {
+        r: Float,
+        l: Float,
+        c: Float,
+        // third harmonic of the inverter current (a fraction of the fundamental)
+        harmonic: Float,
+        time: Float,
+        voltage: Float,
+        frequency: Float,
+        rocof: Float,
+        theta: Float,
+    }
+
+    impl Island {
+        // a load consuming `power` at nominal voltage, resonant at nominal frequency with a quality factor
+        fn new(power: Float, quality: Float) -> Self {
+            let r = NOMINAL * NOMINAL / power;
+            let w0 = 2.0 * PI * F0;
+            Self {
+                r,
+                l: r / (w0 * quality),
+                c: quality / (w0 * r),
+                harmonic: 0.05,
+                time: 0.0,
+                voltage: NOMINAL,
+                frequency: F0,
+                rocof: 0.0,
+                theta: 0.0,
+            }
+        }
+
+        fn susceptance(&self, w: Float) -> Float {
+            w * self.c - 1.0 / (w * self.l)
+        }
+
+        fn impedance(&self, w: Float) -> Float {
+            1.0 / libm::Libm::<Float>::hypot(1.0 / self.r, self.susceptance(w))
+        }
+
+        // steps the model with the inverter's power setpoint (for a current at nominal voltage) and phase lead
+        fn step(&mut self, pq: Pq, lead: Theta) -> Measurement {
+            let (p, q): (Float, Float) = (pq.p.into(), pq.q.into());
+            let (sin, cos) = libm::Libm::<Float>::sincos(lead.to_radians());
+            // current relative to the voltage angle, I = (P - jQ) / V
+            let (re, im) = (p / NOMINAL, -q / NOMINAL);
+            let (re, im) = (re * cos - im * sin, re * sin + im * cos);
+
+            let islanded = self.time >= 0.5;
+            let w = 2.0 * PI * self.frequency;
+            let (voltage, frequency, thd) = if islanded {
+                if self.time - TS < 0.5 {
+                    // the voltage angle jumps to that of the current through the load
+                    let load = libm::Libm::<Float>::atan2(self.r * self.susceptance(w), 1.0);
+                    self.theta += libm::Libm::<Float>::atan2(im, re) - load;
+                }
+                // solve the load for the voltage, and for the frequency which matches the current's angle
+                let voltage = re * self.r;
+                let b = im / voltage;
+                let w =
+                    (b + libm::Libm::<Float>::sqrt(b * b + 4.0 * self.c / self.l)) / (2.0 * self.c);
+                let thd = self.harmonic * self.impedance(3.0 * w) / self.impedance(w);
+                (voltage, w / (2.0 * PI), thd)
+            } else {
+                (NOMINAL, F0, 0.005)
+            };
+
+            // relax towards the steady state
+            let alpha = TS / 0.02;
+            let previous = self.frequency;
+            self.voltage += (voltage - self.voltage) * alpha;
+            self.frequency += (frequency - self.frequency) * alpha;
+            self.rocof = (self.frequency - previous) / TS;
+            self.theta += 2.0 * PI * self.frequency * TS;
+            self.theta %= 2.0 * PI;
+            self.time += TS;
+
+            Measurement {
+                voltage: self.voltage.into(),
+                frequency: self.frequency.into(),
+                rocof: self.rocof.into(),
+                theta: Theta::from_radians(self.theta),
+                thd,
+            }
+        }
+    }
+
+    struct Detectors {
+        rocof: RocofDetector,
+        vector_shift: VectorShiftDetector<CYCLE>,
+        window: WindowDetector,
+        harmonic: HarmonicChangeDetector,
+    }
+
+    impl Detectors {
+        fn new() -> Self {
+            Self {
+                rocof: RocofDetector::new(Rocof::from(2.0), 0.05, TS),
+                vector_shift: VectorShiftDetector::new(Theta::from_degrees(8.0)),
+                window: WindowDetector::new(NOMINAL.into(), (0.88, 1.1), (59.3, 60.5), 0.16, TS),
+                harmonic: HarmonicChangeDetector::new(0.01, 1.0, TS),
+            }
+        }
+
+        fn update(&mut self, measurement: &Measurement) {
+            let mut detectors: [&mut dyn IslandingDetector; 4] = [
+                &mut self.rocof,
+                &mut self.vector_shift,
+                &mut self.window,
+                &mut self.harmonic,
+            ];
+            for detector in detectors.iter_mut() {
+                detector.update(measurement);
+            }
+        }
+    }
+
+    // runs the island for 2s, returning the time at which each detector (rocof, vector shift, window, harmonic) first
+    // detected an island
+    fn run<F: FnMut(&Measurement) -> (Pq, Theta)>(
+        island: &mut Island,
+        mut inverter: F,
+    ) -> [Option<Float>; 4] {
+        let mut detectors = Detectors::new();
+        let mut times = [None; 4];
+        let mut measurement = island.step(power(0.0, 0.0), Theta::from(0));
+        for _ in 0..12000 {
+            let (pq, lead) = inverter(&measurement);
+            measurement = island.step(pq, lead);
+            detectors.update(&measurement);
+
+            let detected = [
+                detectors.rocof.detected(),
+                detectors.vector_shift.detected(),
+                detectors.window.detected(),
+                detectors.harmonic.detected(),
+            ];
+            for (time, &detected) in times.iter_mut().zip(detected.iter()) {
+                if detected && time.is_none() {
+                    *time = Some(island.time);
+                }
+            }
+        }
+        times
+    }
+
+    #[test]
+    fn matched_load() {
+        // the voltage and frequency don't change, only the harmonic voltage from the inverter's current is detected
+        let mut island = Island::new(1000.0, 1.0);
+        let times = run(&mut island, |_| (power(1000.0, 0.0), Theta::from(0)));
+        assert_eq!(times[..3], [None, None, None]);
+        assert_abs_diff_eq!(times[3].unwrap(), 0.5, epsilon = 2.0 * TS);
+        assert_abs_diff_eq!(island.frequency, F0, epsilon = 1e-3);
+        assert_abs_diff_eq!(island.voltage, NOMINAL, epsilon = 1e-2);
+    }
+
+    #[test]
+    fn reactive_mismatch() {
+        // the load absorbs 20% less reactive power than the inverter injects, so the frequency falls
+        let mut island = Island::new(1000.0, 1.0);
+        let times = run(&mut island, |_| (power(1000.0, 200.0), Theta::from(0)));
+        for time in times.iter() {
+            assert!(time.unwrap() < 0.7);
+        }
+        assert!(island.frequency < 59.3);
+    }
+
+    #[test]
+    fn vector_shift() {
+        let mut detector: VectorShiftDetector<CYCLE> =
+            VectorShiftDetector::new(Theta::from_degrees(5.0));
+        let mut measurement = Measurement {
+            voltage: NOMINAL.into(),
+            frequency: F0.into(),
+            rocof: 0.0.into(),
+            theta: Theta::from(0),
+            thd: 0.0,
+        };
+
+        // a steady frequency offset
+        let step = Theta::from_degrees(360.0 * 60.7 / 6000.0);
+        for _ in 0..1000 {
+            measurement.theta += step;
+            assert!(!detector.update(&measurement));
+        }
+        assert_abs_diff_eq!(detector.shift().to_degrees(), 0.0, epsilon = 1e-3);
+
+        // a jump is measured in the sample it occurs
+        measurement.theta += Theta::from_degrees(-6.0) + step;
+        assert!(detector.update(&measurement));
+        assert_abs_diff_eq!(detector.shift().to_degrees(), -6.0, epsilon = 1e-3);
+
+        detector.reset();
+        for _ in 0..CYCLE {
+            measurement.theta += step;
+            detector.update(&measurement);
+            assert_abs_diff_eq!(detector.shift().to_degrees(), 0.06, epsilon = 1e-3);
+        }
+        assert!(!detector.detected());
+    }
+
+    #[test]
+    fn sandia_frequency_shift() {
+        let sfs = SandiaFrequencyShift::new(0.01, 0.05, F0.into());
+        assert_abs_diff_eq!(sfs.update(F0.into()).to_degrees(), 0.9, epsilon = 1e-3);
+        assert_abs_diff_eq!(sfs.update(61.0.into()).to_degrees(), 5.4, epsilon = 1e-3);
+
+        // the positive feedback drives a matched island out of the frequency window
+        let mut island = Island::new(1000.0, 1.0);
+        let times = run(&mut island, |m| {
+            (power(1000.0, 0.0), sfs.update(m.frequency))
+        });
+        assert!(times[2].unwrap() < 1.0);
+        assert!(island.frequency > 60.5);
+
+        // but the grid holds the frequency
+        let mut island = Island::new(1000.0, 1.0);
+        let mut measurement = island.step(power(1000.0, 0.0), Theta::from(0));
+        for _ in 0..2000 {
+            measurement = island.step(power(1000.0, 0.0), sfs.update(measurement.frequency));
+        }
+        assert_abs_diff_eq!(Float::from(measurement.frequency), F0);
+    }
+
+    #[test]
+    fn sandia_voltage_shift() {
+        let svs = SandiaVoltageShift::new(3.0, NOMINAL.into());
+        let offset = svs.update(Voltage::from(NOMINAL * 0.9), Power::from(1000.0));
+        assert_abs_diff_eq!(Float::from(offset.p), -300.0, epsilon = 1e-3);
+        assert_abs_diff_eq!(Float::from(offset.q), 0.0);
+
+        // a 2% active power mismatch grows until the voltage leaves the window (limited by the inverter's rating)
+        let mut island = Island::new(1000.0, 1.0);
+        let times = run(&mut island, |m| {
+            let p = Power::from(1020.0);
+            let offset = svs.update(m.voltage, p);
+            let p = Float::min(Float::from(p + offset.p), 1500.0);
+            (power(p, 0.0), Theta::from(0))
+        });
+        assert!(times[2].unwrap() < 1.0);
+        assert!(island.voltage > 1.1 * NOMINAL);
+    }
+
+    #[test]
+    fn reactive_perturbation() {
+        let mut perturbation = ReactivePerturbation::new(Power::from(50.0), 0.5, TS);
+        let q: Vec<Float> = (0..3000).map(|_| perturbation.update().q.into()).collect();
+        assert!(q[..1500].iter().all(|&q| q == 50.0));
+        assert!(q[1500..].iter().all(|&q| q == -50.0));
+
+        // the perturbation swings the frequency of a matched island out of the window
+        let mut perturbation = ReactivePerturbation::new(Power::from(50.0), 0.5, TS);
+        let mut island = Island::new(1000.0, 1.0);
+        let times = run(&mut island, |_| {
+            let offset = perturbation.update();
+            (power(1000.0, offset.q.into()), Theta::from(0))
+        });
+        assert!(times[2].unwrap() < 1.0);
+    }
+}
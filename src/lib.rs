@@ -16,17 +16,64 @@
 #![doc = include_str!("../README.md")]
 #![cfg_attr(not(test), no_std)]
 
+pub mod batch;
 pub mod constants;
+pub mod control;
+pub mod fixed;
+pub mod frequency;
+pub mod grid_forming;
+pub mod grid_support;
+pub mod harmonics;
+pub mod islanding;
+pub mod modulation;
 mod newtypes;
 pub mod number;
+pub mod plant;
+pub mod pmu;
+pub mod power_quality;
 mod pq;
+pub mod protection;
 mod reference_frames;
 pub mod trig;
 pub mod wavegen;
 
-pub use newtypes::{Current, Impedance, Power, Voltage};
+pub use newtypes::{Current, Frequency, Impedance, Power, Rocof, Voltage};
 pub use pq::Pq;
 pub use reference_frames::{Abc, AlphaBeta, AlphaBeta0, Dq, Dq0, Polar};
 
+// fixtures shared by the unit tests
 #[cfg(test)]
-mod tests {}
+mod tests {
+
+    use crate::number::Float;
+    use crate::{Abc, AlphaBeta, Current, Dq, Pq, Voltage};
+
+    pub fn current(d: Float, q: Float) -> Dq<Current> {
+        Dq {
+            d: d.into(),
+            q: q.into(),
+        }
+    }
+
+    pub fn voltage(alpha: Float, beta: Float) -> AlphaBeta<Voltage> {
+        AlphaBeta {
+            alpha: alpha.into(),
+            beta: beta.into(),
+        }
+    }
+
+    pub fn power(p: Float, q: Float) -> Pq {
+        Pq {
+            p: p.into(),
+            q: q.into(),
+        }
+    }
+
+    pub fn abc<T: From<Float>>(a: Float, b: Float, c: Float) -> Abc<T> {
+        Abc {
+            a: a.into(),
+            b: b.into(),
+            c: c.into(),
+        }
+    }
+}
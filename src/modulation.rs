@@ -0,0 +1,677 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Three-phase two-level modulation
+
+A [Modulator] converts an [AlphaBeta] voltage reference into the duty cycle of each phase leg, normalized by the DC
+link voltage.  The phase voltages are relative to the DC link midpoint, so a duty of 0.5 is zero volts, and the
+[Method] sets the zero sequence voltage added to every phase, which doesn't change the line-to-line voltages:
+
+1. [Method::Sinusoidal] - No zero sequence, linear up to a phase amplitude of `Vdc / 2`
+2. [Method::ThirdHarmonic] - A third harmonic of 1/6 of the amplitude, linear up to `Vdc / √3`
+3. [Method::MinMax] - Centers the phases between the rails, linear up to `Vdc / √3`
+4. [Method::SpaceVector] - The duties from the [SpaceVector] dwell times, with the zero vectors split equally (which
+   is equivalent to min-max)
+5. [Method::Dpwm0], [Method::Dpwm1], [Method::Dpwm2] and [Method::Dpwm3] - Discontinuous modulation, clamping each
+   phase to a rail for a third of the cycle, which removes a third of the switching
+6. [Method::DpwmMin] and [Method::DpwmMax] - Discontinuous modulation, clamping the lowest phase to the negative rail,
+   or the highest to the positive rail
+
+Beyond the linear range the duties are limited to [0, 1], and [Overmodulation] sets whether the reference is first
+scaled to the hexagon of the achievable voltages.
+
+[DeadTime] corrects the duties (or the voltage reference) for the dead time and device drops, from the polarity of the
+phase currents, and estimates the resulting voltage error.
+
+```rust
+use ac_power::modulation::{Method, Modulator};
+use ac_power::number::Float;
+use ac_power::trig::Theta;
+use ac_power::{Abc, AlphaBeta, Voltage};
+use approx::assert_abs_diff_eq;
+
+let modulator = Modulator::new(Method::SpaceVector);
+
+// the limit of the linear range, where the line-to-line voltage between phases a and c is the DC link
+let amplitude = Voltage::from(400.0 / Float::sqrt(3.0));
+let reference: AlphaBeta<Voltage> = AlphaBeta::from(Abc::from_polar(amplitude, Theta::from_degrees(120.0)));
+let duties = modulator.duties(reference, Voltage::from(400.0));
+
+assert_abs_diff_eq!(duties.a, 1.0, epsilon = 1e-4);
+assert_abs_diff_eq!(duties.b, 0.5, epsilon = 1e-4);
+assert_abs_diff_eq!(duties.c, 0.0, epsilon = 1e-4);
+```
+*/
+
+use crate::constants::{ONE_HALF, PI, SQRT_3_OVER_2};
+use crate::newtypes::{Current, Voltage};
+use crate::number::Float;
+use crate::reference_frames::{Abc, AlphaBeta};
+
+/// The zero sequence voltage added to the phase references
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Method {
+    Sinusoidal,
+    ThirdHarmonic,
+    MinMax,
+    SpaceVector,
+    /// Clamps each phase for the 60° leading its peak
+    Dpwm0,
+    /// Clamps each phase for the 60° around its peak
+    Dpwm1,
+    /// Clamps each phase for the 60° lagging its peak
+    Dpwm2,
+    /// Clamps each phase for the 30° intervals either side of the 60° around its peak
+    Dpwm3,
+    DpwmMin,
+    DpwmMax,
+}
+
+/// How a reference beyond the linear range is modulated
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overmodulation {
+    /// Limits the duty of each phase, which distorts the angle and magnitude of the voltage
+    Clip,
+    /// Scales the reference to the hexagon, keeping its angle
+    MinimumPhaseError,
+}
+
+/// The dwell times of a space vector reference, as fractions of the switching period
+///
+/// The active vectors are numbered 1 to 6 anticlockwise from the alpha axis (100, 110, 010, 011, 001 and 101), and
+/// the reference is in the sector between vectors `sector` and `sector + 1`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpaceVector {
+    /// 1 to 6
+    pub sector: u8,
+    /// Time in the first and second active vectors of the sector
+    pub active: (Float, Float),
+    /// Time in the zero vectors
+    pub zero: Float,
+}
+
+// phase states of the active vectors
+const VECTORS: [[Float; 3]; 6] = [
+    [1.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 1.0, 1.0],
+    [0.0, 0.0, 1.0],
+    [1.0, 0.0, 1.0],
+];
+
+impl SpaceVector {
+    /// Calculates the dwell times of a reference for a DC link voltage
+    pub fn new(reference: AlphaBeta<Voltage>, dc_link: Voltage) -> Self {
+        let (alpha, beta): (Float, Float) = (reference.alpha.into(), reference.beta.into());
+        Self::dwell(alpha, beta, dc_link.into())
+    }
+
+    fn dwell(alpha: Float, beta: Float, dc_link: Float) -> Self {
+        let mut angle = libm::Libm::<Float>::atan2(beta, alpha);
+        if angle < 0.0 {
+            angle += 2.0 * PI;
+        }
+        let sector = Float::min(angle / (PI / 3.0), 5.0) as u8;
+        let angle = angle - (sector as Float) * PI / 3.0;
+
+        // the active vectors have a magnitude of 2/3 Vdc, so the modulation index is √3 |v| / Vdc
+        let index = (2.0 * SQRT_3_OVER_2) * libm::Libm::<Float>::hypot(alpha, beta) / dc_link;
+        let first = index * libm::Libm::<Float>::sin(PI / 3.0 - angle);
+        let second = index * libm::Libm::<Float>::sin(angle);
+        Self {
+            sector: sector + 1,
+            active: (first, second),
+            zero: 1.0 - first - second,
+        }
+    }
+
+    /// The duty of each phase, with the zero vector time split equally between 000 and 111
+    pub fn duties(&self) -> Abc<Float> {
+        let first = VECTORS[(self.sector as usize - 1) % 6];
+        let second = VECTORS[self.sector as usize % 6];
+        let duty = |phase: usize| {
+            ONE_HALF * self.zero + self.active.0 * first[phase] + self.active.1 * second[phase]
+        };
+        Abc {
+            a: duty(0),
+            b: duty(1),
+            c: duty(2),
+        }
+    }
+}
+
+/// Converts voltage references to duty cycles
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Modulator {
+    method: Method,
+    overmodulation: Overmodulation,
+}
+
+impl Modulator {
+    /// Creates a modulator, which clips the duties beyond the linear range
+    pub fn new(method: Method) -> Self {
+        Self {
+            method,
+            overmodulation: Overmodulation::Clip,
+        }
+    }
+
+    pub fn with_overmodulation(mut self, overmodulation: Overmodulation) -> Self {
+        self.overmodulation = overmodulation;
+        self
+    }
+
+    /// Calculates the duty of each phase leg (from 0 to 1) for a voltage reference and DC link voltage
+    pub fn duties(&self, reference: AlphaBeta<Voltage>, dc_link: Voltage) -> Abc<Float> {
+        let dc_link = Float::from(dc_link);
+        let mut reference: AlphaBeta<Float> = AlphaBeta {
+            alpha: reference.alpha.into(),
+            beta: reference.beta.into(),
+        };
+
+        let mut abc: Abc<Float> = Abc::from(reference);
+        let (mut max, mut min) = extremes(abc);
+        if self.overmodulation == Overmodulation::MinimumPhaseError && max - min > dc_link {
+            // the line-to-line voltage between the highest and lowest phases can't exceed the DC link
+            let scale = dc_link / (max - min);
+            reference.alpha *= scale;
+            reference.beta *= scale;
+            abc = Abc::from(reference);
+            max *= scale;
+            min *= scale;
+        }
+
+        let half = ONE_HALF * dc_link;
+        let zero = match self.method {
+            Method::Sinusoidal => 0.0,
+            Method::ThirdHarmonic => {
+                // A/6 sin(3θ), from a = A sin(θ)
+                let squared = reference.alpha * reference.alpha + reference.beta * reference.beta;
+                if squared > 0.0 {
+                    ONE_HALF * abc.a - (2.0 / 3.0) * abc.a * abc.a * abc.a / squared
+                } else {
+                    0.0
+                }
+            }
+            Method::MinMax => -ONE_HALF * (max + min),
+            Method::SpaceVector => {
+                return clip(SpaceVector::dwell(reference.alpha, reference.beta, dc_link).duties());
+            }
+            Method::Dpwm0 => clamp_largest(abc, rotate(reference, PI / 6.0), half),
+            Method::Dpwm1 => clamp_largest(abc, abc, half),
+            Method::Dpwm2 => clamp_largest(abc, rotate(reference, -PI / 6.0), half),
+            Method::Dpwm3 => {
+                // clamps whichever of the highest and lowest phases is smaller
+                if max + min >= 0.0 {
+                    -half - min
+                } else {
+                    half - max
+                }
+            }
+            Method::DpwmMin => -half - min,
+            Method::DpwmMax => half - max,
+        };
+
+        clip(Abc {
+            a: ONE_HALF + (abc.a + zero) / dc_link,
+            b: ONE_HALF + (abc.b + zero) / dc_link,
+            c: ONE_HALF + (abc.c + zero) / dc_link,
+        })
+    }
+}
+
+fn extremes(abc: Abc<Float>) -> (Float, Float) {
+    let max = Float::max(abc.a, Float::max(abc.b, abc.c));
+    let min = Float::min(abc.a, Float::min(abc.b, abc.c));
+    (max, min)
+}
+
+// the phases of a reference rotated by an angle (in radians)
+fn rotate(reference: AlphaBeta<Float>, angle: Float) -> Abc<Float> {
+    let (sin, cos) = libm::Libm::<Float>::sincos(angle);
+    Abc::from(AlphaBeta {
+        alpha: reference.alpha * cos - reference.beta * sin,
+        beta: reference.alpha * sin + reference.beta * cos,
+    })
+}
+
+// the zero sequence which clamps the phase that's largest in `selection` to the rail of its sign
+fn clamp_largest(abc: Abc<Float>, selection: Abc<Float>, half: Float) -> Float {
+    let phases = [
+        (abc.a, selection.a),
+        (abc.b, selection.b),
+        (abc.c, selection.c),
+    ];
+    let mut largest = phases[0];
+    for &phase in phases.iter().skip(1) {
+        if Float::abs(phase.1) > Float::abs(largest.1) {
+            largest = phase;
+        }
+    }
+    if largest.1 >= 0.0 {
+        half - largest.0
+    } else {
+        -half - largest.0
+    }
+}
+
+fn clip(duties: Abc<Float>) -> Abc<Float> {
+    Abc {
+        a: Float::clamp(duties.a, 0.0, 1.0),
+        b: Float::clamp(duties.b, 0.0, 1.0),
+        c: Float::clamp(duties.c, 0.0, 1.0),
+    }
+}
+
+/// Compensation of the dead time and device voltage drops of the phase legs
+///
+/// During the dead time the current flows through a diode, so the pole voltage follows the current: a positive
+/// current (out of the leg) loses `td * fsw * Vdc` of the average pole voltage, and a negative current gains it.  The
+/// conducting switch or diode drops the pole voltage by a further `Vce * d + Vd * (1 - d)` for a positive current,
+/// or raises it by `Vd * d + Vce * (1 - d)` for a negative current.  The polarity of each phase current passes
+/// linearly through a band either side of zero, where the current reverses within a switching period.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DeadTime {
+    dead_time: Float,
+    switching_frequency: Float,
+    switch_drop: Float,
+    diode_drop: Float,
+    band: Float,
+}
+
+impl DeadTime {
+    /// Creates the compensation for a dead time (in seconds) and switching frequency (in Hz)
+    pub fn new(dead_time: Float, switching_frequency: Float) -> Self {
+        Self {
+            dead_time,
+            switching_frequency,
+            switch_drop: 0.0,
+            diode_drop: 0.0,
+            band: 0.0,
+        }
+    }
+
+    /// Sets the on-state voltage drops of the switches and the diodes
+    pub fn with_device_drops(mut self, switch: Voltage, diode: Voltage) -> Self {
+        self.switch_drop = switch.into();
+        self.diode_drop = diode.into();
+        self
+    }
+
+    /// Sets the half width of the band around zero current where the polarity changes linearly
+    pub fn with_band(mut self, band: Current) -> Self {
+        self.band = band.into();
+        self
+    }
+
+    /// Corrects the duty of each phase for its current
+    pub fn compensate_duties(
+        &self,
+        duties: Abc<Float>,
+        current: Abc<Current>,
+        dc_link: Voltage,
+    ) -> Abc<Float> {
+        let dc_link = Float::from(dc_link);
+        let correct = |duty: Float, current: Current| {
+            duty - self.pole_error(duty, current, dc_link) / dc_link
+        };
+        clip(Abc {
+            a: correct(duties.a, current.a),
+            b: correct(duties.b, current.b),
+            c: correct(duties.c, current.c),
+        })
+    }
+
+    /// Corrects a voltage reference for the phase currents
+    pub fn compensate(
+        &self,
+        reference: AlphaBeta<Voltage>,
+        current: Abc<Current>,
+        dc_link: Voltage,
+    ) -> AlphaBeta<Voltage> {
+        let error = self.error(current, dc_link);
+        AlphaBeta {
+            alpha: reference.alpha - error.alpha,
+            beta: reference.beta - error.beta,
+        }
+    }
+
+    /// Estimates the error of each phase voltage (the average over a switching period), at a duty of 0.5
+    pub fn phase_error(&self, current: Abc<Current>, dc_link: Voltage) -> Abc<Voltage> {
+        let dc_link = Float::from(dc_link);
+        Abc {
+            a: self.pole_error(ONE_HALF, current.a, dc_link).into(),
+            b: self.pole_error(ONE_HALF, current.b, dc_link).into(),
+            c: self.pole_error(ONE_HALF, current.c, dc_link).into(),
+        }
+    }
+
+    /// Estimates the error of the voltage vector (the zero sequence of the phase errors doesn't reach the load)
+    pub fn error(&self, current: Abc<Current>, dc_link: Voltage) -> AlphaBeta<Voltage> {
+        AlphaBeta::from(self.phase_error(current, dc_link))
+    }
+
+    // the polarity of a current, linear within the band
+    fn polarity(&self, current: Current) -> Float {
+        let current = Float::from(current);
+        if Float::abs(current) < self.band {
+            current / self.band
+        } else if current < 0.0 {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    fn pole_error(&self, duty: Float, current: Current, dc_link: Float) -> Float {
+        let polarity = self.polarity(current);
+        // a positive current flows through the upper switch and lower diode, and a negative current through the upper
+        // diode and lower switch
+        let positive = self.switch_drop * duty + self.diode_drop * (1.0 - duty);
+        let negative = self.diode_drop * duty + self.switch_drop * (1.0 - duty);
+        let drops = Float::max(polarity, 0.0) * positive + Float::min(polarity, 0.0) * negative;
+        -polarity * self.dead_time * self.switching_frequency * dc_link - drops
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::trig::Theta;
+    use approx::assert_abs_diff_eq;
+
+    const DC_LINK: Float = 400.0;
+
+    const METHODS: [Method; 10] = [
+        Method::Sinusoidal,
+        Method::ThirdHarmonic,
+        Method::MinMax,
+        Method::SpaceVector,
+        Method::Dpwm0,
+        Method::Dpwm1,
+        Method::Dpwm2,
+        Method::Dpwm3,
+        Method::DpwmMin,
+        Method::DpwmMax,
+    ];
+
+    // a reference with the peak of phase a at 90°
+    fn reference(amplitude: Float, degrees: Float) -> AlphaBeta<Voltage> {
+        AlphaBeta::from(Abc::from_polar(
+            Voltage::from(amplitude),
+            Theta::from_degrees(degrees),
+        ))
+    }
+
+    // the alpha beta voltage the duties produce
+    fn produced(duties: Abc<Float>) -> AlphaBeta<Float> {
+        AlphaBeta::from(Abc {
+            a: duties.a * DC_LINK,
+            b: duties.b * DC_LINK,
+            c: duties.c * DC_LINK,
+        })
+    }
+
+    #[test]
+    fn dwell_times() {
+        // halfway through sector 1
+        let sv = SpaceVector::new(reference(200.0, 120.0), DC_LINK.into());
+        assert_eq!(sv.sector, 1);
+        let half = 0.5 * 3f64.sqrt() as Float * 200.0 / DC_LINK;
+        assert_abs_diff_eq!(sv.active.0, half, epsilon = 1e-5);
+        assert_abs_diff_eq!(sv.active.1, half, epsilon = 1e-5);
+        assert_abs_diff_eq!(sv.zero, 1.0 - 2.0 * half, epsilon = 1e-5);
+
+        for sector in 1..=6 {
+            let degrees = 90.0 + 60.0 * (sector as Float) - 30.0;
+            assert_eq!(
+                SpaceVector::new(reference(100.0, degrees), DC_LINK.into()).sector,
+                sector
+            );
+        }
+    }
+
+    #[test]
+    fn linear_range() {
+        // every method produces the reference up to the hexagon's inscribed circle (or Vdc / 2 for sinusoidal)
+        for &method in METHODS.iter() {
+            let modulator = Modulator::new(method);
+            let amplitude = match method {
+                Method::Sinusoidal => 0.5 * DC_LINK,
+                _ => DC_LINK / 3f64.sqrt() as Float,
+            };
+            for i in 0..360 {
+                let reference = reference(amplitude, i as Float);
+                let duties = modulator.duties(reference, DC_LINK.into());
+                let produced = produced(duties);
+                assert_abs_diff_eq!(produced.alpha, Float::from(reference.alpha), epsilon = 0.01);
+                assert_abs_diff_eq!(produced.beta, Float::from(reference.beta), epsilon = 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn space_vector_is_min_max() {
+        let sv = Modulator::new(Method::SpaceVector);
+        let min_max = Modulator::new(Method::MinMax);
+        for i in 0..360 {
+            let reference = reference(150.0, i as Float);
+            let a = sv.duties(reference, DC_LINK.into());
+            let b = min_max.duties(reference, DC_LINK.into());
+            assert_abs_diff_eq!(a.a, b.a, epsilon = 1e-5);
+            assert_abs_diff_eq!(a.b, b.b, epsilon = 1e-5);
+            assert_abs_diff_eq!(a.c, b.c, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn sinusoidal_and_third_harmonic() {
+        let duties =
+            Modulator::new(Method::Sinusoidal).duties(reference(100.0, 90.0), DC_LINK.into());
+        assert_abs_diff_eq!(duties.a, 0.75, epsilon = 1e-5);
+        assert_abs_diff_eq!(duties.b, 0.375, epsilon = 1e-5);
+
+        // the third harmonic flattens the peak by 1/6, and is zero where sin(3θ) is
+        let third = Modulator::new(Method::ThirdHarmonic);
+        let duties = third.duties(reference(100.0, 90.0), DC_LINK.into());
+        assert_abs_diff_eq!(
+            duties.a,
+            0.5 + (100.0 - 100.0 / 6.0) / DC_LINK,
+            epsilon = 1e-5
+        );
+        let duties = third.duties(reference(100.0, 60.0), DC_LINK.into());
+        assert_abs_diff_eq!(
+            duties.a,
+            0.5 + 100.0 * SQRT_3_OVER_2 / DC_LINK,
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn discontinuous() {
+        let amplitude = 180.0;
+
+        // (method, centre of the clamp to the positive rail for phase a)
+        let centres = [
+            (Method::Dpwm0, 60.0),
+            (Method::Dpwm1, 90.0),
+            (Method::Dpwm2, 120.0),
+        ];
+        for &(method, centre) in centres.iter() {
+            let modulator = Modulator::new(method);
+            let mut clamped = 0;
+            for i in 0..360 {
+                let duties =
+                    modulator.duties(reference(amplitude, i as Float + 0.5), DC_LINK.into());
+                let rails = [duties.a, duties.b, duties.c]
+                    .iter()
+                    .filter(|&&d| !(1e-6..=1.0 - 1e-6).contains(&d))
+                    .count();
+                assert_eq!(rails, 1);
+                if duties.a > 1.0 - 1e-6 {
+                    clamped += 1;
+                    assert!(Float::abs(i as Float + 0.5 - centre) < 30.0);
+                }
+            }
+            assert_eq!(clamped, 60);
+        }
+
+        // dpwm3 clamps phase a to the positive rail either side of its peak
+        let modulator = Modulator::new(Method::Dpwm3);
+        let mut clamped = 0;
+        for i in 0..360 {
+            let degrees = i as Float + 0.5;
+            let duties = modulator.duties(reference(amplitude, degrees), DC_LINK.into());
+            if duties.a > 1.0 - 1e-6 {
+                clamped += 1;
+                let offset = Float::abs(degrees - 90.0);
+                assert!(offset > 30.0 && offset < 60.0);
+            }
+        }
+        assert_eq!(clamped, 60);
+
+        for i in 0..360 {
+            let reference = reference(amplitude, i as Float);
+            let duties = Modulator::new(Method::DpwmMin).duties(reference, DC_LINK.into());
+            assert_abs_diff_eq!(extremes(duties).1, 0.0, epsilon = 1e-6);
+            let duties = Modulator::new(Method::DpwmMax).duties(reference, DC_LINK.into());
+            assert_abs_diff_eq!(extremes(duties).0, 1.0, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn overmodulation() {
+        let amplitude = 1.2 * DC_LINK / 3f64.sqrt() as Float;
+        let clip = Modulator::new(Method::SpaceVector);
+        let scale = Modulator::new(Method::SpaceVector)
+            .with_overmodulation(Overmodulation::MinimumPhaseError);
+
+        // at a vertex of the hexagon, both reach the vertex
+        let vertex = reference(amplitude, 90.0);
+        for modulator in [clip, scale].iter() {
+            let produced = produced(modulator.duties(vertex, DC_LINK.into()));
+            assert_abs_diff_eq!(produced.alpha, 2.0 * DC_LINK / 3.0, epsilon = 0.01);
+            assert_abs_diff_eq!(produced.beta, 0.0, epsilon = 0.01);
+        }
+
+        // between the vertices, scaling keeps the angle and reaches the edge
+        for i in 0..360 {
+            let reference = reference(amplitude, i as Float + 0.5);
+            let (alpha, beta): (Float, Float) = (reference.alpha.into(), reference.beta.into());
+            let duties = scale.duties(reference, DC_LINK.into());
+            let (max, min) = extremes(duties);
+            assert_abs_diff_eq!(max - min, 1.0, epsilon = 1e-4);
+            let produced = produced(duties);
+            assert_abs_diff_eq!(
+                libm::Libm::<Float>::atan2(produced.beta, produced.alpha),
+                libm::Libm::<Float>::atan2(beta, alpha),
+                epsilon = 1e-4
+            );
+
+            let duties = clip.duties(reference, DC_LINK.into());
+            for &duty in [duties.a, duties.b, duties.c].iter() {
+                assert!((0.0..=1.0).contains(&duty));
+            }
+        }
+    }
+
+    // the average pole voltage (from the negative rail) over a switching period with a dead time of td, for a current
+    // which doesn't reverse in the period
+    fn pole(duty: Float, current: Float, td: Float, fsw: Float, vce: Float, vd: Float) -> Float {
+        // the current flows through the upper device for the time the upper switch is on (less the dead time), or
+        // the time the lower switch is off (plus the dead time)
+        let (upper, switch, diode) = if current > 0.0 {
+            (duty - td * fsw, vce, vd)
+        } else {
+            (duty + td * fsw, -vd, -vce)
+        };
+        upper * (DC_LINK - switch) + (1.0 - upper) * -diode
+    }
+
+    #[test]
+    fn dead_time() {
+        let (td, fsw, vce, vd) = (2e-6, 10e3, 1.5, 1.0);
+        let compensation = DeadTime::new(td, fsw).with_device_drops(vce.into(), vd.into());
+
+        // a positive current loses td * fsw * Vdc = 8V and the device drops
+        let current = Abc {
+            a: Current::from(10.0),
+            b: Current::from(-5.0),
+            c: Current::from(-5.0),
+        };
+        let error = compensation.phase_error(current, DC_LINK.into());
+        assert_abs_diff_eq!(Float::from(error.a), -8.0 - 1.25, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(error.b), 8.0 + 1.25, epsilon = 1e-4);
+
+        // the error vector is 4/3 of the phase error, in the direction of the current's sector
+        let vector = compensation.error(current, DC_LINK.into());
+        assert_abs_diff_eq!(Float::from(vector.alpha), -4.0 / 3.0 * 9.25, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(vector.beta), 0.0, epsilon = 1e-4);
+
+        // the compensated duties produce the intended pole voltages
+        let duties = Abc {
+            a: 0.7,
+            b: 0.4,
+            c: 0.2,
+        };
+        let compensated = compensation.compensate_duties(duties, current, DC_LINK.into());
+        let phases = [
+            (duties.a, compensated.a, 10.0),
+            (duties.b, compensated.b, -5.0),
+            (duties.c, compensated.c, -5.0),
+        ];
+        for &(duty, compensated, current) in phases.iter() {
+            let uncompensated = pole(duty, current, td, fsw, vce, vd);
+            assert!(Float::abs(uncompensated - duty * DC_LINK) > 8.0);
+            let produced = pole(compensated, current, td, fsw, vce, vd);
+            assert_abs_diff_eq!(produced, duty * DC_LINK, epsilon = 0.05);
+        }
+    }
+
+    #[test]
+    fn dead_time_band() {
+        let compensation = DeadTime::new(2e-6, 10e3).with_band(Current::from(0.5));
+        let current = Abc {
+            a: Current::from(0.25),
+            b: Current::from(-1.0),
+            c: Current::from(0.0),
+        };
+        let error = compensation.phase_error(current, DC_LINK.into());
+        assert_abs_diff_eq!(Float::from(error.a), -4.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(error.b), 8.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(error.c), 0.0);
+
+        // the reference is corrected by the error vector
+        let reference = AlphaBeta {
+            alpha: Voltage::from(100.0),
+            beta: Voltage::from(50.0),
+        };
+        let compensated = compensation.compensate(reference, current, DC_LINK.into());
+        let vector = compensation.error(current, DC_LINK.into());
+        assert_abs_diff_eq!(
+            Float::from(compensated.alpha + vector.alpha),
+            100.0,
+            epsilon = 1e-4
+        );
+        assert_abs_diff_eq!(
+            Float::from(compensated.beta + vector.beta),
+            50.0,
+            epsilon = 1e-4
+        );
+    }
+}
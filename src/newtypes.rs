@@ -13,9 +13,9 @@
 //    See the License for the specific language governing permissions and
 //    limitations under the License.
 
+use crate::number::Float;
 use crate::trig::{Cos, Sin};
 use core::ops::{Mul, MulAssign};
-use core::primitive::f32;
 use derive_more::{Add, AddAssign, From, Into, Neg, Sub};
 
 macro_rules! impl_trig_ops {
@@ -29,14 +29,14 @@ macro_rules! impl_trig_ops {
 
         impl Mul<$tr> for $nt {
             fn mul(self, other: $tr) -> $nt {
-                self * f32::from(other)
+                self * Float::from(other)
             }
             type Output = $nt;
         }
 
         impl MulAssign<$tr> for $nt {
             fn mul_assign(&mut self, other: $tr) {
-                self.0 *= f32::from(other);
+                self.0 *= Float::from(other);
             }
         }
     };
@@ -44,14 +44,14 @@ macro_rules! impl_trig_ops {
 
 macro_rules! impl_number {
     ($t:ty) => {
-        impl Mul<f32> for $t {
-            fn mul(self, other: f32) -> $t {
+        impl Mul<Float> for $t {
+            fn mul(self, other: Float) -> $t {
                 (self.0 * other).into()
             }
             type Output = $t;
         }
 
-        impl Mul<$t> for f32 {
+        impl Mul<$t> for Float {
             fn mul(self, other: $t) -> $t {
                 (self * other.0).into()
             }
@@ -63,27 +63,37 @@ macro_rules! impl_number {
     };
 }
 
-/// A newtype representing an electric voltage (wraps f32)
+/// A newtype representing an electric voltage (wraps [Float](crate::number::Float))
 #[derive(Neg, AddAssign, Add, Sub, Debug, Copy, Clone, PartialEq, From, Into)]
-pub struct Voltage(f32);
+pub struct Voltage(Float);
 
-/// A newtype representing an electric current (wraps f32)
+/// A newtype representing an electric current (wraps [Float](crate::number::Float))
 #[derive(Neg, AddAssign, Add, Sub, Debug, Copy, Clone, PartialEq, From, Into)]
-pub struct Current(f32);
+pub struct Current(Float);
 
-/// A newtype representing an electric power (wraps f32)
+/// A newtype representing an electric power (wraps [Float](crate::number::Float))
 #[derive(Neg, AddAssign, Add, Sub, Debug, Copy, Clone, PartialEq, From, Into)]
-pub struct Power(f32);
+pub struct Power(Float);
 
-/// A newtype representing an electric impedance (wraps f32)
+/// A newtype representing an electric impedance (wraps [Float](crate::number::Float))
 #[derive(Neg, AddAssign, Add, Sub, Debug, Copy, Clone, PartialEq, From, Into)]
-pub struct Impedance(f32);
+pub struct Impedance(Float);
+
+/// A newtype representing a frequency in Hz (wraps [Float](crate::number::Float))
+#[derive(Neg, AddAssign, Add, Sub, Debug, Copy, Clone, PartialEq, PartialOrd, From, Into)]
+pub struct Frequency(Float);
+
+/// A newtype representing a rate of change of frequency in Hz/s (wraps [Float](crate::number::Float))
+#[derive(Neg, AddAssign, Add, Sub, Debug, Copy, Clone, PartialEq, PartialOrd, From, Into)]
+pub struct Rocof(Float);
 
 // derive operations for the new-types
 impl_number!(Voltage);
 impl_number!(Current);
 impl_number!(Power);
 impl_number!(Impedance);
+impl_number!(Frequency);
+impl_number!(Rocof);
 
 // derive special multiplies that yeild a unit change (i.e. Voltage * Current = Power)
 impl Mul<Current> for Voltage {
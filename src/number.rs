@@ -17,28 +17,91 @@
 Definition of the numeric trait required for the reference frames elements
 */
 
+use crate::constants;
+use crate::fixed::{Q15, Q31};
 use crate::newtypes::{Current, Power, Voltage};
-use crate::trig::{Cos, Sin};
+use crate::trig::{Cos, Trig};
 use core::fmt::Debug;
 use core::ops::{Add, AddAssign, Mul, Neg, Sub};
 
+/// The floating point type used throughout the crate
+///
+/// This is `f32` by default, or `f64` when the `f64` feature is enabled.
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+
+/// The floating point type used throughout the crate
+///
+/// This is `f32` by default, or `f64` when the `f64` feature is enabled.
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+/// Multiplication by the constants of the Clarke transforms
+///
+/// The constants are in the number's own representation, which is [Float] for the floating point types and [Q31]
+/// for the fixed-point types, so the fixed-point transforms stay in fixed-point.
+pub trait Scale: Sized {
+    /// The representation of the constants
+    type Factor: Copy;
+
+    const ONE_THIRD: Self::Factor;
+    const TWO_THIRDS: Self::Factor;
+    const SQRT_3_OVER_3: Self::Factor;
+    const ONE_HALF: Self::Factor;
+    const SQRT_3_OVER_2: Self::Factor;
+
+    fn scale(self, factor: Self::Factor) -> Self;
+}
+
+macro_rules! impl_scale {
+    ($t:ty) => {
+        impl Scale for $t {
+            type Factor = Float;
+
+            const ONE_THIRD: Float = constants::ONE_THIRD;
+            const TWO_THIRDS: Float = constants::TWO_THIRDS;
+            const SQRT_3_OVER_3: Float = constants::SQRT_3_OVER_3;
+            const ONE_HALF: Float = constants::ONE_HALF;
+            const SQRT_3_OVER_2: Float = constants::SQRT_3_OVER_2;
+
+            #[inline]
+            fn scale(self, factor: Float) -> Self {
+                self * factor
+            }
+        }
+    };
+}
+
+impl_scale!(Float);
+impl_scale!(Voltage);
+impl_scale!(Current);
+impl_scale!(Power);
+
 /// Generic type with a trait bound for acceptable number types for use with reference frame structures
-pub trait Num:
+///
+/// The rotating transforms require the number to be multipliable by a [Trig] representation, which is
+/// [Cos] by default.
+pub trait Num<C: Trig = Cos>:
     Add<Output = Self>
     + AddAssign<Self>
     + Sub<Output = Self>
-    + Mul<f32, Output = Self>
-    + Mul<Cos, Output = Self>
-    + Mul<Sin, Output = Self>
+    + Mul<Float, Output = Self>
+    + Mul<C, Output = Self>
+    + Mul<C::Sin, Output = Self>
     + Neg<Output = Self>
-    + From<f32>
-    + Into<f32>
+    + From<Float>
+    + Into<Float>
+    + Scale
     + Copy
     + Debug
 {
 }
 
-impl Num for f32 {}
+impl Num for Float {}
 impl Num for Voltage {}
 impl Num for Current {}
 impl Num for Power {}
+impl Num for Q15 {}
+impl Num for Q31 {}
+impl Num<Q31> for Q15 {}
+impl Num<Q31> for Q31 {}
@@ -0,0 +1,241 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::newtypes::{Current, Frequency, Impedance, Voltage};
+use crate::number::Float;
+use crate::reference_frames::{Abc, AlphaBeta};
+use crate::trig::{PhaseAccumulator, Theta};
+use crate::wavegen::Waveform;
+
+/// A Thevenin equivalent of the grid: a source driven by a [Waveform], behind a series RL impedance
+///
+/// The grid impedance is in series with the filter, so is stepped by including it in the filter (e.g. an
+/// [RlFilter](super::RlFilter) of the sum of the two, or the grid side of an [LclFilter](super::LclFilter)), driven
+/// by the [source](TheveninGrid::source).  The voltage at the point of common coupling is then recovered from the
+/// current with [pcc_voltage](TheveninGrid::pcc_voltage).
+#[derive(Debug, Clone)]
+pub struct TheveninGrid<const N: usize> {
+    waveform: Waveform<Voltage, N>,
+    frequency: Frequency,
+    ts: Float,
+    resistance: Float,
+    inductance: Float,
+    phase: PhaseAccumulator,
+}
+
+impl<const N: usize> TheveninGrid<N> {
+    /// Creates a stiff grid (without impedance) from a waveform at a frequency, stepped every `ts` seconds
+    pub fn new(waveform: Waveform<Voltage, N>, frequency: Frequency, ts: Float) -> Self {
+        Self {
+            waveform,
+            frequency,
+            ts,
+            resistance: 0.0,
+            inductance: 0.0,
+            phase: PhaseAccumulator::new(ts),
+        }
+    }
+
+    /// Sets the grid impedance from a resistance and inductance (in henries)
+    pub fn with_impedance(mut self, resistance: Impedance, inductance: Float) -> Self {
+        self.resistance = resistance.into();
+        self.inductance = inductance;
+        self
+    }
+
+    pub fn set_frequency(&mut self, frequency: Frequency) {
+        self.frequency = frequency;
+    }
+
+    /// The grid resistance and inductance
+    pub fn impedance(&self) -> (Impedance, Float) {
+        (self.resistance.into(), self.inductance)
+    }
+
+    /// Phase of the fundamental for this sample
+    pub fn theta(&self) -> Theta {
+        self.phase.theta()
+    }
+
+    /// The source voltage for this sample
+    pub fn source(&self) -> Abc<Voltage> {
+        self.waveform.calculate(self.phase.theta())
+    }
+
+    /// The voltage at the point of common coupling over a sample, from the current injected into the grid at the
+    /// start and end of the sample: the source, plus the drop across the grid impedance
+    pub fn pcc_voltage(
+        &self,
+        start: AlphaBeta<Current>,
+        end: AlphaBeta<Current>,
+    ) -> AlphaBeta<Voltage> {
+        let source = AlphaBeta::from(self.source());
+        let drop = |start: Current, end: Current| {
+            let (start, end) = (Float::from(start), Float::from(end));
+            Voltage::from(
+                self.resistance * (start + end) / 2.0 + self.inductance * (end - start) / self.ts,
+            )
+        };
+        AlphaBeta {
+            alpha: source.alpha + drop(start.alpha, end.alpha),
+            beta: source.beta + drop(start.beta, end.beta),
+        }
+    }
+
+    /// Advances the source to the next sample
+    pub fn advance(&mut self) {
+        self.phase.update(self.frequency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::constants::PI;
+    use crate::control::{CurrentController, DqPi};
+    use crate::plant::RlFilter;
+    use crate::reference_frames::Dq;
+    use crate::trig::cos_sin;
+    use crate::wavegen::{Magnitude, Sequence};
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+    const AMPLITUDE: Float = 325.0;
+
+    fn grid() -> TheveninGrid<5> {
+        let waveform = Waveform::new()
+            .with_harmonic(
+                1,
+                Sequence::Positive,
+                Magnitude::Absolute(Voltage::from(AMPLITUDE)),
+                Theta::from(0),
+            )
+            .with_harmonic(
+                5,
+                Sequence::Negative,
+                Magnitude::Percent(3.0),
+                Theta::from(0),
+            );
+        TheveninGrid::new(waveform, Frequency::from(50.0), TS).with_impedance(0.2.into(), 1e-3)
+    }
+
+    #[test]
+    fn source() {
+        // the source follows the waveform, advancing at the grid frequency
+        let mut grid = grid();
+        let waveform: Waveform<Voltage, 5> = Waveform::new()
+            .with_harmonic(
+                1,
+                Sequence::Positive,
+                Magnitude::Absolute(Voltage::from(AMPLITUDE)),
+                Theta::from(0),
+            )
+            .with_harmonic(
+                5,
+                Sequence::Negative,
+                Magnitude::Percent(3.0),
+                Theta::from(0),
+            );
+        for k in 0..100 {
+            let theta = Theta::from_degrees(360.0 * 50.0 * (k as Float) * TS);
+            let expected = waveform.calculate(theta);
+            let source = grid.source();
+            assert_abs_diff_eq!(
+                Float::from(source.a),
+                Float::from(expected.a),
+                epsilon = 0.01
+            );
+            assert_abs_diff_eq!(
+                Float::from(source.b),
+                Float::from(expected.b),
+                epsilon = 0.01
+            );
+            grid.advance();
+        }
+    }
+
+    #[test]
+    fn pcc() {
+        let grid = grid();
+        let current = |alpha: Float| AlphaBeta {
+            alpha: Current::from(alpha),
+            beta: Current::from(0.0),
+        };
+        let source = AlphaBeta::from(grid.source());
+
+        // without current the PCC is at the source, and a constant current drops across the resistance
+        let v = grid.pcc_voltage(current(0.0), current(0.0));
+        assert_abs_diff_eq!(
+            Float::from(v.alpha),
+            Float::from(source.alpha),
+            epsilon = 1e-3
+        );
+        let v = grid.pcc_voltage(current(10.0), current(10.0));
+        assert_abs_diff_eq!(
+            Float::from(v.alpha),
+            Float::from(source.alpha) + 2.0,
+            epsilon = 1e-3
+        );
+
+        // and a changing current across the inductance
+        let v = grid.pcc_voltage(current(10.0), current(11.0));
+        assert_abs_diff_eq!(
+            Float::from(v.alpha),
+            Float::from(source.alpha) + 2.1 + 1e-3 / TS,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn closed_loop() {
+        // a current controller regulating an RL filter into the Thevenin grid, measuring the PCC voltage
+        let (r, l) = (0.1, 3e-3);
+        let mut grid = grid();
+        let (rg, lg) = grid.impedance();
+        let mut filter = RlFilter::new(Impedance::from(r) + rg, l + lg, TS);
+        let bandwidth = 2.0 * PI * 200.0;
+        let mut controller =
+            CurrentController::new(DqPi::new(l * bandwidth, r * bandwidth, TS), l, TS)
+                .with_feedforward_filter(2e-3);
+
+        let reference = Dq {
+            d: Current::from(20.0),
+            q: Current::from(-5.0),
+        };
+        let mut pcc = AlphaBeta::from(grid.source());
+        let mut average: Dq<Float> = Dq::zero();
+        for k in 0..4000 {
+            let theta = grid.theta();
+            let (cos, sin) = cos_sin(theta);
+            let i = filter.current();
+            let current = i.to_dq(cos, sin);
+            if k >= 3800 {
+                average.d += Float::from(current.d) / 200.0;
+                average.q += Float::from(current.q) / 200.0;
+            }
+            let v = controller.update(reference, current, pcc.to_dq(cos, sin), theta, 50.0.into());
+
+            let source = AlphaBeta::from(grid.source());
+            filter.step(v, source);
+            pcc = grid.pcc_voltage(i, filter.current());
+            grid.advance();
+        }
+
+        // the fundamental current (averaging out the fifth harmonic over the last cycle) tracks the reference
+        assert_abs_diff_eq!(average.d, 20.0, epsilon = 0.05);
+        assert_abs_diff_eq!(average.q, -5.0, epsilon = 0.05);
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::newtypes::{Current, Impedance, Power, Voltage};
+use crate::number::Float;
+use crate::pq::Pq;
+use crate::reference_frames::Abc;
+
+/// An average-value model of a two-level, three-phase inverter with a DC link capacitor
+///
+/// Over a sample each pole is at the DC link voltage for its duty cycle, so the terminal voltages (relative to the
+/// midpoint of the DC link) are `v = (d - 0.5) Vdc - Ron i`, where Ron is the conduction resistance of the switches.
+/// The poles draw `idc = Σ d i` from the link, and the capacitor integrates the difference from the source current,
+/// `C dVdc/dt = isource - idc`, so the DC power is the AC power plus the conduction losses.
+#[derive(Debug, Copy, Clone)]
+pub struct AveragedInverter {
+    capacitance: Float,
+    ts: Float,
+    resistance: Float,
+    dc_voltage: Float,
+    dc_current: Float,
+    dc_power: Float,
+    power: Pq<Float>,
+}
+
+impl AveragedInverter {
+    /// Creates an inverter with a DC link capacitance (in farads) charged to a voltage, stepped every `ts` seconds
+    pub fn new(capacitance: Float, dc_voltage: Voltage, ts: Float) -> Self {
+        Self {
+            capacitance,
+            ts,
+            resistance: 0.0,
+            dc_voltage: dc_voltage.into(),
+            dc_current: 0.0,
+            dc_power: 0.0,
+            power: Pq { p: 0.0, q: 0.0 },
+        }
+    }
+
+    /// Sets the conduction resistance of the switches
+    pub fn with_conduction_resistance(mut self, resistance: Impedance) -> Self {
+        self.resistance = resistance.into();
+        self
+    }
+
+    /// The terminal voltages for duty cycles and phase currents, at the present DC link voltage
+    pub fn output(&self, duties: Abc<Float>, current: Abc<Current>) -> Abc<Voltage> {
+        let pole = |duty: Float, current: Current| {
+            Voltage::from((duty - 0.5) * self.dc_voltage - self.resistance * Float::from(current))
+        };
+        Abc {
+            a: pole(duties.a, current.a),
+            b: pole(duties.b, current.b),
+            c: pole(duties.c, current.c),
+        }
+    }
+
+    /// Steps the DC link through a sample with the duty cycles, phase currents and source current held, returning the
+    /// terminal voltages over the sample
+    pub fn step(
+        &mut self,
+        duties: Abc<Float>,
+        current: Abc<Current>,
+        source: Current,
+    ) -> Abc<Voltage> {
+        let output = self.output(duties, current);
+        let pq = output * current;
+        self.power = Pq {
+            p: pq.p.into(),
+            q: pq.q.into(),
+        };
+
+        self.dc_current = duties.a * Float::from(current.a)
+            + duties.b * Float::from(current.b)
+            + duties.c * Float::from(current.c);
+        self.dc_power = self.dc_voltage * self.dc_current;
+        self.dc_voltage += self.ts * (Float::from(source) - self.dc_current) / self.capacitance;
+        output
+    }
+
+    pub fn dc_voltage(&self) -> Voltage {
+        self.dc_voltage.into()
+    }
+
+    pub fn set_dc_voltage(&mut self, dc_voltage: Voltage) {
+        self.dc_voltage = dc_voltage.into();
+    }
+
+    /// The current drawn from the DC link over the last sample
+    pub fn dc_current(&self) -> Current {
+        self.dc_current.into()
+    }
+
+    /// The power delivered at the terminals over the last sample
+    pub fn power(&self) -> Pq {
+        Pq {
+            p: self.power.p.into(),
+            q: self.power.q.into(),
+        }
+    }
+
+    /// The power drawn from the DC link over the last sample
+    pub fn dc_power(&self) -> Power {
+        self.dc_power.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::constants::PI;
+    use crate::control::{CurrentController, DqPi};
+    use crate::modulation::{Method, Modulator};
+    use crate::plant::RlFilter;
+    use crate::reference_frames::{AlphaBeta, Dq};
+    use crate::tests::abc;
+    use crate::trig::{cos_sin, Theta};
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+
+    #[test]
+    fn terminals() {
+        let inverter = AveragedInverter::new(1e-3, Voltage::from(400.0), TS)
+            .with_conduction_resistance(0.05.into());
+        let v = inverter.output(abc(0.75, 0.5, 0.25), abc(10.0, -4.0, -6.0));
+        assert_abs_diff_eq!(Float::from(v.a), 100.0 - 0.5, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(v.b), 0.2, epsilon = 1e-4);
+        assert_abs_diff_eq!(Float::from(v.c), -100.0 + 0.3, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn dc_link() {
+        // without load, the source current charges the capacitor
+        let mut inverter = AveragedInverter::new(1e-3, Voltage::from(400.0), TS);
+        for _ in 0..1000 {
+            inverter.step(abc(0.5, 0.5, 0.5), abc(0.0, 0.0, 0.0), Current::from(2.0));
+        }
+        assert_abs_diff_eq!(Float::from(inverter.dc_voltage()), 600.0, epsilon = 0.05);
+
+        // and the poles draw the sum of their duties times the phase currents
+        let before = Float::from(inverter.dc_voltage());
+        inverter.step(
+            abc(0.9, 0.3, 0.3),
+            abc(10.0, -5.0, -5.0),
+            Current::from(0.0),
+        );
+        assert_abs_diff_eq!(Float::from(inverter.dc_current()), 6.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(
+            Float::from(inverter.dc_voltage()),
+            before - 6.0 * TS / 1e-3,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn power_balance() {
+        // the DC power is the AC power plus the conduction losses, for three-wire currents
+        let mut inverter = AveragedInverter::new(1e-3, Voltage::from(400.0), TS)
+            .with_conduction_resistance(0.05.into());
+        let current = abc(10.0, -4.0, -6.0);
+        inverter.step(abc(0.8, 0.45, 0.3), current, Current::from(0.0));
+
+        let losses = 0.05 * (100.0 + 16.0 + 36.0);
+        assert_abs_diff_eq!(
+            Float::from(inverter.dc_power()),
+            Float::from(inverter.power().p) + losses,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn closed_loop() {
+        // a current controller and space vector modulator, through the inverter and an RL filter to a stiff grid,
+        // with the DC link fed by a 700V supply through 0.5Ω
+        let (r, l, f, amplitude) = (0.1, 3e-3, 50.0, 325.0);
+        let ron = 0.02;
+        let mut inverter = AveragedInverter::new(2e-3, Voltage::from(700.0), TS)
+            .with_conduction_resistance(ron.into());
+        let mut filter = RlFilter::new(Impedance::from(r), l, TS);
+        let bandwidth = 2.0 * PI * 200.0;
+        let mut controller =
+            CurrentController::new(DqPi::new(l * bandwidth, r * bandwidth, TS), l, TS)
+                .with_delay(0.5);
+        let modulator = Modulator::new(Method::SpaceVector);
+
+        let reference = Dq {
+            d: Current::from(20.0),
+            q: Current::from(0.0),
+        };
+        let mut current = Dq::zero();
+        let mut source = 0.0;
+        for k in 0..10000 {
+            let theta = Theta::from_radians(2.0 * PI * f * (k as Float) * TS);
+            let (cos, sin) = cos_sin(theta);
+            let grid: AlphaBeta<Voltage> =
+                AlphaBeta::from(Abc::from_polar(Voltage::from(amplitude), theta));
+            let i = filter.current();
+            current = i.to_dq(cos, sin);
+            let v = controller.update(reference, current, grid.to_dq(cos, sin), theta, f.into());
+
+            let duties = modulator.duties(v, inverter.dc_voltage());
+            source = (700.0 - Float::from(inverter.dc_voltage())) / 0.5;
+            let terminals = inverter.step(duties, Abc::from(i), Current::from(source));
+            filter.step_abc(terminals, Abc::from(grid));
+        }
+
+        // the current tracks, and the supply delivers the grid power plus the losses
+        assert_abs_diff_eq!(Float::from(current.d), 20.0, epsilon = 0.05);
+        assert_abs_diff_eq!(Float::from(current.q), 0.0, epsilon = 0.05);
+        let delivered = 1.5 * amplitude * 20.0;
+        let losses = 1.5 * (r + ron) * 20.0 * 20.0;
+        let supplied = source * Float::from(inverter.dc_voltage());
+        assert_abs_diff_eq!(supplied, delivered + losses, epsilon = 0.005 * delivered);
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::newtypes::{Current, Impedance, Voltage};
+use crate::number::Float;
+use crate::reference_frames::{Abc, AlphaBeta};
+
+type Matrix = [[Float; 3]; 3];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut product = [[0.0; 3]; 3];
+    for (row, a) in product.iter_mut().zip(a.iter()) {
+        for (j, element) in row.iter_mut().enumerate() {
+            *element = a[0] * b[0][j] + a[1] * b[1][j] + a[2] * b[2][j];
+        }
+    }
+    product
+}
+
+// the inverse from the adjugate
+fn invert(m: &Matrix) -> Matrix {
+    let cofactor = |i: usize, j: usize| {
+        let (r0, r1) = ((i + 1) % 3, (i + 2) % 3);
+        let (c0, c1) = ((j + 1) % 3, (j + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let determinant =
+        m[0][0] * cofactor(0, 0) + m[0][1] * cofactor(0, 1) + m[0][2] * cofactor(0, 2);
+    let mut inverse = [[0.0; 3]; 3];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        for (j, element) in row.iter_mut().enumerate() {
+            *element = cofactor(j, i) / determinant;
+        }
+    }
+    inverse
+}
+
+/// An LCL filter between the inverter and grid voltages, with an optional damping resistor in series with the
+/// capacitor
+///
+/// The state is the inverter side current, capacitor voltage and grid side current, `x = [i1, vc, i2]`, with
+/// `L1 di1/dt = v - R1 i1 - vpcc`, `C dvc/dt = i1 - i2`, and `L2 di2/dt = vpcc - R2 i2 - e`, where
+/// `vpcc = vc + Rd (i1 - i2)`.  It's discretized with the Tustin (trapezoidal) transform, for voltages held over each
+/// sample, which is stable for any sample period, but warps the resonance towards lower frequencies as it approaches
+/// the Nyquist frequency.
+#[derive(Debug, Copy, Clone)]
+pub struct LclFilter {
+    inverter: (Float, Float),
+    capacitance: Float,
+    grid: (Float, Float),
+    damping: Float,
+    ts: Float,
+    a: Matrix,
+    b: [[Float; 2]; 3],
+    alpha: [Float; 3],
+    beta: [Float; 3],
+}
+
+impl LclFilter {
+    /// Creates a filter from the inverter side resistance and inductance (in henries), the capacitance (in farads),
+    /// and the grid side resistance and inductance, stepped every `ts` seconds
+    pub fn new(
+        inverter: (Impedance, Float),
+        capacitance: Float,
+        grid: (Impedance, Float),
+        ts: Float,
+    ) -> Self {
+        let mut filter = Self {
+            inverter: (inverter.0.into(), inverter.1),
+            capacitance,
+            grid: (grid.0.into(), grid.1),
+            damping: 0.0,
+            ts,
+            a: [[0.0; 3]; 3],
+            b: [[0.0; 2]; 3],
+            alpha: [0.0; 3],
+            beta: [0.0; 3],
+        };
+        filter.discretize();
+        filter
+    }
+
+    /// Adds a damping resistor in series with the capacitor
+    pub fn with_damping(mut self, resistance: Impedance) -> Self {
+        self.damping = resistance.into();
+        self.discretize();
+        self
+    }
+
+    fn discretize(&mut self) {
+        let ((r1, l1), c, (r2, l2), rd) =
+            (self.inverter, self.capacitance, self.grid, self.damping);
+        let a = [
+            [-(r1 + rd) / l1, -1.0 / l1, rd / l1],
+            [1.0 / c, 0.0, -1.0 / c],
+            [rd / l2, 1.0 / l2, -(r2 + rd) / l2],
+        ];
+
+        // x[k+1] = (I - A h)^-1 ((I + A h) x[k] + 2h B u[k]), with h = ts / 2
+        let h = self.ts / 2.0;
+        let mut minus = [[0.0; 3]; 3];
+        let mut plus = [[0.0; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                minus[i][j] = identity - a[i][j] * h;
+                plus[i][j] = identity + a[i][j] * h;
+            }
+        }
+        let inverse = invert(&minus);
+        self.a = multiply(&inverse, &plus);
+        // B has 1/L1 for the inverter voltage on i1, and -1/L2 for the grid voltage on i2
+        for (b, inverse) in self.b.iter_mut().zip(inverse.iter()) {
+            *b = [2.0 * h * inverse[0] / l1, -2.0 * h * inverse[2] / l2];
+        }
+    }
+
+    fn advance(&self, x: &[Float; 3], v: Float, e: Float) -> [Float; 3] {
+        let mut next = [0.0; 3];
+        for ((next, a), b) in next.iter_mut().zip(self.a.iter()).zip(self.b.iter()) {
+            *next = a[0] * x[0] + a[1] * x[1] + a[2] * x[2] + b[0] * v + b[1] * e;
+        }
+        next
+    }
+
+    /// Steps the filter through a sample, with the inverter and grid voltages held, returning the grid side current at
+    /// the end of the sample
+    pub fn step(
+        &mut self,
+        inverter: AlphaBeta<Voltage>,
+        grid: AlphaBeta<Voltage>,
+    ) -> AlphaBeta<Current> {
+        self.alpha = self.advance(&self.alpha, inverter.alpha.into(), grid.alpha.into());
+        self.beta = self.advance(&self.beta, inverter.beta.into(), grid.beta.into());
+        self.grid_current()
+    }
+
+    /// Steps the filter through a sample with phase voltages (as a three-wire circuit, so without zero sequence)
+    pub fn step_abc(&mut self, inverter: Abc<Voltage>, grid: Abc<Voltage>) -> Abc<Current> {
+        Abc::from(self.step(AlphaBeta::from(inverter), AlphaBeta::from(grid)))
+    }
+
+    pub fn inverter_current(&self) -> AlphaBeta<Current> {
+        AlphaBeta {
+            alpha: self.alpha[0].into(),
+            beta: self.beta[0].into(),
+        }
+    }
+
+    pub fn capacitor_voltage(&self) -> AlphaBeta<Voltage> {
+        AlphaBeta {
+            alpha: self.alpha[1].into(),
+            beta: self.beta[1].into(),
+        }
+    }
+
+    pub fn grid_current(&self) -> AlphaBeta<Current> {
+        AlphaBeta {
+            alpha: self.alpha[2].into(),
+            beta: self.beta[2].into(),
+        }
+    }
+
+    /// The voltage at the point of common coupling, across the capacitor and damping resistor
+    pub fn pcc_voltage(&self) -> AlphaBeta<Voltage> {
+        let rd = self.damping;
+        AlphaBeta {
+            alpha: (self.alpha[1] + rd * (self.alpha[0] - self.alpha[2])).into(),
+            beta: (self.beta[1] + rd * (self.beta[0] - self.beta[2])).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::constants::PI;
+    use crate::tests::voltage;
+    use crate::trig::Theta;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-5;
+    const L1: Float = 2e-3;
+    const C: Float = 10e-6;
+    const L2: Float = 1e-3;
+
+    fn filter() -> LclFilter {
+        LclFilter::new((0.1.into(), L1), C, (0.05.into(), L2), TS)
+    }
+
+    #[test]
+    fn inverse() {
+        let m = [[2.0, -1.0, 0.5], [1.0, 3.0, 0.0], [-0.5, 1.0, 4.0]];
+        let product = multiply(&m, &invert(&m));
+        for (i, row) in product.iter().enumerate() {
+            for (j, &element) in row.iter().enumerate() {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                assert_abs_diff_eq!(element, identity, epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn dc() {
+        // in steady state the capacitor carries no current, so the current is limited by the resistances
+        let mut filter = filter().with_damping(2.0.into());
+        for _ in 0..100000 {
+            filter.step(voltage(3.0, -1.5), voltage(0.0, 0.0));
+        }
+        let i = filter.grid_current();
+        assert_abs_diff_eq!(Float::from(i.alpha), 20.0, epsilon = 5e-3);
+        assert_abs_diff_eq!(Float::from(i.beta), -10.0, epsilon = 5e-3);
+        let i1 = filter.inverter_current();
+        assert_abs_diff_eq!(Float::from(i1.alpha), 20.0, epsilon = 5e-3);
+        let pcc = filter.pcc_voltage();
+        assert_abs_diff_eq!(Float::from(pcc.alpha), 1.0, epsilon = 1e-3);
+    }
+
+    // the amplitude of the grid current over the last cycle of 0.2s of a 3 phase inverter voltage at a frequency
+    fn response(filter: &mut LclFilter, frequency: Float) -> Float {
+        let w = 2.0 * PI * frequency;
+        let cycle = (1.0 / (frequency * TS)) as usize;
+        let mut largest: Float = 0.0;
+        let samples = (0.2 / TS) as usize;
+        for k in 0..samples {
+            let theta = Theta::from_radians(w * ((k as Float) + 0.5) * TS);
+            let v = Abc::from_polar(Voltage::from(1.0), theta);
+            let i = filter.step_abc(v, Abc::from_polar(Voltage::from(0.0), theta));
+            if k >= samples - cycle {
+                largest = largest.max(Float::from(i.a).abs());
+            }
+        }
+        largest
+    }
+
+    #[test]
+    fn frequency_response() {
+        // well below the resonance, the filter is the series inductance
+        let w = 2.0 * PI * 60.0;
+        let expected = 1.0 / libm::Libm::<Float>::hypot(0.15, w * (L1 + L2));
+        assert_abs_diff_eq!(
+            response(&mut filter(), 60.0),
+            expected,
+            epsilon = 0.01 * expected
+        );
+
+        // the resonance, at sqrt((L1 + L2) / (L1 L2 C)), is damped by the resistor
+        let resonance = libm::Libm::<Float>::sqrt((L1 + L2) / (L1 * L2 * C)) / (2.0 * PI);
+        let undamped = response(&mut filter(), resonance);
+        let damped = response(&mut filter().with_damping(5.0.into()), resonance);
+        assert!(undamped > 5.0 * expected);
+        assert!(damped < 0.2 * undamped);
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Discrete-time plant models, for simulating controllers in closed loop
+
+1. [RlFilter] - A series RL filter, discretized exactly with a zero-order hold
+2. [LclFilter] - An LCL filter with an optional damping resistor, discretized with the Tustin transform
+3. [TheveninGrid] - A grid source driven by a [Waveform](crate::wavegen::Waveform), behind an RL impedance
+4. [AveragedInverter] - An average-value three-phase inverter, from duty cycles to terminal voltages, with DC link
+   dynamics and conduction losses
+
+The filters step [AlphaBeta](crate::AlphaBeta) states (or [Abc](crate::Abc) voltages and currents, as three-wire
+circuits) with the inverter and grid voltages held over each sample.
+
+```rust
+use ac_power::number::Float;
+use ac_power::plant::RlFilter;
+use ac_power::{AlphaBeta, Impedance, Voltage};
+use approx::assert_abs_diff_eq;
+
+// 10V across 0.5Ω and 5mH, for a time constant
+let mut filter = RlFilter::new(Impedance::from(0.5), 5e-3, 1e-4);
+let v = AlphaBeta { alpha: Voltage::from(10.0), beta: Voltage::from(0.0) };
+let e = AlphaBeta { alpha: Voltage::from(0.0), beta: Voltage::from(0.0) };
+let i = (0..100).map(|_| filter.step(v, e)).last().unwrap();
+assert_abs_diff_eq!(Float::from(i.alpha), 20.0 * 0.6321, epsilon = 1e-3);
+```
+*/
+
+mod grid;
+mod inverter;
+mod lcl;
+mod rl;
+
+pub use grid::TheveninGrid;
+pub use inverter::AveragedInverter;
+pub use lcl::LclFilter;
+pub use rl::RlFilter;
@@ -0,0 +1,144 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::newtypes::{Current, Impedance, Voltage};
+use crate::number::Float;
+use crate::reference_frames::{Abc, AlphaBeta};
+
+/// A series RL filter between the inverter and grid voltages
+///
+/// `L di/dt = v - R i - e`, discretized exactly for voltages held over each sample (zero-order hold):
+/// `i[k+1] = a i[k] + b (v[k] - e[k])`, where `a = exp(-R ts / L)` and `b = (1 - a) / R`.
+#[derive(Debug, Copy, Clone)]
+pub struct RlFilter {
+    a: Float,
+    b: Float,
+    current: AlphaBeta<Float>,
+}
+
+impl RlFilter {
+    /// Creates a filter from a resistance and inductance (in henries), stepped every `ts` seconds
+    pub fn new(resistance: Impedance, inductance: Float, ts: Float) -> Self {
+        let resistance = Float::from(resistance);
+        let a = libm::Libm::<Float>::exp(-resistance * ts / inductance);
+        // the limit of (1 - a) / R as R goes to zero
+        let b = if resistance > 0.0 {
+            (1.0 - a) / resistance
+        } else {
+            ts / inductance
+        };
+        Self {
+            a,
+            b,
+            current: AlphaBeta::zero(),
+        }
+    }
+
+    /// Steps the current through a sample, with the inverter and grid voltages held, returning the current at the
+    /// end of the sample
+    pub fn step(
+        &mut self,
+        inverter: AlphaBeta<Voltage>,
+        grid: AlphaBeta<Voltage>,
+    ) -> AlphaBeta<Current> {
+        self.current = AlphaBeta {
+            alpha: self.a * self.current.alpha + self.b * Float::from(inverter.alpha - grid.alpha),
+            beta: self.a * self.current.beta + self.b * Float::from(inverter.beta - grid.beta),
+        };
+        self.current()
+    }
+
+    /// Steps the current through a sample with phase voltages (as a three-wire circuit, so without zero sequence)
+    pub fn step_abc(&mut self, inverter: Abc<Voltage>, grid: Abc<Voltage>) -> Abc<Current> {
+        Abc::from(self.step(AlphaBeta::from(inverter), AlphaBeta::from(grid)))
+    }
+
+    pub fn current(&self) -> AlphaBeta<Current> {
+        AlphaBeta {
+            alpha: self.current.alpha.into(),
+            beta: self.current.beta.into(),
+        }
+    }
+
+    pub fn set_current(&mut self, current: AlphaBeta<Current>) {
+        self.current = AlphaBeta {
+            alpha: current.alpha.into(),
+            beta: current.beta.into(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::constants::PI;
+    use crate::tests::voltage;
+    use crate::trig::Theta;
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1e-4;
+    const R: Float = 0.5;
+    const L: Float = 5e-3;
+
+    #[test]
+    fn step_response() {
+        // exact at the samples, i = V / R (1 - exp(-t R / L))
+        let mut filter = RlFilter::new(R.into(), L, TS);
+        for k in 1..=200 {
+            let i = filter.step(voltage(10.0, -5.0), voltage(0.0, 0.0));
+            let t = (k as Float) * TS;
+            let expected = 1.0 - libm::Libm::<Float>::exp(-t * R / L);
+            assert_abs_diff_eq!(Float::from(i.alpha), 10.0 / R * expected, epsilon = 1e-3);
+            assert_abs_diff_eq!(Float::from(i.beta), -5.0 / R * expected, epsilon = 1e-3);
+        }
+
+        // and a pure inductance integrates the voltage
+        let mut filter = RlFilter::new(0.0.into(), L, TS);
+        let i = (0..100)
+            .map(|_| filter.step(voltage(10.0, 0.0), voltage(2.0, 0.0)))
+            .last()
+            .unwrap();
+        assert_abs_diff_eq!(Float::from(i.alpha), 8.0 * 100.0 * TS / L, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn sinusoidal() {
+        // the steady state current is V / |R + jωL|, lagging by atan(ωL / R)
+        let mut filter = RlFilter::new(R.into(), L, 1e-5);
+        let w = 2.0 * PI * 60.0;
+        let z = libm::Libm::<Float>::hypot(R, w * L);
+        let lag = libm::Libm::<Float>::atan2(w * L, R);
+
+        let mut i = Abc {
+            a: Current::from(0.0),
+            b: Current::from(0.0),
+            c: Current::from(0.0),
+        };
+        let samples = 100000;
+        for k in 0..samples {
+            // the voltage at the middle of the sample
+            let theta = Theta::from_radians(w * ((k as Float) + 0.5) * 1e-5);
+            let v = Abc::from_polar(Voltage::from(100.0), theta);
+            i = filter.step_abc(v, Abc::from_polar(Voltage::from(0.0), theta));
+        }
+
+        let theta = Theta::from_radians(w * (samples as Float) * 1e-5 - lag);
+        let expected = Abc::from_polar(100.0 / z, theta);
+        assert_abs_diff_eq!(Float::from(i.a), expected.a, epsilon = 0.02);
+        assert_abs_diff_eq!(Float::from(i.b), expected.b, epsilon = 0.02);
+        assert_abs_diff_eq!(Float::from(i.c), expected.c, epsilon = 0.02);
+    }
+}
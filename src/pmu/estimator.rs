@@ -0,0 +1,498 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::constants::{ONE_HALF, ONE_THIRD, PI, SQRT_2, SQRT_3_OVER_2};
+use crate::number::{Float, Num};
+use crate::reference_frames::{Abc, Dq, Polar};
+use crate::trig::{Backend, Libm, Theta};
+
+/// Parameters of the M class low-pass filter
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MClassFilter {
+    /// Cutoff frequency of the windowed sinc (in Hz)
+    pub frequency: Float,
+    /// Length of the filter window (in seconds)
+    pub window: Float,
+}
+
+impl MClassFilter {
+    /// Filter parameters of the standard's reference model for a reporting rate (in frames per second)
+    ///
+    /// # Panics
+    ///
+    /// If the reporting rate is not one of the standard's rates (10, 12, 15, 20, 25, 30, 50 or 60)
+    pub fn for_reporting_rate(rate: u32) -> Self {
+        let (frequency, window) = match rate {
+            10 => (1.779, 864.0 / 960.0),
+            12 => (1.996, 784.0 / 960.0),
+            15 => (2.344, 688.0 / 960.0),
+            20 => (3.195, 482.0 / 960.0),
+            25 => (4.355, 338.0 / 1000.0),
+            30 => (5.1506, 298.0 / 960.0),
+            50 => (7.75, 142.0 / 1000.0),
+            60 => (8.19, 164.0 / 960.0),
+            _ => panic!("unsupported M class reporting rate {}", rate),
+        };
+        Self { frequency, window }
+    }
+}
+
+/// Performance class of a [Pmu]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Class {
+    /// Protection class: a two cycle triangular filter, for a fast response
+    P,
+    /// Measurement class: a windowed sinc filter, which rejects out-of-band signals
+    M(MClassFilter),
+}
+
+/// A synchrophasor measurement
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Measurement<T> {
+    /// Index of the sample the measurement refers to (at the centre of the filter window)
+    pub sample: u64,
+    /// Phasor of each phase
+    pub phasors: Abc<Polar<T>>,
+    /// Positive sequence phasor
+    pub positive: Polar<T>,
+    /// Frequency (in Hz)
+    pub frequency: Float,
+    /// Rate of change of frequency (in Hz/s)
+    pub rocof: Float,
+}
+
+/// Phasor measurement unit, following the reference model of IEEE C37.118.1
+///
+/// The last `N` samples are kept for the filter, which must be at least the filter order plus half a cycle.
+pub struct Pmu<T, const N: usize> {
+    nominal: u32,
+    sample_rate: u32,
+    samples_per_report: u64,
+    class: Class,
+    weights: [Float; N],
+    half_order: usize,
+    spacing: usize,
+    buffer: [Abc<T>; N],
+    count: u64,
+}
+
+impl<T: Num, const N: usize> Pmu<T, N> {
+    /// Creates a PMU for a nominal frequency, sample rate and reporting rate (all in Hz)
+    ///
+    /// The sample rate must be at least four samples per nominal cycle, as frequency and ROCOF are calculated from
+    /// phasors a quarter cycle apart.
+    ///
+    /// # Panics
+    ///
+    /// If there are fewer than four samples per nominal cycle, the reporting rate does not divide the sample rate, or
+    /// the filter does not fit in `N` samples
+    pub fn new(class: Class, nominal: u32, sample_rate: u32, reporting_rate: u32) -> Self {
+        assert!(
+            nominal > 0 && sample_rate >= 4 * nominal,
+            "the sample rate must be at least four samples per nominal cycle"
+        );
+        assert!(
+            sample_rate.is_multiple_of(reporting_rate),
+            "the reporting rate must divide the sample rate"
+        );
+
+        let fs = sample_rate as Float;
+        let half_order = match class {
+            Class::P => (sample_rate / nominal) as usize - 1,
+            Class::M(filter) => (filter.window * fs * 0.5 + 0.5) as usize,
+        };
+
+        // frequency and ROCOF are differentiated from phasors a quarter cycle apart
+        let spacing = usize::max(1, (sample_rate / (4 * nominal)) as usize);
+        assert!(
+            2 * (half_order + spacing) < N,
+            "the filter does not fit in the sample buffer"
+        );
+
+        // filter weights from k = -half_order to half_order
+        let order = (2 * half_order) as Float;
+        let mut weights = [0.0; N];
+        for (i, weight) in weights.iter_mut().take(2 * half_order + 1).enumerate() {
+            let k = i as Float - half_order as Float;
+            *weight = match class {
+                Class::P => 1.0 - 2.0 * Float::abs(k) / (order + 2.0),
+                Class::M(filter) => {
+                    let x = 2.0 * PI * 2.0 * filter.frequency * k / fs;
+                    let sinc = if k == 0.0 {
+                        1.0
+                    } else {
+                        libm::Libm::<Float>::sin(x) / x
+                    };
+                    let hamming = 0.54 + 0.46 * libm::Libm::<Float>::cos(2.0 * PI * k / order);
+                    sinc * hamming
+                }
+            };
+        }
+
+        // normalize to unity gain, and scale from peak to rms
+        let gain: Float = weights.iter().sum();
+        for weight in weights.iter_mut() {
+            *weight *= SQRT_2 / gain;
+        }
+
+        Self {
+            nominal,
+            sample_rate,
+            samples_per_report: (sample_rate / reporting_rate) as u64,
+            class,
+            weights,
+            half_order,
+            spacing,
+            buffer: [Abc::zero(); N],
+            count: 0,
+        }
+    }
+
+    /// Adds a sample, returning a measurement at each reporting instant
+    ///
+    /// Measurements are delayed by half the filter window (plus a quarter cycle), and refer to the sample at the centre
+    /// of the window.
+    pub fn update(&mut self, abc: Abc<T>) -> Option<Measurement<T>> {
+        self.buffer[(self.count % N as u64) as usize] = abc;
+        self.count += 1;
+
+        let reach = (self.half_order + self.spacing) as u64;
+        if self.count <= 2 * reach {
+            return None;
+        }
+        let sample = self.count - 1 - reach;
+        if !sample.is_multiple_of(self.samples_per_report) {
+            return None;
+        }
+
+        let spacing = self.spacing as u64;
+        let before = positive_sequence(self.phasors(sample - spacing));
+        let phasors = self.phasors(sample);
+        let positive = positive_sequence(phasors);
+        let after = positive_sequence(self.phasors(sample + spacing));
+
+        // differentiate the positive sequence angle
+        let dt = (self.spacing as Float) / (self.sample_rate as Float);
+        let angle = |dq: Dq<Float>| Libm::atan2(dq.q, dq.d);
+        let rising = (angle(after) - angle(positive)).to_radians();
+        let falling = (angle(positive) - angle(before)).to_radians();
+        let deviation = (rising + falling) / (4.0 * PI * dt);
+        let frequency = self.nominal as Float + deviation;
+        let rocof = (rising - falling) / (2.0 * PI * dt * dt);
+
+        // the P class filter droops off-nominal, which the reference model corrects
+        let correction = match self.class {
+            Class::P => {
+                let nominal = self.nominal as Float;
+                1.0 / libm::Libm::<Float>::sin(PI * (nominal + 1.625 * deviation) / (2.0 * nominal))
+            }
+            Class::M(_) => 1.0,
+        };
+        let polar = |dq: Dq<Float>| Polar {
+            amplitude: T::from(libm::Libm::<Float>::hypot(dq.d, dq.q) * correction),
+            theta: Libm::atan2(dq.q, dq.d),
+        };
+
+        Some(Measurement {
+            sample,
+            phasors: Abc {
+                a: polar(phasors.a),
+                b: polar(phasors.b),
+                c: polar(phasors.c),
+            },
+            positive: polar(positive),
+            frequency,
+            rocof,
+        })
+    }
+
+    // phase of the nominal frequency cosine at a sample, calculated exactly so it doesn't drift
+    fn demodulation(&self, sample: u64) -> Theta {
+        let fs = self.sample_rate as u64;
+        let phase = (sample % fs) * (self.nominal as u64) % fs;
+        Theta::from(((phase << 32) / fs) as u32 as i32)
+    }
+
+    // filtered phasors (real part in d, imaginary part in q) centred on a sample
+    fn phasors(&self, sample: u64) -> Abc<Dq<Float>> {
+        let mut sum = Abc {
+            a: Dq::zero(),
+            b: Dq::zero(),
+            c: Dq::zero(),
+        };
+        let first = sample - self.half_order as u64;
+        for (i, weight) in self.weights[..=2 * self.half_order].iter().enumerate() {
+            let index = first + i as u64;
+            let abc = self.buffer[(index % N as u64) as usize];
+            // libm, since the error of the default cos/sin would show up in the ROCOF
+            let (cos, sin) = Libm::cos_sin(self.demodulation(index));
+            let cos = Float::from(cos) * weight;
+            let sin = -Float::from(sin) * weight;
+            sum.a.d += abc.a.into() * cos;
+            sum.a.q += abc.a.into() * sin;
+            sum.b.d += abc.b.into() * cos;
+            sum.b.q += abc.b.into() * sin;
+            sum.c.d += abc.c.into() * cos;
+            sum.c.q += abc.c.into() * sin;
+        }
+        sum
+    }
+}
+
+// rotates a complex phasor by +/-120 degrees
+fn rotate_120(dq: Dq<Float>, sin: Float) -> Dq<Float> {
+    Dq {
+        d: -ONE_HALF * dq.d - sin * dq.q,
+        q: sin * dq.d - ONE_HALF * dq.q,
+    }
+}
+
+// positive sequence component, (a + b rotated by 120 + c rotated by 240) / 3
+fn positive_sequence(phasors: Abc<Dq<Float>>) -> Dq<Float> {
+    let sum =
+        phasors.a + rotate_120(phasors.b, SQRT_3_OVER_2) + rotate_120(phasors.c, -SQRT_3_OVER_2);
+    Dq {
+        d: sum.d * ONE_THIRD,
+        q: sum.q * ONE_THIRD,
+    }
+}
+
+/// Total vector error of an estimated phasor against a reference, as a fraction
+pub fn tve<T: Num>(estimate: Polar<T>, reference: Polar<T>) -> Float {
+    let complex = |polar: Polar<T>| {
+        let (cos, sin) = Libm::cos_sin(polar.theta);
+        let amplitude: Float = polar.amplitude.into();
+        (amplitude * Float::from(cos), amplitude * Float::from(sin))
+    };
+    let (re, im) = complex(estimate);
+    let (re_ref, im_ref) = complex(reference);
+    libm::Libm::<Float>::hypot(re - re_ref, im - im_ref)
+        / libm::Libm::<Float>::hypot(re_ref, im_ref)
+}
+
+/// Largest total vector error, frequency error and ROCOF error over a series of measurements
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Errors {
+    /// Total vector error of the positive sequence phasor (as a fraction)
+    pub tve: Float,
+    /// Frequency error (in Hz)
+    pub fe: Float,
+    /// ROCOF error (in Hz/s)
+    pub rfe: Float,
+}
+
+impl Errors {
+    /// Compares a measurement against the reference positive sequence phasor, frequency and ROCOF
+    pub fn update<T: Num>(
+        &mut self,
+        measurement: &Measurement<T>,
+        positive: Polar<T>,
+        frequency: Float,
+        rocof: Float,
+    ) {
+        self.tve = Float::max(self.tve, tve(measurement.positive, positive));
+        self.fe = Float::max(self.fe, Float::abs(measurement.frequency - frequency));
+        self.rfe = Float::max(self.rfe, Float::abs(measurement.rocof - rocof));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::wavegen::{Event, EventKind, Magnitude, Scenario, Sequence, Waveform};
+    use approx::assert_abs_diff_eq;
+
+    const NOMINAL: u32 = 60;
+    const SAMPLE_RATE: u32 = 960;
+    const BUFFER: usize = 1024;
+
+    // samples of a scenario, with the phase of the fundamental at each sample
+    fn samples(
+        amplitude: Float,
+        events: &[Event<Float>],
+        seconds: Float,
+    ) -> Vec<(Theta, Abc<Float>)> {
+        let waveform: Waveform<Float, 1> = Waveform::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(amplitude),
+            Theta::from(0),
+        );
+        let mut scenario = Scenario::new(
+            waveform,
+            NOMINAL as Float,
+            1.0 / SAMPLE_RATE as Float,
+            events,
+        );
+        let count = (seconds * SAMPLE_RATE as Float) as usize;
+        (0..count)
+            .map(|_| {
+                let theta = scenario.theta();
+                (theta, scenario.next().unwrap())
+            })
+            .collect()
+    }
+
+    // reference phasor for a sample, from the phase of the (sine referenced) fundamental
+    fn reference(
+        pmu: &Pmu<Float, BUFFER>,
+        amplitude: Float,
+        theta: Theta,
+        sample: u64,
+    ) -> Polar<Float> {
+        Polar {
+            amplitude: amplitude / SQRT_2,
+            theta: theta - Theta::from_degrees(90.0) - pmu.demodulation(sample),
+        }
+    }
+
+    fn steady_state(class: Class, deviation: Float) -> Errors {
+        let events = [Event::new(0.0, None, EventKind::FrequencyStep(deviation))];
+        let samples = samples(1.0, &events, 2.0);
+        let mut pmu: Pmu<Float, BUFFER> = Pmu::new(class, NOMINAL, SAMPLE_RATE, 60);
+        let mut errors = Errors::default();
+        for (_, abc) in samples.iter() {
+            if let Some(measurement) = pmu.update(*abc) {
+                let (theta, _) = samples[measurement.sample as usize];
+                let positive = reference(&pmu, 1.0, theta, measurement.sample);
+                errors.update(&measurement, positive, NOMINAL as Float + deviation, 0.0);
+            }
+        }
+        errors
+    }
+
+    #[test]
+    #[should_panic]
+    fn undersampled() {
+        Pmu::<Float, 64>::new(Class::P, 60, 180, 60);
+    }
+
+    #[test]
+    fn p_class_steady_state() {
+        for deviation in [-2.0, -0.5, 0.0, 1.0, 2.0] {
+            let errors = steady_state(Class::P, deviation);
+            assert!(errors.tve < 0.01, "{}: {:?}", deviation, errors);
+            assert!(errors.fe < 0.005, "{}: {:?}", deviation, errors);
+            assert!(errors.rfe < 0.01, "{}: {:?}", deviation, errors);
+        }
+    }
+
+    #[test]
+    fn m_class_steady_state() {
+        let class = Class::M(MClassFilter::for_reporting_rate(60));
+        for deviation in [-5.0, -2.0, 0.0, 1.0, 5.0] {
+            let errors = steady_state(class, deviation);
+            assert!(errors.tve < 0.01, "{}: {:?}", deviation, errors);
+            assert!(errors.fe < 0.005, "{}: {:?}", deviation, errors);
+            assert!(errors.rfe < 0.01, "{}: {:?}", deviation, errors);
+        }
+    }
+
+    fn ramp(class: Class, rocof: Float, exclusion: Float) -> Errors {
+        let (start, duration) = (0.5, 1.0);
+        let events = [Event::new(
+            start,
+            Some(duration),
+            EventKind::FrequencyRamp(rocof),
+        )];
+        let samples = samples(1.0, &events, 2.0);
+        let mut pmu: Pmu<Float, BUFFER> = Pmu::new(class, NOMINAL, SAMPLE_RATE, 60);
+        let mut errors = Errors::default();
+        for (_, abc) in samples.iter() {
+            if let Some(measurement) = pmu.update(*abc) {
+                // skip the measurements around the start and end of the ramp
+                let t = measurement.sample as Float / SAMPLE_RATE as Float;
+                if t < start + exclusion || t > start + duration - exclusion {
+                    continue;
+                }
+                let (theta, _) = samples[measurement.sample as usize];
+                let positive = reference(&pmu, 1.0, theta, measurement.sample);
+                let frequency = NOMINAL as Float + rocof * (t - start);
+                errors.update(&measurement, positive, frequency, rocof);
+            }
+        }
+        errors
+    }
+
+    #[test]
+    fn frequency_ramp() {
+        let errors = ramp(Class::P, 1.0, 2.0 / 60.0);
+        assert!(errors.tve < 0.01, "{:?}", errors);
+        assert!(errors.fe < 0.01, "{:?}", errors);
+        assert!(errors.rfe < 0.4, "{:?}", errors);
+
+        let class = Class::M(MClassFilter::for_reporting_rate(60));
+        let errors = ramp(class, -1.0, 7.0 / 60.0);
+        assert!(errors.tve < 0.01, "{:?}", errors);
+        assert!(errors.fe < 0.01, "{:?}", errors);
+        assert!(errors.rfe < 0.2, "{:?}", errors);
+    }
+
+    #[test]
+    fn per_phase() {
+        let scale = Abc {
+            a: 1.0,
+            b: 0.9,
+            c: 1.1,
+        };
+        let events = [Event::new(0.0, None, EventKind::Amplitude(scale))];
+        let samples = samples(100.0, &events, 0.5);
+        let mut pmu: Pmu<Float, BUFFER> = Pmu::new(Class::P, NOMINAL, SAMPLE_RATE, 30);
+        let mut count = 0;
+        for (_, abc) in samples.iter() {
+            if let Some(measurement) = pmu.update(*abc) {
+                let (theta, _) = samples[measurement.sample as usize];
+                let a = reference(&pmu, 100.0, theta, measurement.sample);
+                let b = Polar {
+                    amplitude: a.amplitude * 0.9,
+                    theta: a.theta - Theta::from_degrees(120.0),
+                };
+                let c = Polar {
+                    amplitude: a.amplitude * 1.1,
+                    theta: a.theta + Theta::from_degrees(120.0),
+                };
+                assert!(tve(measurement.phasors.a, a) < 1e-3);
+                assert!(tve(measurement.phasors.b, b) < 1e-3);
+                assert!(tve(measurement.phasors.c, c) < 1e-3);
+                assert_abs_diff_eq!(measurement.positive.amplitude, a.amplitude, epsilon = 0.01);
+                assert_eq!(measurement.sample % 32, 0);
+                count += 1;
+            }
+        }
+        // 30 reports per second, less the filter delay
+        assert_eq!(count, 14);
+    }
+
+    #[test]
+    fn tve_definition() {
+        let reference = Polar {
+            amplitude: 1.0,
+            theta: Theta::from_degrees(30.0),
+        };
+        let estimate = Polar {
+            amplitude: 1.01,
+            theta: Theta::from_degrees(30.0),
+        };
+        assert_abs_diff_eq!(tve(estimate, reference), 0.01, epsilon = 1e-5);
+
+        // a 1% TVE is about 0.573 degrees of phase error
+        let estimate = Polar {
+            amplitude: 1.0,
+            theta: Theta::from_degrees(30.573),
+        };
+        assert_abs_diff_eq!(tve(estimate, reference), 0.01, epsilon = 1e-4);
+    }
+}
@@ -0,0 +1,1480 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+IEEE C37.118.2 frame encoding and decoding
+
+Data, configuration (CFG-2 and CFG-3), header and command frames are encoded into and decoded from byte slices,
+without allocating.  Each frame carries a single PMU, with `P` phasor, `A` analog and `D` digital channels.  Decoded
+names borrow from the frame bytes.
+
+Frames are encoded with version 2 (C37.118.2-2011) in the SYNC word, and frames of version 1 or 2 are decoded.  The
+CFG-3 phasor modification flags and angle adjustments are encoded as zero, and ignored when decoding.
+
+```rust
+use ac_power::number::Float;
+use ac_power::pmu::frame::{Config, DataFrame, Format, PhasorChannel, PhasorKind, Stat, Time};
+use ac_power::trig::Theta;
+use ac_power::Polar;
+
+let config: Config<1, 0, 0> = Config {
+    station: "STATION A",
+    phasors: [PhasorChannel {
+        name: "VA",
+        kind: PhasorKind::Voltage,
+        scale: 0.01,
+    }],
+    format: Format {
+        polar: true,
+        ..Format::default()
+    },
+    ..Config::default()
+};
+
+let frame: DataFrame<Float, 1, 0, 0> = DataFrame {
+    id_code: config.id_code,
+    time: Time::from_sample(960 * 1000, 960, config.time_base),
+    stat: Stat::default(),
+    phasors: [Polar {
+        amplitude: 120.0,
+        theta: Theta::from_degrees(30.0),
+    }],
+    frequency: 60.01,
+    rocof: 0.0,
+    analogs: [],
+    digitals: [],
+};
+
+let mut buffer = [0u8; 64];
+let length = frame.encode(&config, &mut buffer).unwrap();
+let decoded: DataFrame<Float, 1, 0, 0> = DataFrame::decode(&buffer[..length], &config).unwrap();
+assert_eq!(decoded.time.soc, 1000);
+```
+*/
+
+use crate::number::{Float, Num};
+use crate::reference_frames::Polar;
+use crate::trig::{Backend, Libm, Theta};
+use core::convert::TryFrom;
+
+const SYNC: u8 = 0xAA;
+const VERSION: u8 = 2;
+
+/// Errors encoding or decoding a frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The frame is truncated, or its FRAMESIZE doesn't match
+    Length,
+    /// The buffer is too small for the encoded frame
+    BufferTooSmall,
+    /// The frame doesn't start with the SYNC byte, or has an unknown version
+    Sync,
+    /// The frame is a different type to the one being decoded
+    FrameType(FrameType),
+    /// The CHK word doesn't match the CRC of the frame
+    Checksum,
+    /// The frame holds more than one PMU, or a different number of channels to the frame type
+    Channels,
+    /// A name isn't valid UTF-8, or is too long for the frame
+    Name,
+}
+
+/// Type of a frame, from its SYNC word
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrameType {
+    Data,
+    Header,
+    Cfg1,
+    Cfg2,
+    Command,
+    Cfg3,
+}
+
+impl FrameType {
+    /// Reads the type of a frame, to choose how to decode it
+    pub fn of(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 2 {
+            return Err(Error::Length);
+        }
+        let version = bytes[1] & 0x0f;
+        if bytes[0] != SYNC || !(1..=2).contains(&version) {
+            return Err(Error::Sync);
+        }
+        match (bytes[1] >> 4) & 0x07 {
+            0 => Ok(FrameType::Data),
+            1 => Ok(FrameType::Header),
+            2 => Ok(FrameType::Cfg1),
+            3 => Ok(FrameType::Cfg2),
+            4 => Ok(FrameType::Command),
+            5 => Ok(FrameType::Cfg3),
+            _ => Err(Error::Sync),
+        }
+    }
+
+    fn bits(self) -> u8 {
+        match self {
+            FrameType::Data => 0,
+            FrameType::Header => 1,
+            FrameType::Cfg1 => 2,
+            FrameType::Cfg2 => 3,
+            FrameType::Command => 4,
+            FrameType::Cfg3 => 5,
+        }
+    }
+}
+
+/// CRC-CCITT of a frame, as used for the CHK word (polynomial 0x1021, initial value 0xFFFF)
+pub fn crc_ccitt(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Time stamp of a frame
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Time {
+    /// Second of century (seconds since the UNIX epoch)
+    pub soc: u32,
+    /// Fraction of the second, in units of 1/TIME_BASE (24 bits)
+    pub fraction: u32,
+    /// Time quality flags (the top byte of FRACSEC)
+    pub quality: u8,
+}
+
+impl Time {
+    /// Time stamp of a sample, counting samples from the top of a second of century
+    pub fn from_sample(sample: u64, sample_rate: u32, time_base: u32) -> Self {
+        let sample_rate = sample_rate as u64;
+        Self {
+            soc: (sample / sample_rate) as u32,
+            fraction: ((sample % sample_rate) * time_base as u64 / sample_rate) as u32,
+            quality: 0,
+        }
+    }
+}
+
+/// Data error code of a [Stat] word
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum DataError {
+    #[default]
+    Good,
+    /// PMU error, no information about the data
+    PmuError,
+    /// PMU in test mode, or absent data tags inserted
+    TestMode,
+    /// PMU error, do not use the values
+    DoNotUse,
+}
+
+/// The STAT word of a data frame
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Stat {
+    pub error: DataError,
+    /// The PMU has lost time synchronization
+    pub sync_lost: bool,
+    /// Data is sorted by arrival rather than time stamp
+    pub sorted_by_arrival: bool,
+    pub trigger: bool,
+    /// The configuration will change in one minute
+    pub config_change: bool,
+    /// The data has been modified by a post-processing device
+    pub modified: bool,
+    /// PMU time quality (3 bits)
+    pub time_quality: u8,
+    /// Time since synchronization was lost (2 bits)
+    pub unlocked_time: u8,
+    /// Trigger reason (4 bits)
+    pub trigger_reason: u8,
+}
+
+impl From<u16> for Stat {
+    fn from(word: u16) -> Self {
+        let error = match word >> 14 {
+            0 => DataError::Good,
+            1 => DataError::PmuError,
+            2 => DataError::TestMode,
+            _ => DataError::DoNotUse,
+        };
+        Self {
+            error,
+            sync_lost: word & (1 << 13) != 0,
+            sorted_by_arrival: word & (1 << 12) != 0,
+            trigger: word & (1 << 11) != 0,
+            config_change: word & (1 << 10) != 0,
+            modified: word & (1 << 9) != 0,
+            time_quality: ((word >> 6) & 0x07) as u8,
+            unlocked_time: ((word >> 4) & 0x03) as u8,
+            trigger_reason: (word & 0x0f) as u8,
+        }
+    }
+}
+
+impl From<Stat> for u16 {
+    fn from(stat: Stat) -> Self {
+        let error = match stat.error {
+            DataError::Good => 0,
+            DataError::PmuError => 1,
+            DataError::TestMode => 2,
+            DataError::DoNotUse => 3,
+        };
+        (error << 14)
+            | ((stat.sync_lost as u16) << 13)
+            | ((stat.sorted_by_arrival as u16) << 12)
+            | ((stat.trigger as u16) << 11)
+            | ((stat.config_change as u16) << 10)
+            | ((stat.modified as u16) << 9)
+            | (((stat.time_quality & 0x07) as u16) << 6)
+            | (((stat.unlocked_time & 0x03) as u16) << 4)
+            | ((stat.trigger_reason & 0x0f) as u16)
+    }
+}
+
+/// The FORMAT word of a configuration, which selects the number formats of the data frames
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Format {
+    /// Phasors are polar (magnitude and angle), rather than rectangular
+    pub polar: bool,
+    /// Phasors are 32-bit floats, rather than 16-bit integers
+    pub float_phasors: bool,
+    /// Analogs are 32-bit floats, rather than 16-bit integers
+    pub float_analogs: bool,
+    /// Frequency and ROCOF are 32-bit floats, rather than 16-bit integers
+    pub float_frequency: bool,
+}
+
+impl From<u16> for Format {
+    fn from(word: u16) -> Self {
+        Self {
+            polar: word & 1 != 0,
+            float_phasors: word & 2 != 0,
+            float_analogs: word & 4 != 0,
+            float_frequency: word & 8 != 0,
+        }
+    }
+}
+
+impl From<Format> for u16 {
+    fn from(format: Format) -> Self {
+        (format.polar as u16)
+            | ((format.float_phasors as u16) << 1)
+            | ((format.float_analogs as u16) << 2)
+            | ((format.float_frequency as u16) << 3)
+    }
+}
+
+/// Whether a phasor channel is a voltage or current
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum PhasorKind {
+    #[default]
+    Voltage,
+    Current,
+}
+
+/// A phasor channel of a configuration
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct PhasorChannel<'a> {
+    pub name: &'a str,
+    pub kind: PhasorKind,
+    /// Volts or amps per bit of the integer format (a multiple of 1e-5 in CFG-2)
+    pub scale: Float,
+}
+
+/// The kind of an analog channel (CFG-2 only)
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum AnalogKind {
+    #[default]
+    PointOnWave,
+    Rms,
+    Peak,
+}
+
+/// An analog channel of a configuration
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct AnalogChannel<'a> {
+    pub name: &'a str,
+    pub kind: AnalogKind,
+    /// Value per bit of the integer format (an integer in CFG-2)
+    pub scale: Float,
+    /// Offset of the value (CFG-3 only)
+    pub offset: Float,
+}
+
+/// A digital status word of a configuration
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct DigitalChannel<'a> {
+    /// Names of each bit, from the least significant
+    pub names: [&'a str; 16],
+    /// Normal status of the inputs
+    pub normal: u16,
+    /// Which inputs are valid
+    pub valid: u16,
+}
+
+/// The additional PMU information in a CFG-3 frame
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// Global PMU ID
+    pub global_id: [u8; 16],
+    /// Latitude, longitude (in degrees) and elevation (in meters)
+    pub latitude: Float,
+    pub longitude: Float,
+    pub elevation: Float,
+    /// Service class (b'P' or b'M')
+    pub class: u8,
+    /// Length of the measurement window (in microseconds)
+    pub window: i32,
+    /// Group delay of the measurement (in microseconds)
+    pub group_delay: i32,
+}
+
+/// A configuration frame (CFG-2 or CFG-3) for a single PMU
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Config<'a, const P: usize, const A: usize, const D: usize> {
+    /// ID of the data stream
+    pub id_code: u16,
+    pub time: Time,
+    /// Resolution of the fraction of second time stamps
+    pub time_base: u32,
+    pub station: &'a str,
+    /// ID of the PMU within the stream
+    pub pmu_id: u16,
+    pub format: Format,
+    pub phasors: [PhasorChannel<'a>; P],
+    pub analogs: [AnalogChannel<'a>; A],
+    pub digitals: [DigitalChannel<'a>; D],
+    /// Nominal frequency (50 or 60Hz)
+    pub nominal: u32,
+    /// Configuration change count
+    pub config_count: u16,
+    /// Frames per second (or seconds per frame if negative)
+    pub data_rate: i16,
+}
+
+impl<const P: usize, const A: usize, const D: usize> Default for Config<'_, P, A, D> {
+    fn default() -> Self {
+        Self {
+            id_code: 1,
+            time: Time::default(),
+            time_base: 1_000_000,
+            station: "",
+            pmu_id: 1,
+            format: Format::default(),
+            phasors: [PhasorChannel::default(); P],
+            analogs: [AnalogChannel::default(); A],
+            digitals: [DigitalChannel::default(); D],
+            nominal: 60,
+            config_count: 0,
+            data_rate: 60,
+        }
+    }
+}
+
+// writes big endian fields into a buffer
+struct Writer<'b> {
+    buffer: &'b mut [u8],
+    position: usize,
+}
+
+impl<'b> Writer<'b> {
+    // starts a frame, leaving FRAMESIZE to be filled in by finish
+    fn start(
+        buffer: &'b mut [u8],
+        frame_type: FrameType,
+        id_code: u16,
+        time: Time,
+    ) -> Result<Self, Error> {
+        let mut writer = Self {
+            buffer,
+            position: 0,
+        };
+        writer.bytes(&[SYNC, (frame_type.bits() << 4) | VERSION, 0, 0])?;
+        writer.u16(id_code)?;
+        writer.u32(time.soc)?;
+        writer.u32(((time.quality as u32) << 24) | (time.fraction & 0x00ff_ffff))?;
+        Ok(writer)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let end = self.position + bytes.len();
+        self.buffer
+            .get_mut(self.position..end)
+            .ok_or(Error::BufferTooSmall)?
+            .copy_from_slice(bytes);
+        self.position = end;
+        Ok(())
+    }
+
+    fn u8(&mut self, value: u8) -> Result<(), Error> {
+        self.bytes(&[value])
+    }
+
+    fn u16(&mut self, value: u16) -> Result<(), Error> {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn i16(&mut self, value: i16) -> Result<(), Error> {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn u32(&mut self, value: u32) -> Result<(), Error> {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn i32(&mut self, value: i32) -> Result<(), Error> {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    #[allow(clippy::unnecessary_cast)]
+    fn f32(&mut self, value: Float) -> Result<(), Error> {
+        self.bytes(&(value as f32).to_be_bytes())
+    }
+
+    // a CFG-2 name, space padded to 16 bytes
+    fn fixed_name(&mut self, name: &str) -> Result<(), Error> {
+        if name.len() > 16 {
+            return Err(Error::Name);
+        }
+        let mut padded = [b' '; 16];
+        padded[..name.len()].copy_from_slice(name.as_bytes());
+        self.bytes(&padded)
+    }
+
+    // a CFG-3 name, prefixed by its length
+    fn variable_name(&mut self, name: &str) -> Result<(), Error> {
+        let length = u8::try_from(name.len()).map_err(|_| Error::Name)?;
+        self.u8(length)?;
+        self.bytes(name.as_bytes())
+    }
+
+    // fills in FRAMESIZE and appends CHK, returning the length of the frame
+    fn finish(mut self) -> Result<usize, Error> {
+        let size = u16::try_from(self.position + 2).map_err(|_| Error::BufferTooSmall)?;
+        self.buffer[2..4].copy_from_slice(&size.to_be_bytes());
+        let crc = crc_ccitt(&self.buffer[..self.position]);
+        self.u16(crc)?;
+        Ok(self.position)
+    }
+}
+
+// reads big endian fields from a frame
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    // checks the SYNC, FRAMESIZE and CHK of a frame, and reads its ID and time stamp
+    fn start(bytes: &'a [u8], frame_type: FrameType) -> Result<(Self, u16, Time), Error> {
+        let actual = FrameType::of(bytes)?;
+        if actual != frame_type {
+            return Err(Error::FrameType(actual));
+        }
+        if bytes.len() < 16 {
+            return Err(Error::Length);
+        }
+        let size = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        if size < 16 || size > bytes.len() {
+            return Err(Error::Length);
+        }
+        let bytes = &bytes[..size];
+        let chk = u16::from_be_bytes([bytes[size - 2], bytes[size - 1]]);
+        if crc_ccitt(&bytes[..size - 2]) != chk {
+            return Err(Error::Checksum);
+        }
+
+        let mut reader = Self {
+            bytes: &bytes[..size - 2],
+            position: 4,
+        };
+        let id_code = reader.u16()?;
+        let soc = reader.u32()?;
+        let fracsec = reader.u32()?;
+        let time = Time {
+            soc,
+            fraction: fracsec & 0x00ff_ffff,
+            quality: (fracsec >> 24) as u8,
+        };
+        Ok((reader, id_code, time))
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], Error> {
+        let end = self.position + length;
+        let bytes = self.bytes.get(self.position..end).ok_or(Error::Length)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array<const L: usize>(&mut self) -> Result<[u8; L], Error> {
+        let mut array = [0; L];
+        array.copy_from_slice(self.bytes(L)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn i16(&mut self) -> Result<i16, Error> {
+        Ok(i16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, Error> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<Float, Error> {
+        Ok(f32::from_be_bytes(self.array()?) as Float)
+    }
+
+    fn fixed_name(&mut self) -> Result<&'a str, Error> {
+        let name = core::str::from_utf8(self.bytes(16)?).map_err(|_| Error::Name)?;
+        Ok(name.trim_end_matches([' ', '\0']))
+    }
+
+    fn variable_name(&mut self) -> Result<&'a str, Error> {
+        let length = self.u8()? as usize;
+        core::str::from_utf8(self.bytes(length)?).map_err(|_| Error::Name)
+    }
+
+    // the whole frame must be consumed
+    fn finish(&self) -> Result<(), Error> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(Error::Length)
+        }
+    }
+}
+
+// rounds and saturates to an i16
+fn to_i16(value: Float) -> i16 {
+    let value = if value < 0.0 {
+        value - 0.5
+    } else {
+        value + 0.5
+    };
+    value as i16
+}
+
+impl<'a, const P: usize, const A: usize, const D: usize> Config<'a, P, A, D> {
+    /// Encodes a CFG-2 frame, returning its length
+    pub fn encode_cfg2(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = Writer::start(buffer, FrameType::Cfg2, self.id_code, self.time)?;
+        writer.u32(self.time_base)?;
+        writer.u16(1)?;
+        writer.fixed_name(self.station)?;
+        self.write_counts(&mut writer)?;
+        for phasor in self.phasors.iter() {
+            writer.fixed_name(phasor.name)?;
+        }
+        for analog in self.analogs.iter() {
+            writer.fixed_name(analog.name)?;
+        }
+        for digital in self.digitals.iter() {
+            for name in digital.names.iter() {
+                writer.fixed_name(name)?;
+            }
+        }
+        for phasor in self.phasors.iter() {
+            // the scale is a 24-bit integer, in units of 1e-5 per bit
+            let scale = (phasor.scale * 1e5 + 0.5) as u32 & 0x00ff_ffff;
+            let kind = match phasor.kind {
+                PhasorKind::Voltage => 0,
+                PhasorKind::Current => 1,
+            };
+            writer.u32((kind << 24) | scale)?;
+        }
+        for analog in self.analogs.iter() {
+            let kind = match analog.kind {
+                AnalogKind::PointOnWave => 0,
+                AnalogKind::Rms => 1,
+                AnalogKind::Peak => 2,
+            };
+            let scale = (to_i32(analog.scale) as u32) & 0x00ff_ffff;
+            writer.u32((kind << 24) | scale)?;
+        }
+        self.write_digital_units(&mut writer)?;
+        self.write_trailer(&mut writer)?;
+        writer.finish()
+    }
+
+    /// Encodes a CFG-3 frame, returning its length
+    pub fn encode_cfg3(&self, metadata: &Metadata, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = Writer::start(buffer, FrameType::Cfg3, self.id_code, self.time)?;
+        // CONT_IDX, as the configuration is never fragmented
+        writer.u16(0)?;
+        writer.u32(self.time_base)?;
+        writer.u16(1)?;
+        writer.variable_name(self.station)?;
+        writer.u16(self.pmu_id)?;
+        writer.bytes(&metadata.global_id)?;
+        writer.u16(self.format.into())?;
+        writer.u16(P as u16)?;
+        writer.u16(A as u16)?;
+        writer.u16(D as u16)?;
+        for phasor in self.phasors.iter() {
+            writer.variable_name(phasor.name)?;
+        }
+        for analog in self.analogs.iter() {
+            writer.variable_name(analog.name)?;
+        }
+        for digital in self.digitals.iter() {
+            for name in digital.names.iter() {
+                writer.variable_name(name)?;
+            }
+        }
+        for phasor in self.phasors.iter() {
+            let kind = match phasor.kind {
+                PhasorKind::Voltage => 0,
+                PhasorKind::Current => 1 << 3,
+            };
+            // modification flags, phasor type and a user defined byte
+            writer.bytes(&[0, 0, kind, 0])?;
+            writer.f32(phasor.scale)?;
+            // angle adjustment
+            writer.f32(0.0)?;
+        }
+        for analog in self.analogs.iter() {
+            writer.f32(analog.scale)?;
+            writer.f32(analog.offset)?;
+        }
+        self.write_digital_units(&mut writer)?;
+        writer.f32(metadata.latitude)?;
+        writer.f32(metadata.longitude)?;
+        writer.f32(metadata.elevation)?;
+        writer.u8(metadata.class)?;
+        writer.i32(metadata.window)?;
+        writer.i32(metadata.group_delay)?;
+        self.write_trailer(&mut writer)?;
+        writer.finish()
+    }
+
+    /// Decodes a CFG-2 frame
+    pub fn decode_cfg2(bytes: &'a [u8]) -> Result<Self, Error> {
+        let (mut reader, id_code, time) = Reader::start(bytes, FrameType::Cfg2)?;
+        let time_base = reader.u32()?;
+        if reader.u16()? != 1 {
+            return Err(Error::Channels);
+        }
+        let station = reader.fixed_name()?;
+        let (pmu_id, format) = Self::read_counts(&mut reader)?;
+
+        let mut config = Self {
+            id_code,
+            time,
+            time_base,
+            station,
+            pmu_id,
+            format,
+            ..Self::default()
+        };
+        for phasor in config.phasors.iter_mut() {
+            phasor.name = reader.fixed_name()?;
+        }
+        for analog in config.analogs.iter_mut() {
+            analog.name = reader.fixed_name()?;
+        }
+        for digital in config.digitals.iter_mut() {
+            for name in digital.names.iter_mut() {
+                *name = reader.fixed_name()?;
+            }
+        }
+        for phasor in config.phasors.iter_mut() {
+            let unit = reader.u32()?;
+            phasor.kind = if unit >> 24 == 0 {
+                PhasorKind::Voltage
+            } else {
+                PhasorKind::Current
+            };
+            phasor.scale = ((unit & 0x00ff_ffff) as Float) * 1e-5;
+        }
+        for analog in config.analogs.iter_mut() {
+            let unit = reader.u32()?;
+            analog.kind = match unit >> 24 {
+                1 => AnalogKind::Rms,
+                2 => AnalogKind::Peak,
+                _ => AnalogKind::PointOnWave,
+            };
+            // sign extend the 24-bit scale
+            analog.scale = (((unit << 8) as i32) >> 8) as Float;
+        }
+        config.read_digital_units(&mut reader)?;
+        config.read_trailer(&mut reader)?;
+        Ok(config)
+    }
+
+    /// Decodes a CFG-3 frame
+    pub fn decode_cfg3(bytes: &'a [u8]) -> Result<(Self, Metadata), Error> {
+        let (mut reader, id_code, time) = Reader::start(bytes, FrameType::Cfg3)?;
+        if reader.u16()? != 0 {
+            // fragmented configurations aren't supported
+            return Err(Error::Length);
+        }
+        let time_base = reader.u32()?;
+        if reader.u16()? != 1 {
+            return Err(Error::Channels);
+        }
+        let station = reader.variable_name()?;
+        let pmu_id = reader.u16()?;
+        let global_id = reader.array()?;
+        let format = reader.u16()?.into();
+        if (reader.u16()?, reader.u16()?, reader.u16()?) != (P as u16, A as u16, D as u16) {
+            return Err(Error::Channels);
+        }
+
+        let mut config = Self {
+            id_code,
+            time,
+            time_base,
+            station,
+            pmu_id,
+            format,
+            ..Self::default()
+        };
+        for phasor in config.phasors.iter_mut() {
+            phasor.name = reader.variable_name()?;
+        }
+        for analog in config.analogs.iter_mut() {
+            analog.name = reader.variable_name()?;
+        }
+        for digital in config.digitals.iter_mut() {
+            for name in digital.names.iter_mut() {
+                *name = reader.variable_name()?;
+            }
+        }
+        for phasor in config.phasors.iter_mut() {
+            let flags: [u8; 4] = reader.array()?;
+            phasor.kind = if flags[2] & (1 << 3) == 0 {
+                PhasorKind::Voltage
+            } else {
+                PhasorKind::Current
+            };
+            phasor.scale = reader.f32()?;
+            reader.f32()?;
+        }
+        for analog in config.analogs.iter_mut() {
+            analog.scale = reader.f32()?;
+            analog.offset = reader.f32()?;
+        }
+        config.read_digital_units(&mut reader)?;
+        let metadata = Metadata {
+            global_id,
+            latitude: reader.f32()?,
+            longitude: reader.f32()?,
+            elevation: reader.f32()?,
+            class: reader.u8()?,
+            window: reader.i32()?,
+            group_delay: reader.i32()?,
+        };
+        config.read_trailer(&mut reader)?;
+        Ok((config, metadata))
+    }
+
+    // IDCODE, FORMAT and the channel counts of a CFG-2 frame
+    fn write_counts(&self, writer: &mut Writer) -> Result<(), Error> {
+        writer.u16(self.pmu_id)?;
+        writer.u16(self.format.into())?;
+        writer.u16(P as u16)?;
+        writer.u16(A as u16)?;
+        writer.u16(D as u16)
+    }
+
+    fn read_counts(reader: &mut Reader) -> Result<(u16, Format), Error> {
+        let pmu_id = reader.u16()?;
+        let format = reader.u16()?.into();
+        if (reader.u16()?, reader.u16()?, reader.u16()?) != (P as u16, A as u16, D as u16) {
+            return Err(Error::Channels);
+        }
+        Ok((pmu_id, format))
+    }
+
+    fn write_digital_units(&self, writer: &mut Writer) -> Result<(), Error> {
+        for digital in self.digitals.iter() {
+            writer.u16(digital.normal)?;
+            writer.u16(digital.valid)?;
+        }
+        Ok(())
+    }
+
+    fn read_digital_units(&mut self, reader: &mut Reader) -> Result<(), Error> {
+        for digital in self.digitals.iter_mut() {
+            digital.normal = reader.u16()?;
+            digital.valid = reader.u16()?;
+        }
+        Ok(())
+    }
+
+    // FNOM, CFGCNT and DATA_RATE
+    fn write_trailer(&self, writer: &mut Writer) -> Result<(), Error> {
+        writer.u16(if self.nominal == 50 { 1 } else { 0 })?;
+        writer.u16(self.config_count)?;
+        writer.i16(self.data_rate)
+    }
+
+    fn read_trailer(&mut self, reader: &mut Reader) -> Result<(), Error> {
+        self.nominal = if reader.u16()? & 1 == 1 { 50 } else { 60 };
+        self.config_count = reader.u16()?;
+        self.data_rate = reader.i16()?;
+        reader.finish()
+    }
+}
+
+// rounds and saturates to an i32
+fn to_i32(value: Float) -> i32 {
+    let value = if value < 0.0 {
+        value - 0.5
+    } else {
+        value + 0.5
+    };
+    value as i32
+}
+
+/// A data frame for a single PMU, in the formats of a [Config]
+///
+/// Phasor amplitudes are rms volts or amps, and analogs are scaled by the configuration when they are integers.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DataFrame<T, const P: usize, const A: usize, const D: usize> {
+    pub id_code: u16,
+    pub time: Time,
+    pub stat: Stat,
+    pub phasors: [Polar<T>; P],
+    /// Frequency (in Hz)
+    pub frequency: Float,
+    /// Rate of change of frequency (in Hz/s)
+    pub rocof: Float,
+    pub analogs: [Float; A],
+    pub digitals: [u16; D],
+}
+
+impl<T: Num, const P: usize, const A: usize, const D: usize> DataFrame<T, P, A, D> {
+    /// Encodes the data frame, returning its length
+    pub fn encode(&self, config: &Config<P, A, D>, buffer: &mut [u8]) -> Result<usize, Error> {
+        let format = config.format;
+        let mut writer = Writer::start(buffer, FrameType::Data, self.id_code, self.time)?;
+        writer.u16(self.stat.into())?;
+
+        for (phasor, channel) in self.phasors.iter().zip(config.phasors.iter()) {
+            let amplitude: Float = phasor.amplitude.into();
+            match (format.float_phasors, format.polar) {
+                (true, true) => {
+                    writer.f32(amplitude)?;
+                    writer.f32(phasor.theta.to_radians())?;
+                }
+                (true, false) => {
+                    let (re, im) = rectangular(amplitude, phasor.theta);
+                    writer.f32(re)?;
+                    writer.f32(im)?;
+                }
+                (false, true) => {
+                    let magnitude = amplitude / channel.scale + 0.5;
+                    writer.u16(magnitude as u16)?;
+                    // angle in units of 1e-4 radians
+                    writer.i16(to_i16(phasor.theta.to_radians() * 1e4))?;
+                }
+                (false, false) => {
+                    let (re, im) = rectangular(amplitude, phasor.theta);
+                    writer.i16(to_i16(re / channel.scale))?;
+                    writer.i16(to_i16(im / channel.scale))?;
+                }
+            }
+        }
+
+        if format.float_frequency {
+            writer.f32(self.frequency)?;
+            writer.f32(self.rocof)?;
+        } else {
+            // deviation from nominal in mHz, and ROCOF in units of 0.01Hz/s
+            writer.i16(to_i16((self.frequency - config.nominal as Float) * 1e3))?;
+            writer.i16(to_i16(self.rocof * 1e2))?;
+        }
+
+        for (analog, channel) in self.analogs.iter().zip(config.analogs.iter()) {
+            if format.float_analogs {
+                writer.f32(*analog)?;
+            } else {
+                writer.i16(to_i16((analog - channel.offset) / channel.scale))?;
+            }
+        }
+
+        for digital in self.digitals.iter() {
+            writer.u16(*digital)?;
+        }
+        writer.finish()
+    }
+
+    /// Decodes a data frame
+    pub fn decode(bytes: &[u8], config: &Config<P, A, D>) -> Result<Self, Error> {
+        let format = config.format;
+        let (mut reader, id_code, time) = Reader::start(bytes, FrameType::Data)?;
+        let stat = reader.u16()?.into();
+
+        let mut phasors = [Polar {
+            amplitude: T::from(0.0),
+            theta: Theta::from(0),
+        }; P];
+        for (phasor, channel) in phasors.iter_mut().zip(config.phasors.iter()) {
+            let (amplitude, theta) = match (format.float_phasors, format.polar) {
+                (true, true) => (reader.f32()?, Theta::from_radians(reader.f32()?)),
+                (true, false) => polar(reader.f32()?, reader.f32()?),
+                (false, true) => (
+                    (reader.u16()? as Float) * channel.scale,
+                    Theta::from_radians((reader.i16()? as Float) * 1e-4),
+                ),
+                (false, false) => polar(
+                    (reader.i16()? as Float) * channel.scale,
+                    (reader.i16()? as Float) * channel.scale,
+                ),
+            };
+            *phasor = Polar {
+                amplitude: T::from(amplitude),
+                theta,
+            };
+        }
+
+        let (frequency, rocof) = if format.float_frequency {
+            (reader.f32()?, reader.f32()?)
+        } else {
+            (
+                config.nominal as Float + (reader.i16()? as Float) * 1e-3,
+                (reader.i16()? as Float) * 1e-2,
+            )
+        };
+
+        let mut analogs = [0.0; A];
+        for (analog, channel) in analogs.iter_mut().zip(config.analogs.iter()) {
+            *analog = if format.float_analogs {
+                reader.f32()?
+            } else {
+                (reader.i16()? as Float) * channel.scale + channel.offset
+            };
+        }
+
+        let mut digitals = [0; D];
+        for digital in digitals.iter_mut() {
+            *digital = reader.u16()?;
+        }
+        reader.finish()?;
+
+        Ok(Self {
+            id_code,
+            time,
+            stat,
+            phasors,
+            frequency,
+            rocof,
+            analogs,
+            digitals,
+        })
+    }
+}
+
+fn rectangular(amplitude: Float, theta: Theta) -> (Float, Float) {
+    let (cos, sin) = Libm::cos_sin(theta);
+    (amplitude * Float::from(cos), amplitude * Float::from(sin))
+}
+
+fn polar(re: Float, im: Float) -> (Float, Theta) {
+    (libm::Libm::<Float>::hypot(re, im), Libm::atan2(im, re))
+}
+
+/// A header frame, with human readable information about the data stream
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HeaderFrame<'a> {
+    pub id_code: u16,
+    pub time: Time,
+    pub text: &'a str,
+}
+
+impl<'a> HeaderFrame<'a> {
+    /// Encodes the header frame, returning its length
+    pub fn encode(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = Writer::start(buffer, FrameType::Header, self.id_code, self.time)?;
+        writer.bytes(self.text.as_bytes())?;
+        writer.finish()
+    }
+
+    /// Decodes a header frame
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, Error> {
+        let (mut reader, id_code, time) = Reader::start(bytes, FrameType::Header)?;
+        let length = reader.bytes.len() - reader.position;
+        let text = core::str::from_utf8(reader.bytes(length)?).map_err(|_| Error::Name)?;
+        Ok(Self {
+            id_code,
+            time,
+            text,
+        })
+    }
+}
+
+/// A command to a PMU
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    TurnOffTransmission,
+    TurnOnTransmission,
+    SendHeader,
+    SendCfg1,
+    SendCfg2,
+    SendCfg3,
+    /// An extended or user defined command
+    Other(u16),
+}
+
+impl From<u16> for Command {
+    fn from(word: u16) -> Self {
+        match word {
+            1 => Command::TurnOffTransmission,
+            2 => Command::TurnOnTransmission,
+            3 => Command::SendHeader,
+            4 => Command::SendCfg1,
+            5 => Command::SendCfg2,
+            6 => Command::SendCfg3,
+            other => Command::Other(other),
+        }
+    }
+}
+
+impl From<Command> for u16 {
+    fn from(command: Command) -> Self {
+        match command {
+            Command::TurnOffTransmission => 1,
+            Command::TurnOnTransmission => 2,
+            Command::SendHeader => 3,
+            Command::SendCfg1 => 4,
+            Command::SendCfg2 => 5,
+            Command::SendCfg3 => 6,
+            Command::Other(other) => other,
+        }
+    }
+}
+
+/// A command frame, sent to a PMU
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CommandFrame {
+    pub id_code: u16,
+    pub time: Time,
+    pub command: Command,
+}
+
+impl CommandFrame {
+    /// Encodes the command frame, returning its length
+    pub fn encode(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = Writer::start(buffer, FrameType::Command, self.id_code, self.time)?;
+        writer.u16(self.command.into())?;
+        writer.finish()
+    }
+
+    /// Decodes a command frame (ignoring any extended frame data)
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let (mut reader, id_code, time) = Reader::start(bytes, FrameType::Command)?;
+        let command = reader.u16()?.into();
+        Ok(Self {
+            id_code,
+            time,
+            command,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    const SOC: u32 = 1_149_577_200;
+
+    // reference frames, assembled independently from the field tables of the standard
+
+    const DATA: [u8; 30] = [
+        0xaa, 0x02, 0x00, 0x1e, 0x1e, 0x36, 0x44, 0x85, 0x27, 0xf0, 0x0a, 0x07, 0xa1, 0x20, 0x08,
+        0x01, 0x2e, 0xe0, 0x14, 0x74, 0x00, 0x0c, 0x00, 0x05, 0x00, 0x2a, 0x00, 0x01, 0xb4, 0xed,
+    ];
+
+    const FLOAT_DATA: [u8; 40] = [
+        0xaa, 0x02, 0x00, 0x28, 0x1e, 0x36, 0x44, 0x85, 0x27, 0xf0, 0x00, 0x07, 0xa1, 0x20, 0x00,
+        0x00, 0x42, 0xcf, 0xd8, 0x9a, 0x42, 0x70, 0x00, 0x00, 0x42, 0x70, 0x0c, 0x4a, 0x3d, 0x4c,
+        0xcc, 0xcd, 0x42, 0x2a, 0x00, 0x00, 0x80, 0x00, 0x88, 0x15,
+    ];
+
+    const CFG2: [u8; 114] = [
+        0xaa, 0x32, 0x00, 0x72, 0x1e, 0x36, 0x44, 0x85, 0x27, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x0f, 0x42, 0x40, 0x00, 0x01, 0x53, 0x54, 0x41, 0x54, 0x49, 0x4f, 0x4e, 0x20, 0x41, 0x20,
+        0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x1e, 0x36, 0x00, 0x01, 0x00, 0x02, 0x00, 0x01, 0x00,
+        0x00, 0x56, 0x41, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20,
+        0x20, 0x20, 0x49, 0x41, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20,
+        0x20, 0x20, 0x20, 0x41, 0x4e, 0x41, 0x4c, 0x4f, 0x47, 0x31, 0x20, 0x20, 0x20, 0x20, 0x20,
+        0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x03, 0xe8, 0x01, 0x00, 0x00, 0x32, 0x01, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x1e, 0x11, 0xa0,
+    ];
+
+    const CFG3: [u8; 133] = [
+        0xaa, 0x52, 0x00, 0x85, 0x1e, 0x36, 0x44, 0x85, 0x27, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x0f, 0x42, 0x40, 0x00, 0x01, 0x09, 0x53, 0x54, 0x41, 0x54, 0x49, 0x4f, 0x4e,
+        0x20, 0x41, 0x1e, 0x36, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a,
+        0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x00, 0x01, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x02, 0x56,
+        0x41, 0x02, 0x49, 0x41, 0x07, 0x41, 0x4e, 0x41, 0x4c, 0x4f, 0x47, 0x31, 0x00, 0x00, 0x00,
+        0x00, 0x3c, 0x23, 0xd7, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x3a, 0x03,
+        0x12, 0x6f, 0x00, 0x00, 0x00, 0x00, 0x3f, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42,
+        0x36, 0x00, 0x00, 0xc2, 0xf4, 0x80, 0x00, 0x41, 0xf0, 0x00, 0x00, 0x50, 0x00, 0x00, 0x82,
+        0x35, 0x00, 0x00, 0x41, 0x1b, 0x00, 0x00, 0x00, 0x03, 0x00, 0x1e, 0x4e, 0xe8,
+    ];
+
+    const HEADER: [u8; 28] = [
+        0xaa, 0x12, 0x00, 0x1c, 0x1e, 0x36, 0x44, 0x85, 0x27, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x61,
+        0x63, 0x2d, 0x70, 0x6f, 0x77, 0x65, 0x72, 0x20, 0x50, 0x4d, 0x55, 0x1b, 0xbc,
+    ];
+
+    const COMMAND: [u8; 18] = [
+        0xaa, 0x42, 0x00, 0x12, 0x1e, 0x36, 0x44, 0x85, 0x27, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x05, 0x73, 0x48,
+    ];
+
+    fn time() -> Time {
+        Time {
+            soc: SOC,
+            fraction: 0,
+            quality: 0,
+        }
+    }
+
+    fn data_config() -> Config<'static, 1, 1, 1> {
+        let mut digital = DigitalChannel::default();
+        digital.names[0] = "BREAKER A";
+        digital.valid = 0xffff;
+        Config {
+            id_code: 7734,
+            time: time(),
+            station: "STATION A",
+            pmu_id: 7734,
+            format: Format {
+                polar: true,
+                ..Format::default()
+            },
+            phasors: [PhasorChannel {
+                name: "VA",
+                kind: PhasorKind::Voltage,
+                scale: 0.01,
+            }],
+            analogs: [AnalogChannel {
+                name: "ANALOG1",
+                kind: AnalogKind::Rms,
+                scale: 1.0,
+                offset: 0.0,
+            }],
+            digitals: [digital],
+            config_count: 3,
+            data_rate: 30,
+            ..Config::default()
+        }
+    }
+
+    fn config() -> Config<'static, 2, 1, 0> {
+        Config {
+            id_code: 7734,
+            time: time(),
+            station: "STATION A",
+            pmu_id: 7734,
+            format: Format {
+                polar: true,
+                ..Format::default()
+            },
+            phasors: [
+                PhasorChannel {
+                    name: "VA",
+                    kind: PhasorKind::Voltage,
+                    scale: 0.01,
+                },
+                PhasorChannel {
+                    name: "IA",
+                    kind: PhasorKind::Current,
+                    scale: 0.0005,
+                },
+            ],
+            analogs: [AnalogChannel {
+                name: "ANALOG1",
+                kind: AnalogKind::Rms,
+                scale: 1.0,
+                offset: 0.0,
+            }],
+            config_count: 3,
+            data_rate: 30,
+            ..Config::default()
+        }
+    }
+
+    fn metadata() -> Metadata {
+        let mut global_id = [0; 16];
+        for (i, byte) in global_id.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        Metadata {
+            global_id,
+            latitude: 45.5,
+            longitude: -122.25,
+            elevation: 30.0,
+            class: b'P',
+            window: 33333,
+            group_delay: 16667,
+        }
+    }
+
+    fn check_channels<const P: usize, const A: usize, const D: usize>(
+        decoded: &Config<P, A, D>,
+        expected: &Config<P, A, D>,
+    ) {
+        assert_eq!(decoded.id_code, expected.id_code);
+        assert_eq!(decoded.time, expected.time);
+        assert_eq!(decoded.time_base, expected.time_base);
+        assert_eq!(decoded.station, expected.station);
+        assert_eq!(decoded.pmu_id, expected.pmu_id);
+        assert_eq!(decoded.format, expected.format);
+        for (decoded, expected) in decoded.phasors.iter().zip(expected.phasors.iter()) {
+            assert_eq!(decoded.name, expected.name);
+            assert_eq!(decoded.kind, expected.kind);
+            assert_abs_diff_eq!(decoded.scale, expected.scale, epsilon = 1e-9);
+        }
+        for (decoded, expected) in decoded.analogs.iter().zip(expected.analogs.iter()) {
+            assert_eq!(decoded.name, expected.name);
+            assert_abs_diff_eq!(decoded.scale, expected.scale);
+        }
+        assert_eq!(decoded.digitals, expected.digitals);
+        assert_eq!(decoded.nominal, expected.nominal);
+        assert_eq!(decoded.config_count, expected.config_count);
+        assert_eq!(decoded.data_rate, expected.data_rate);
+    }
+
+    #[test]
+    fn crc() {
+        // the standard check value of CRC-16/CCITT-FALSE
+        assert_eq!(crc_ccitt(b"123456789"), 0x29b1);
+    }
+
+    #[test]
+    fn data_frame() {
+        let config = data_config();
+        let frame: DataFrame<Float, 1, 1, 1> = DataFrame {
+            id_code: 7734,
+            time: Time {
+                soc: SOC,
+                fraction: 500_000,
+                quality: 0x0a,
+            },
+            stat: Stat {
+                trigger: true,
+                trigger_reason: 1,
+                ..Stat::default()
+            },
+            phasors: [Polar {
+                amplitude: 120.0,
+                theta: Theta::from_degrees(30.0),
+            }],
+            frequency: 60.012,
+            rocof: 0.05,
+            analogs: [42.0],
+            digitals: [1],
+        };
+
+        let mut buffer = [0; 64];
+        let length = frame.encode(&config, &mut buffer).unwrap();
+        assert_eq!(buffer[..length], DATA);
+
+        let decoded: DataFrame<Float, 1, 1, 1> = DataFrame::decode(&DATA, &config).unwrap();
+        assert_eq!(decoded.id_code, frame.id_code);
+        assert_eq!(decoded.time, frame.time);
+        assert_eq!(decoded.stat, frame.stat);
+        assert_abs_diff_eq!(decoded.phasors[0].amplitude, 120.0, epsilon = 0.01);
+        assert_abs_diff_eq!(decoded.phasors[0].theta.to_degrees(), 30.0, epsilon = 0.01);
+        assert_abs_diff_eq!(decoded.frequency, 60.012, epsilon = 1e-4);
+        assert_abs_diff_eq!(decoded.rocof, 0.05, epsilon = 1e-4);
+        assert_eq!(decoded.analogs, [42.0]);
+        assert_eq!(decoded.digitals, [1]);
+    }
+
+    #[test]
+    fn float_data_frame() {
+        let config = Config {
+            format: Format {
+                polar: false,
+                float_phasors: true,
+                float_analogs: true,
+                float_frequency: true,
+            },
+            ..data_config()
+        };
+
+        let decoded: DataFrame<Float, 1, 1, 1> = DataFrame::decode(&FLOAT_DATA, &config).unwrap();
+        assert_eq!(decoded.time.fraction, 500_000);
+        assert_eq!(decoded.stat, Stat::default());
+        assert_abs_diff_eq!(decoded.phasors[0].amplitude, 120.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(decoded.phasors[0].theta.to_degrees(), 30.0, epsilon = 1e-4);
+        assert_abs_diff_eq!(decoded.frequency, 60.012, epsilon = 1e-5);
+        assert_abs_diff_eq!(decoded.rocof, 0.05, epsilon = 1e-6);
+        assert_eq!(decoded.analogs, [42.5]);
+        assert_eq!(decoded.digitals, [0x8000]);
+
+        // round trip every format
+        for format in 0..16u16 {
+            let config = Config {
+                format: format.into(),
+                ..data_config()
+            };
+            let mut buffer = [0; 64];
+            let length = decoded.encode(&config, &mut buffer).unwrap();
+            let again: DataFrame<Float, 1, 1, 1> =
+                DataFrame::decode(&buffer[..length], &config).unwrap();
+            assert_abs_diff_eq!(again.phasors[0].amplitude, 120.0, epsilon = 0.01);
+            assert_abs_diff_eq!(again.phasors[0].theta.to_degrees(), 30.0, epsilon = 0.01);
+            assert_abs_diff_eq!(again.frequency, 60.012, epsilon = 1e-3);
+            assert_abs_diff_eq!(again.rocof, 0.05, epsilon = 1e-2);
+            assert_abs_diff_eq!(again.analogs[0], 42.5, epsilon = 0.5);
+        }
+    }
+
+    #[test]
+    fn cfg2() {
+        let config = config();
+        let mut buffer = [0; 256];
+        let length = config.encode_cfg2(&mut buffer).unwrap();
+        assert_eq!(buffer[..length], CFG2);
+
+        let decoded: Config<2, 1, 0> = Config::decode_cfg2(&CFG2).unwrap();
+        check_channels(&decoded, &config);
+        assert_eq!(decoded.analogs[0].kind, AnalogKind::Rms);
+    }
+
+    #[test]
+    fn cfg3() {
+        let config = config();
+        let mut buffer = [0; 256];
+        let length = config.encode_cfg3(&metadata(), &mut buffer).unwrap();
+        assert_eq!(buffer[..length], CFG3);
+
+        let (decoded, metadata): (Config<2, 1, 0>, _) = Config::decode_cfg3(&CFG3).unwrap();
+        check_channels(&decoded, &config);
+        assert_eq!(metadata, self::metadata());
+    }
+
+    #[test]
+    fn digital_channels() {
+        let config = data_config();
+        let mut buffer = [0; 512];
+
+        let length = config.encode_cfg2(&mut buffer).unwrap();
+        let decoded: Config<1, 1, 1> = Config::decode_cfg2(&buffer[..length]).unwrap();
+        check_channels(&decoded, &config);
+
+        let length = config.encode_cfg3(&metadata(), &mut buffer).unwrap();
+        let (decoded, _): (Config<1, 1, 1>, _) = Config::decode_cfg3(&buffer[..length]).unwrap();
+        check_channels(&decoded, &config);
+    }
+
+    #[test]
+    fn header_and_command() {
+        let header = HeaderFrame {
+            id_code: 7734,
+            time: time(),
+            text: "ac-power PMU",
+        };
+        let mut buffer = [0; 64];
+        let length = header.encode(&mut buffer).unwrap();
+        assert_eq!(buffer[..length], HEADER);
+        assert_eq!(HeaderFrame::decode(&HEADER).unwrap(), header);
+
+        let command = CommandFrame {
+            id_code: 7734,
+            time: time(),
+            command: Command::SendCfg2,
+        };
+        let length = command.encode(&mut buffer).unwrap();
+        assert_eq!(buffer[..length], COMMAND);
+        assert_eq!(CommandFrame::decode(&COMMAND).unwrap(), command);
+    }
+
+    #[test]
+    fn stat() {
+        for word in [0x0000, 0x0801, 0x8000, 0xffff, 0x2345] {
+            assert_eq!(u16::from(Stat::from(word)), word);
+        }
+        let stat = Stat::from(0xa000);
+        assert_eq!(stat.error, DataError::TestMode);
+        assert!(stat.sync_lost);
+    }
+
+    #[test]
+    fn frame_types() {
+        assert_eq!(FrameType::of(&DATA), Ok(FrameType::Data));
+        assert_eq!(FrameType::of(&CFG2), Ok(FrameType::Cfg2));
+        assert_eq!(FrameType::of(&CFG3), Ok(FrameType::Cfg3));
+        assert_eq!(FrameType::of(&HEADER), Ok(FrameType::Header));
+        assert_eq!(FrameType::of(&COMMAND), Ok(FrameType::Command));
+    }
+
+    #[test]
+    fn errors() {
+        let config = data_config();
+
+        let mut corrupt = DATA;
+        corrupt[20] ^= 1;
+        let result: Result<DataFrame<Float, 1, 1, 1>, _> = DataFrame::decode(&corrupt, &config);
+        assert_eq!(result, Err(Error::Checksum));
+
+        let result: Result<DataFrame<Float, 1, 1, 1>, _> = DataFrame::decode(&DATA[..20], &config);
+        assert_eq!(result, Err(Error::Length));
+
+        let mut corrupt = DATA;
+        corrupt[0] = 0xab;
+        assert_eq!(FrameType::of(&corrupt), Err(Error::Sync));
+
+        let result: Result<Config<2, 1, 0>, _> = Config::decode_cfg2(&CFG3);
+        assert_eq!(result, Err(Error::FrameType(FrameType::Cfg3)));
+
+        let result: Result<Config<1, 1, 0>, _> = Config::decode_cfg2(&CFG2);
+        assert_eq!(result, Err(Error::Channels));
+
+        let mut buffer = [0; 100];
+        assert_eq!(config.encode_cfg2(&mut buffer), Err(Error::BufferTooSmall));
+
+        let long = Config {
+            station: "A STATION NAME LONGER THAN 16 BYTES",
+            ..config
+        };
+        let mut buffer = [0; 512];
+        assert_eq!(long.encode_cfg2(&mut buffer), Err(Error::Name));
+    }
+
+    #[test]
+    fn time_from_sample() {
+        let time = Time::from_sample(960 * 100 + 480, 960, 1_000_000);
+        assert_eq!(time.soc, 100);
+        assert_eq!(time.fraction, 500_000);
+    }
+}
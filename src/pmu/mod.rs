@@ -0,0 +1,63 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Synchrophasor measurement following IEEE C37.118.1
+
+The [Pmu] estimator implements the reference model of the standard: each phase is demodulated by the nominal
+frequency and low-pass filtered, with a short triangular filter for P class (protection) or a longer windowed sinc
+filter for M class (measurement).  It reports per-phase and positive sequence phasors, frequency and rate of change of
+frequency (ROCOF) at the reporting rate.
+
+Phasors follow the standard's convention: the amplitude is the rms, and the angle is relative to a cosine at the
+nominal frequency, synchronized to the sample clock (i.e. to UTC when the first sample is at a second rollover).
+
+The [Errors] evaluator calculates the total vector error (TVE), frequency error (FE) and ROCOF error (RFE) of the
+measurements against a reference, for running the standard's compliance tests.
+
+The [frame] module encodes and decodes the IEEE C37.118.2 frames for publishing the measurements.
+
+```rust
+use ac_power::number::Float;
+use ac_power::pmu::{Class, Pmu};
+use ac_power::trig::Theta;
+use ac_power::Abc;
+use approx::assert_abs_diff_eq;
+
+// 60Hz nominal, sampled at 960Hz, reporting 60 times per second
+let mut pmu: Pmu<Float, 64> = Pmu::new(Class::P, 60, 960, 60);
+
+// a 60.5Hz signal with an rms of 1.0
+let step = Theta::from_degrees(360.0 * 60.5 / 960.0);
+let mut theta = Theta::from(0);
+let mut measurement = None;
+for _ in 0..960 {
+    let abc = Abc::from_polar(1.414, theta);
+    theta += step;
+    if let Some(m) = pmu.update(abc) {
+        measurement = Some(m);
+    }
+}
+
+let measurement = measurement.unwrap();
+assert_abs_diff_eq!(measurement.positive.amplitude, 1.0, epsilon = 0.001);
+assert_abs_diff_eq!(measurement.frequency, 60.5, epsilon = 0.001);
+```
+*/
+
+mod estimator;
+pub mod frame;
+
+pub use estimator::{tve, Class, Errors, MClassFilter, Measurement, Pmu};
@@ -0,0 +1,378 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+Harmonic distortion measurements following IEC 61000-4-7
+
+A [Spectrum] is calculated from a window of samples spanning a whole number of fundamental cycles (i.e. between two
+zero crossings of a PLL's phase).  IEC 61000-4-7 uses 10 cycles at 50Hz and 12 cycles at 60Hz, so the bins are 5Hz
+apart.  The spectrum provides the individual harmonics, the harmonic groups and subgroups, the interharmonic groups
+and centred subgroups, and the total harmonic distortion (THD) and total demand distortion (TDD) calculated from them.
+
+```rust
+use ac_power::number::Float;
+use ac_power::power_quality::{Spectrum, CYCLES_60HZ};
+use ac_power::trig::Theta;
+use ac_power::wavegen::{Magnitude, Sequence, Waveform};
+use ac_power::Abc;
+use approx::assert_abs_diff_eq;
+
+let waveform: Waveform<Float, 5> = Waveform::new()
+    .with_harmonic(1, Sequence::Positive, Magnitude::Absolute(1.0), Theta::from(0))
+    .with_harmonic(5, Sequence::Negative, Magnitude::Percent(4.0), Theta::from(0));
+
+// 12 cycles sampled 64 times per cycle
+let step = Theta::from_degrees(360.0 / 64.0);
+let mut theta = Theta::from(0);
+let window: Vec<Abc<Float>> = (0..12 * 64)
+    .map(|_| {
+        let abc = waveform.calculate(theta);
+        theta += step;
+        abc
+    })
+    .collect();
+
+let spectrum: Abc<Spectrum<128>> = Spectrum::from_abc(&window, CYCLES_60HZ);
+assert_abs_diff_eq!(spectrum.thd(5).a, 0.04, epsilon = 0.0001);
+```
+*/
+
+use crate::number::{Float, Num};
+use crate::reference_frames::Abc;
+use crate::trig::{cos_sin, Theta};
+use libm::Libm;
+
+/// Window length (in cycles) for a 50Hz system
+pub const CYCLES_50HZ: usize = 10;
+
+/// Window length (in cycles) for a 60Hz system
+pub const CYCLES_60HZ: usize = 12;
+
+/// RMS magnitudes of the first `B` DFT bins of a window spanning `cycles` fundamental cycles
+///
+/// Bin k is at k/cycles times the fundamental frequency, so harmonic order h is bin h·cycles.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Spectrum<const B: usize> {
+    pub cycles: usize,
+    pub bins: [Float; B],
+}
+
+fn sqrt(x: Float) -> Float {
+    Libm::<Float>::sqrt(x)
+}
+
+impl<const B: usize> Spectrum<B> {
+    /// Calculates the spectrum of a single phase
+    pub fn from_window<T: Num>(window: &[T], cycles: usize) -> Self {
+        let n = window.len();
+        assert!(2 * B <= n, "too many bins for the window length");
+
+        let mut bins = [0.0; B];
+        for (k, bin) in bins.iter_mut().enumerate() {
+            let (re, im) = dft(window.iter().map(|x| (*x).into()), k, n);
+            *bin = rms(re, im, k, n);
+        }
+        Self { cycles, bins }
+    }
+
+    /// Calculates the spectrum of each phase
+    pub fn from_abc<T: Num>(window: &[Abc<T>], cycles: usize) -> Abc<Self> {
+        let n = window.len();
+        assert!(2 * B <= n, "too many bins for the window length");
+
+        let mut spectrum = Abc {
+            a: Self::zero(cycles),
+            b: Self::zero(cycles),
+            c: Self::zero(cycles),
+        };
+        for k in 0..B {
+            let (re, im) = dft(window.iter().map(|x| x.a.into()), k, n);
+            spectrum.a.bins[k] = rms(re, im, k, n);
+            let (re, im) = dft(window.iter().map(|x| x.b.into()), k, n);
+            spectrum.b.bins[k] = rms(re, im, k, n);
+            let (re, im) = dft(window.iter().map(|x| x.c.into()), k, n);
+            spectrum.c.bins[k] = rms(re, im, k, n);
+        }
+        spectrum
+    }
+
+    fn zero(cycles: usize) -> Self {
+        Self {
+            cycles,
+            bins: [0.0; B],
+        }
+    }
+
+    // sum of squares of the bins offset from bin k, with the bins at the ends optionally halved
+    fn sum_of_squares(&self, k: usize, from: isize, to: isize, half_ends: bool) -> Float {
+        (from..=to)
+            .map(|i| {
+                let index = (k as isize) + i;
+                assert!(
+                    index >= 0 && (index as usize) < B,
+                    "harmonic order out of range of the spectrum"
+                );
+                let square = self.bins[index as usize] * self.bins[index as usize];
+                if half_ends && (i == from || i == to) {
+                    0.5 * square
+                } else {
+                    square
+                }
+            })
+            .sum()
+    }
+
+    /// RMS of the dc component
+    pub fn dc(&self) -> Float {
+        // the dc bin is not doubled by the rms scaling, so it is already the rms
+        self.bins[0]
+    }
+
+    /// RMS of a single harmonic order (the bin at that order)
+    pub fn harmonic(&self, order: usize) -> Float {
+        self.bins[order * self.cycles]
+    }
+
+    /// RMS of a harmonic group, including the neighbouring bins half way to the next harmonic on each side
+    pub fn harmonic_group(&self, order: usize) -> Float {
+        let half = (self.cycles / 2) as isize;
+        sqrt(self.sum_of_squares(order * self.cycles, -half, half, true))
+    }
+
+    /// RMS of a harmonic subgroup, the harmonic bin and the two immediately adjacent bins
+    pub fn harmonic_subgroup(&self, order: usize) -> Float {
+        sqrt(self.sum_of_squares(order * self.cycles, -1, 1, false))
+    }
+
+    /// RMS of the interharmonic group between `order` and `order + 1`
+    pub fn interharmonic_group(&self, order: usize) -> Float {
+        let cycles = self.cycles as isize;
+        sqrt(self.sum_of_squares(order * self.cycles, 1, cycles - 1, false))
+    }
+
+    /// RMS of the interharmonic centred subgroup between `order` and `order + 1`, which excludes the bins adjacent
+    /// to each harmonic
+    pub fn interharmonic_subgroup(&self, order: usize) -> Float {
+        let cycles = self.cycles as isize;
+        sqrt(self.sum_of_squares(order * self.cycles, 2, cycles - 2, false))
+    }
+
+    // ratio of the square root of the sum of squares of orders 2 to `max_order` to a reference
+    fn total(
+        &self,
+        max_order: usize,
+        reference: Float,
+        component: fn(&Self, usize) -> Float,
+    ) -> Float {
+        let sum: Float = (2..=max_order)
+            .map(|order| {
+                let x = component(self, order);
+                x * x
+            })
+            .sum();
+        sqrt(sum) / reference
+    }
+
+    /// Total harmonic distortion, from the individual harmonics up to `max_order` (i.e. 40 or 50)
+    pub fn thd(&self, max_order: usize) -> Float {
+        self.total(max_order, self.harmonic(1), Self::harmonic)
+    }
+
+    /// Group total harmonic distortion (THDG), from the harmonic groups up to `max_order`
+    pub fn thdg(&self, max_order: usize) -> Float {
+        self.total(max_order, self.harmonic_group(1), Self::harmonic_group)
+    }
+
+    /// Subgroup total harmonic distortion (THDS), from the harmonic subgroups up to `max_order`
+    pub fn thds(&self, max_order: usize) -> Float {
+        self.total(
+            max_order,
+            self.harmonic_subgroup(1),
+            Self::harmonic_subgroup,
+        )
+    }
+
+    /// Total demand distortion, from the individual harmonics up to `max_order` relative to the maximum demand
+    /// (rms) current
+    pub fn tdd(&self, max_order: usize, demand: Float) -> Float {
+        self.total(max_order, demand, Self::harmonic)
+    }
+}
+
+impl<const B: usize> Abc<Spectrum<B>> {
+    /// Total harmonic distortion of each phase
+    pub fn thd(&self, max_order: usize) -> Abc<Float> {
+        Abc {
+            a: self.a.thd(max_order),
+            b: self.b.thd(max_order),
+            c: self.c.thd(max_order),
+        }
+    }
+
+    /// Group total harmonic distortion of each phase
+    pub fn thdg(&self, max_order: usize) -> Abc<Float> {
+        Abc {
+            a: self.a.thdg(max_order),
+            b: self.b.thdg(max_order),
+            c: self.c.thdg(max_order),
+        }
+    }
+
+    /// Subgroup total harmonic distortion of each phase
+    pub fn thds(&self, max_order: usize) -> Abc<Float> {
+        Abc {
+            a: self.a.thds(max_order),
+            b: self.b.thds(max_order),
+            c: self.c.thds(max_order),
+        }
+    }
+
+    /// Total demand distortion of each phase
+    pub fn tdd(&self, max_order: usize, demand: Float) -> Abc<Float> {
+        Abc {
+            a: self.a.tdd(max_order, demand),
+            b: self.b.tdd(max_order, demand),
+            c: self.c.tdd(max_order, demand),
+        }
+    }
+}
+
+// dft bin k of a window of n samples
+fn dft(window: impl Iterator<Item = Float>, k: usize, n: usize) -> (Float, Float) {
+    let mut re = 0.0;
+    let mut im = 0.0;
+    for (i, x) in window.enumerate() {
+        // the angle k*i/n of a full circle, reduced exactly before converting to theta
+        let fraction = ((k * i) % n) as u64;
+        let theta = Theta::from(((fraction << 32) / (n as u64)) as u32 as i32);
+        let (cos, sin) = cos_sin(theta);
+        re += x * cos;
+        im -= x * sin;
+    }
+    (re, im)
+}
+
+// rms magnitude of dft bin k of a window of n samples
+fn rms(re: Float, im: Float, k: usize, n: usize) -> Float {
+    let magnitude = sqrt(re * re + im * im) / (n as Float);
+    if k == 0 {
+        magnitude
+    } else {
+        // the peak is twice the bin magnitude, and the rms is the peak over sqrt(2)
+        magnitude * crate::constants::SQRT_2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::wavegen::{Interharmonic, Magnitude, Sequence, Waveform};
+    use crate::Voltage;
+    use approx::assert_abs_diff_eq;
+
+    const SAMPLES_PER_CYCLE: usize = 64;
+
+    // a 10 cycle window with 5th and 7th harmonics, and a 7.3 order interharmonic
+    fn window() -> Vec<Abc<Voltage>> {
+        let waveform: Waveform<Voltage, 7> = Waveform::new()
+            .with_harmonic(
+                1,
+                Sequence::Positive,
+                Magnitude::Absolute(100.0.into()),
+                Theta::from_degrees(20.0),
+            )
+            .with_harmonic(
+                5,
+                Sequence::Negative,
+                Magnitude::Percent(3.0),
+                Theta::from_degrees(50.0),
+            )
+            .with_harmonic(
+                7,
+                Sequence::Positive,
+                Magnitude::Percent(4.0),
+                Theta::from(0),
+            );
+        let mut interharmonic =
+            Interharmonic::new(7.3, Sequence::Positive, Voltage::from(2.0), Theta::from(0));
+
+        let step = Theta::from_degrees(360.0 / SAMPLES_PER_CYCLE as Float);
+        let mut theta = Theta::from(0);
+        (0..CYCLES_50HZ * SAMPLES_PER_CYCLE)
+            .map(|_| {
+                let abc = waveform.calculate(theta) + interharmonic.calculate();
+                theta += step;
+                interharmonic.update(step);
+                abc
+            })
+            .collect()
+    }
+
+    #[test]
+    fn harmonics() {
+        let spectrum: Abc<Spectrum<128>> = Spectrum::from_abc(&window(), CYCLES_50HZ);
+        let rms = |peak: Float| peak / crate::constants::SQRT_2;
+
+        for phase in [spectrum.a, spectrum.b, spectrum.c] {
+            assert_abs_diff_eq!(phase.dc(), 0.0, epsilon = 0.01);
+            assert_abs_diff_eq!(phase.harmonic(1), rms(100.0), epsilon = 0.01);
+            assert_abs_diff_eq!(phase.harmonic(5), rms(3.0), epsilon = 0.01);
+            assert_abs_diff_eq!(phase.harmonic(7), rms(4.0), epsilon = 0.01);
+            assert_abs_diff_eq!(phase.harmonic(3), 0.0, epsilon = 0.01);
+
+            // the interharmonic is 3 bins above the 7th
+            let with_interharmonic = Libm::<Float>::hypot(rms(4.0), rms(2.0));
+            assert_abs_diff_eq!(phase.harmonic_group(7), with_interharmonic, epsilon = 0.01);
+            assert_abs_diff_eq!(phase.harmonic_subgroup(7), rms(4.0), epsilon = 0.01);
+            assert_abs_diff_eq!(phase.interharmonic_group(7), rms(2.0), epsilon = 0.01);
+            assert_abs_diff_eq!(phase.interharmonic_subgroup(7), rms(2.0), epsilon = 0.01);
+            assert_abs_diff_eq!(phase.interharmonic_group(5), 0.0, epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn distortion() {
+        let spectrum: Abc<Spectrum<128>> = Spectrum::from_abc(&window(), CYCLES_50HZ);
+
+        let thd = Libm::<Float>::hypot(0.03, 0.04);
+        let thdg = Libm::<Float>::hypot(thd, 0.02);
+        for phase in [spectrum.thd(10), spectrum.thds(10)] {
+            assert_abs_diff_eq!(phase.a, thd, epsilon = 0.0001);
+            assert_abs_diff_eq!(phase.b, thd, epsilon = 0.0001);
+            assert_abs_diff_eq!(phase.c, thd, epsilon = 0.0001);
+        }
+        assert_abs_diff_eq!(spectrum.thdg(10).a, thdg, epsilon = 0.0001);
+
+        // tdd is relative to the demand current, rather than the fundamental
+        let tdd = spectrum.tdd(10, 2.0 * spectrum.a.harmonic(1));
+        assert_abs_diff_eq!(tdd.a, 0.5 * thd, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn single_phase() {
+        let window: Vec<Voltage> = window().iter().map(|abc| abc.b).collect();
+        let spectrum: Spectrum<128> = Spectrum::from_window(&window, CYCLES_50HZ);
+        let expected: Abc<Spectrum<128>> = Spectrum::from_abc(&self::window(), CYCLES_50HZ);
+        assert_eq!(spectrum, expected.b);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        let spectrum: Abc<Spectrum<128>> = Spectrum::from_abc(&window(), CYCLES_50HZ);
+        spectrum.a.harmonic_group(13);
+    }
+}
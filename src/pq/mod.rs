@@ -14,6 +14,76 @@
 //    limitations under the License.
 
 mod power;
-mod pq;
 
-pub use pq::Pq;
+use crate::newtypes::Power;
+use crate::trig::{atan2, cos_sin, Cos};
+
+/// Instantaneous real (p) and reactive (q) powers
+///
+/// Defaults to [Power], but fixed-point power calculations return a per-unit `Pq<Q31>` or `Pq<Q15>` (see
+/// [fixed](crate::fixed))
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pq<T = Power> {
+    pub p: T,
+    pub q: T,
+}
+
+impl Pq {
+    /// Calculates power factor of a Pq value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ac_power::constants::FRAC_1_SQRT_2;
+    /// use ac_power::number::Float;
+    /// use ac_power::Pq;
+    /// use approx::assert_abs_diff_eq;
+    ///
+    /// let pq = Pq {
+    ///     p: 1.0.into(),
+    ///     q: 1.0.into(),
+    /// };
+    ///
+    /// let pf = pq.power_factor();
+    /// assert_abs_diff_eq!(Float::from(pf), FRAC_1_SQRT_2, epsilon = 0.0001);
+    /// ```
+    pub fn power_factor(&self) -> Cos {
+        // PF = cos(arctan(Q/P))
+        let (pf, _) = cos_sin(atan2(self.q.into(), self.p.into()));
+
+        pf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::constants::FRAC_1_SQRT_2;
+    use crate::number::Float;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn power_factor() {
+        let pq = Pq {
+            p: 1.0.into(),
+            q: 0.0.into(),
+        };
+        let pf = pq.power_factor();
+        assert_abs_diff_eq!(Float::from(pf), 1.0, epsilon = 0.0001);
+
+        let pq = Pq {
+            p: 0.0.into(),
+            q: 1.0.into(),
+        };
+        let pf = pq.power_factor();
+        assert_abs_diff_eq!(Float::from(pf), 0.0, epsilon = 0.0001);
+
+        let pq = Pq {
+            p: 1.0.into(),
+            q: 1.0.into(),
+        };
+        let pf = pq.power_factor();
+        assert_abs_diff_eq!(Float::from(pf), FRAC_1_SQRT_2, epsilon = 0.0001);
+    }
+}
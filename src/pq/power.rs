@@ -14,6 +14,7 @@
 //    limitations under the License.
 
 use crate::constants::FRAC_1_SQRT_3;
+use crate::fixed::{Q15, Q31};
 use crate::newtypes::{Current, Voltage};
 use crate::pq::Pq;
 use crate::reference_frames::{Abc, AlphaBeta, AlphaBeta0, Dq, Dq0};
@@ -99,18 +100,45 @@ impl Mul<Dq<Voltage>> for Dq<Current> {
     type Output = Pq;
 }
 
+// fixed-point powers are per-unit of 3/2 * Vbase * Ibase, which keeps them in the range of the fixed-point type
+macro_rules! impl_fixed_power {
+    ($t:ty) => {
+        impl Mul<AlphaBeta<$t>> for AlphaBeta<$t> {
+            fn mul(self, rhs: AlphaBeta<$t>) -> Pq<$t> {
+                let p = self.alpha * rhs.alpha + self.beta * rhs.beta;
+                let q = self.beta * rhs.alpha - self.alpha * rhs.beta;
+                Pq { p, q }
+            }
+            type Output = Pq<$t>;
+        }
+
+        impl Mul<Dq<$t>> for Dq<$t> {
+            fn mul(self, rhs: Dq<$t>) -> Pq<$t> {
+                let p = self.d * rhs.d + self.q * rhs.q;
+                let q = self.q * rhs.d - self.d * rhs.q;
+                Pq { p, q }
+            }
+            type Output = Pq<$t>;
+        }
+    };
+}
+
+impl_fixed_power!(Q15);
+impl_fixed_power!(Q31);
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::number::Float;
     use crate::trig::cos_sin;
     use crate::trig::Theta;
     use approx::assert_abs_diff_eq;
 
     // helper function to assert pq approximate equality
     fn check_pqs(pq0: Pq, pq1: Pq) {
-        assert_abs_diff_eq!(f32::from(pq0.p), f32::from(pq1.p), epsilon = 0.01,);
-        assert_abs_diff_eq!(f32::from(pq0.q), f32::from(pq1.q), epsilon = 0.01,);
+        assert_abs_diff_eq!(Float::from(pq0.p), Float::from(pq1.p), epsilon = 0.01,);
+        assert_abs_diff_eq!(Float::from(pq0.q), Float::from(pq1.q), epsilon = 0.01,);
     }
 
     #[test]
@@ -0,0 +1,467 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+IEEE 1547-2018 voltage and frequency ride-through and trip
+
+[Protection] evaluates the must-trip settings (OV1, OV2, UV1, UV2, OF1, OF2, UF1 and UF2, each with a threshold and a
+clearing time) every sample, and classifies the operating point into the ride-through regions of the standard.  The
+defaults for each abnormal performance [Category] are from the standard's default settings tables.
+
+Voltages are rms per-unit of the nominal voltage.  The highest phase is used for over-voltage and the lowest for
+under-voltage.  Once tripped, the trip is latched until [Protection::reset].
+
+```rust
+use ac_power::protection::{Category, Protection, Reason, Region, Settings};
+use ac_power::{Abc, Frequency, Voltage};
+
+let settings = Settings::category(Category::II, 60.0);
+let mut protection = Protection::new(settings, Voltage::from(120.0), 1e-3);
+
+// a 50% sag on phase a is in the permissive operation region, and trips UV1 after 10s
+let sag = Abc {a: Voltage::from(60.0), b: Voltage::from(120.0), c: Voltage::from(120.0)};
+let status = protection.update(sag, Frequency::from(60.0));
+assert_eq!(status.region, Region::Permissive);
+assert_eq!(status.trip, None);
+
+let status = (0..10000).map(|_| protection.update(sag, Frequency::from(60.0))).last().unwrap();
+assert_eq!(status.trip, Some(Reason::Uv1));
+```
+*/
+
+use crate::constants::SQRT_2;
+use crate::newtypes::{Frequency, Voltage};
+use crate::number::Float;
+use crate::reference_frames::{Abc, Dq};
+
+/// Abnormal operating performance category
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Category {
+    I,
+    II,
+    III,
+}
+
+/// A must-trip setting
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TripSetting {
+    /// Voltage (per-unit) or frequency (Hz) threshold
+    pub threshold: Float,
+    /// Time the threshold must be exceeded before tripping (in seconds)
+    pub clearing_time: Float,
+}
+
+impl TripSetting {
+    pub fn new(threshold: Float, clearing_time: Float) -> Self {
+        Self {
+            threshold,
+            clearing_time,
+        }
+    }
+}
+
+/// Boundaries of the ride-through regions
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RideThrough {
+    /// Continuous operation voltage range (per-unit)
+    pub continuous: (Float, Float),
+    /// Lowest voltage of the mandatory operation region (per-unit)
+    pub mandatory: Float,
+    /// Momentary cessation below this voltage (per-unit)
+    pub cessation_under: Option<Float>,
+    /// Momentary cessation above this voltage (per-unit)
+    pub cessation_over: Option<Float>,
+    /// Continuous operation frequency range (Hz)
+    pub frequency_continuous: (Float, Float),
+    /// Mandatory operation frequency range (Hz)
+    pub frequency_mandatory: (Float, Float),
+}
+
+/// Trip and ride-through settings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Settings {
+    pub ov2: TripSetting,
+    pub ov1: TripSetting,
+    pub uv1: TripSetting,
+    pub uv2: TripSetting,
+    pub of2: TripSetting,
+    pub of1: TripSetting,
+    pub uf1: TripSetting,
+    pub uf2: TripSetting,
+    pub ride_through: RideThrough,
+}
+
+impl Settings {
+    /// Default settings of a category, for a nominal frequency (in Hz)
+    pub fn category(category: Category, nominal: Float) -> Self {
+        let (ov1, uv1, uv2) = match category {
+            Category::I => (2.0, (0.70, 2.0), (0.45, 0.16)),
+            Category::II => (2.0, (0.70, 10.0), (0.45, 0.16)),
+            Category::III => (13.0, (0.88, 21.0), (0.50, 2.0)),
+        };
+        let (mandatory, cessation_under, cessation_over) = match category {
+            Category::I => (0.70, None, None),
+            Category::II => (0.65, None, None),
+            Category::III => (0.50, Some(0.50), Some(1.10)),
+        };
+
+        // the frequency settings are given for 60Hz systems, and scaled to the nominal
+        let hz = |frequency: Float| frequency * nominal / 60.0;
+        Self {
+            ov2: TripSetting::new(1.20, 0.16),
+            ov1: TripSetting::new(1.10, ov1),
+            uv1: TripSetting::new(uv1.0, uv1.1),
+            uv2: TripSetting::new(uv2.0, uv2.1),
+            of2: TripSetting::new(hz(62.0), 0.16),
+            of1: TripSetting::new(hz(61.2), 300.0),
+            uf1: TripSetting::new(hz(58.5), 300.0),
+            uf2: TripSetting::new(hz(56.5), 0.16),
+            ride_through: RideThrough {
+                continuous: (0.88, 1.10),
+                mandatory,
+                cessation_under,
+                cessation_over,
+                frequency_continuous: (hz(58.8), hz(61.2)),
+                frequency_mandatory: (hz(57.0), hz(61.8)),
+            },
+        }
+    }
+}
+
+/// Ride-through region of an operating point, in order of severity
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Region {
+    /// Normal operation
+    Continuous,
+    /// Must ride through and keep exchanging current
+    Mandatory,
+    /// May ride through, with or without exchanging current
+    Permissive,
+    /// Must ride through, but cease to energize
+    MomentaryCessation,
+}
+
+/// Reason for a trip, with its reason code
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Reason {
+    Ov2 = 1,
+    Ov1 = 2,
+    Uv1 = 3,
+    Uv2 = 4,
+    Of2 = 5,
+    Of1 = 6,
+    Uf1 = 7,
+    Uf2 = 8,
+}
+
+impl From<Reason> for u8 {
+    fn from(reason: Reason) -> Self {
+        reason as u8
+    }
+}
+
+/// Result of a protection update
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub region: Region,
+    /// The latched trip, if any
+    pub trip: Option<Reason>,
+}
+
+impl Status {
+    /// Whether the inverter must stop exchanging current, because it tripped or is in momentary cessation
+    pub fn cease_to_energize(&self) -> bool {
+        self.trip.is_some() || self.region == Region::MomentaryCessation
+    }
+}
+
+/// IEEE 1547 ride-through and must-trip protection
+pub struct Protection {
+    settings: Settings,
+    nominal: Float,
+    ts: Float,
+    // samples each setting has been exceeded for (counted, so long clearing times don't accumulate rounding errors)
+    timers: [u32; 8],
+    trip: Option<Reason>,
+}
+
+impl Protection {
+    /// Creates the protection for a nominal rms voltage, updated every `ts` seconds
+    pub fn new(settings: Settings, nominal: Voltage, ts: Float) -> Self {
+        Self {
+            settings,
+            nominal: nominal.into(),
+            ts,
+            timers: [0; 8],
+            trip: None,
+        }
+    }
+
+    /// Updates the protection with the rms voltage of each phase, and the frequency
+    pub fn update(&mut self, voltage: Abc<Voltage>, frequency: Frequency) -> Status {
+        let (a, b, c): (Float, Float, Float) =
+            (voltage.a.into(), voltage.b.into(), voltage.c.into());
+        let high = Float::max(a, Float::max(b, c)) / self.nominal;
+        let low = Float::min(a, Float::min(b, c)) / self.nominal;
+        self.evaluate(high, low, frequency.into())
+    }
+
+    /// Updates the protection with an amplitude invariant [Dq] voltage (whose magnitude is the peak phase voltage), and
+    /// the frequency
+    pub fn update_dq(&mut self, voltage: Dq<Voltage>, frequency: Frequency) -> Status {
+        let (d, q): (Float, Float) = (voltage.d.into(), voltage.q.into());
+        let rms = libm::Libm::<Float>::hypot(d, q) / SQRT_2;
+        let pu = rms / self.nominal;
+        self.evaluate(pu, pu, frequency.into())
+    }
+
+    /// Clears a latched trip
+    pub fn reset(&mut self) {
+        self.trip = None;
+        self.timers = [0; 8];
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    fn evaluate(&mut self, high: Float, low: Float, frequency: Float) -> Status {
+        let s = &self.settings;
+        let elements = [
+            (Reason::Ov2, high > s.ov2.threshold, s.ov2.clearing_time),
+            (Reason::Ov1, high > s.ov1.threshold, s.ov1.clearing_time),
+            (Reason::Uv1, low < s.uv1.threshold, s.uv1.clearing_time),
+            (Reason::Uv2, low < s.uv2.threshold, s.uv2.clearing_time),
+            (
+                Reason::Of2,
+                frequency > s.of2.threshold,
+                s.of2.clearing_time,
+            ),
+            (
+                Reason::Of1,
+                frequency > s.of1.threshold,
+                s.of1.clearing_time,
+            ),
+            (
+                Reason::Uf1,
+                frequency < s.uf1.threshold,
+                s.uf1.clearing_time,
+            ),
+            (
+                Reason::Uf2,
+                frequency < s.uf2.threshold,
+                s.uf2.clearing_time,
+            ),
+        ];
+
+        for (timer, &(reason, exceeded, clearing_time)) in
+            self.timers.iter_mut().zip(elements.iter())
+        {
+            if exceeded {
+                *timer = timer.saturating_add(1);
+                if (*timer as Float) * self.ts >= clearing_time && self.trip.is_none() {
+                    self.trip = Some(reason);
+                }
+            } else {
+                *timer = 0;
+            }
+        }
+
+        let region = Region::max(
+            self.voltage_region(high, low),
+            self.frequency_region(frequency),
+        );
+        Status {
+            region,
+            trip: self.trip,
+        }
+    }
+
+    fn voltage_region(&self, high: Float, low: Float) -> Region {
+        let r = &self.settings.ride_through;
+        let cessation = r.cessation_under.is_some_and(|v| low < v)
+            || r.cessation_over.is_some_and(|v| high > v);
+        if cessation {
+            Region::MomentaryCessation
+        } else if high > r.continuous.1 || low < r.mandatory {
+            Region::Permissive
+        } else if low < r.continuous.0 {
+            Region::Mandatory
+        } else {
+            Region::Continuous
+        }
+    }
+
+    fn frequency_region(&self, frequency: Float) -> Region {
+        let r = &self.settings.ride_through;
+        if frequency >= r.frequency_continuous.0 && frequency <= r.frequency_continuous.1 {
+            Region::Continuous
+        } else if frequency >= r.frequency_mandatory.0 && frequency <= r.frequency_mandatory.1 {
+            Region::Mandatory
+        } else {
+            Region::Permissive
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const TS: Float = 1e-3;
+
+    fn balanced(pu: Float) -> Abc<Voltage> {
+        let v = Voltage::from(pu * 120.0);
+        Abc { a: v, b: v, c: v }
+    }
+
+    // runs the protection for a duration, returning the last status
+    fn run(protection: &mut Protection, pu: Float, frequency: Float, seconds: Float) -> Status {
+        let samples = (seconds / TS + 0.5) as usize;
+        let mut status = protection.update(balanced(pu), frequency.into());
+        for _ in 1..samples {
+            status = protection.update(balanced(pu), frequency.into());
+        }
+        status
+    }
+
+    fn protection(category: Category) -> Protection {
+        Protection::new(Settings::category(category, 60.0), Voltage::from(120.0), TS)
+    }
+
+    #[test]
+    fn under_voltage() {
+        let mut protection = protection(Category::II);
+        let status = run(&mut protection, 0.6, 60.0, 9.9);
+        assert_eq!(status.region, Region::Permissive);
+        assert_eq!(status.trip, None);
+        let status = run(&mut protection, 0.6, 60.0, 0.2);
+        assert_eq!(status.trip, Some(Reason::Uv1));
+        assert!(status.cease_to_energize());
+
+        // latched until reset
+        let status = run(&mut protection, 1.0, 60.0, 1.0);
+        assert_eq!(status.trip, Some(Reason::Uv1));
+        protection.reset();
+        let status = run(&mut protection, 1.0, 60.0, 1.0);
+        assert_eq!(status.trip, None);
+        assert_eq!(status.region, Region::Continuous);
+
+        // UV2 is faster
+        let status = run(&mut protection, 0.3, 60.0, 0.17);
+        assert_eq!(status.trip, Some(Reason::Uv2));
+    }
+
+    #[test]
+    fn over_voltage() {
+        let mut protection = protection(Category::I);
+        let status = run(&mut protection, 1.15, 60.0, 1.9);
+        assert_eq!(status.region, Region::Permissive);
+        assert_eq!(status.trip, None);
+        let status = run(&mut protection, 1.15, 60.0, 0.2);
+        assert_eq!(status.trip, Some(Reason::Ov1));
+
+        let mut protection = self::protection(Category::I);
+        let status = run(&mut protection, 1.25, 60.0, 0.15);
+        assert_eq!(status.trip, None);
+        let status = run(&mut protection, 1.25, 60.0, 0.02);
+        assert_eq!(status.trip, Some(Reason::Ov2));
+    }
+
+    #[test]
+    fn timer_reset() {
+        // excursions shorter than the clearing time don't accumulate
+        let mut protection = protection(Category::I);
+        for _ in 0..10 {
+            run(&mut protection, 0.6, 60.0, 1.5);
+            run(&mut protection, 1.0, 60.0, 0.1);
+        }
+        assert_eq!(protection.update(balanced(1.0), 60.0.into()).trip, None);
+    }
+
+    #[test]
+    fn frequency() {
+        let mut protection = protection(Category::II);
+        let status = run(&mut protection, 1.0, 61.5, 10.0);
+        assert_eq!(status.region, Region::Mandatory);
+        assert_eq!(status.trip, None);
+        let status = run(&mut protection, 1.0, 62.5, 0.2);
+        assert_eq!(status.trip, Some(Reason::Of2));
+
+        let mut protection = self::protection(Category::II);
+        let status = run(&mut protection, 1.0, 58.0, 299.0);
+        assert_eq!(status.trip, None);
+        let status = run(&mut protection, 1.0, 58.0, 1.1);
+        assert_eq!(status.trip, Some(Reason::Uf1));
+
+        // 50Hz settings are scaled from 60Hz
+        let settings = Settings::category(Category::II, 50.0);
+        assert!((settings.uf2.threshold - 47.083).abs() < 0.001);
+        assert!((settings.of2.threshold - 51.667).abs() < 0.001);
+    }
+
+    #[test]
+    fn momentary_cessation() {
+        let mut protection = protection(Category::III);
+        let status = run(&mut protection, 0.4, 60.0, 1.0);
+        assert_eq!(status.region, Region::MomentaryCessation);
+        assert_eq!(status.trip, None);
+        assert!(status.cease_to_energize());
+        let status = run(&mut protection, 0.4, 60.0, 1.1);
+        assert_eq!(status.trip, Some(Reason::Uv2));
+
+        let mut protection = self::protection(Category::III);
+        let status = run(&mut protection, 1.15, 60.0, 1.0);
+        assert_eq!(status.region, Region::MomentaryCessation);
+        let status = run(&mut protection, 0.8, 60.0, 1.0);
+        assert_eq!(status.region, Region::Mandatory);
+        assert!(!status.cease_to_energize());
+    }
+
+    #[test]
+    fn unbalanced() {
+        let mut protection = protection(Category::II);
+        let voltage = Abc {
+            a: Voltage::from(120.0),
+            b: Voltage::from(145.0),
+            c: Voltage::from(100.0),
+        };
+        let status = protection.update(voltage, 60.0.into());
+        // b is over-voltage, c is in the mandatory region, the worst wins
+        assert_eq!(status.region, Region::Permissive);
+        for _ in 0..160 {
+            protection.update(voltage, 60.0.into());
+        }
+        assert_eq!(
+            protection.update(voltage, 60.0.into()).trip,
+            Some(Reason::Ov2)
+        );
+    }
+
+    #[test]
+    fn dq() {
+        let mut protection = protection(Category::II);
+        // 0.8 pu peak phase voltage
+        let peak = 0.8 * 120.0 * SQRT_2;
+        let voltage = Dq {
+            d: Voltage::from(peak * 0.6),
+            q: Voltage::from(peak * 0.8),
+        };
+        let status = protection.update_dq(voltage, 60.0.into());
+        assert_eq!(status.region, Region::Mandatory);
+    }
+}
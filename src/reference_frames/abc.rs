@@ -14,6 +14,7 @@
 //    limitations under the License.
 
 use crate::constants::{ONE_HALF, SQRT_3_OVER_2};
+use crate::number::Float;
 use crate::trig::{cos_sin, Sin, Theta};
 use core::ops::{Add, AddAssign, Mul, Sub, SubAssign};
 
@@ -77,7 +78,7 @@ impl<T: Sub<Output = T> + Copy> Sub<T> for Abc<T> {
     type Output = Abc<T>;
 }
 
-impl<T: Mul<f32, Output = T> + Mul<Sin, Output = T> + Copy + From<f32>> Abc<T> {
+impl<T: Mul<Float, Output = T> + Mul<Sin, Output = T> + Copy + From<Float>> Abc<T> {
     pub fn zero() -> Self {
         Self {
             a: 0.0.into(),
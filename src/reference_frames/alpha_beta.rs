@@ -13,6 +13,8 @@
 //    See the License for the specific language governing permissions and
 //    limitations under the License.
 
+use crate::number::Float;
+
 /// Balanced stationary orthoganal reference frame (i.e. clarke)
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct AlphaBeta<T> {
@@ -28,7 +30,7 @@ pub struct AlphaBeta0<T> {
     pub zero: T,
 }
 
-impl<T: From<f32>> AlphaBeta<T> {
+impl<T: From<Float>> AlphaBeta<T> {
     pub fn zero() -> Self {
         Self {
             alpha: 0.0.into(),
@@ -37,7 +39,7 @@ impl<T: From<f32>> AlphaBeta<T> {
     }
 }
 
-impl<T: From<f32>> AlphaBeta0<T> {
+impl<T: From<Float>> AlphaBeta0<T> {
     pub fn zero() -> Self {
         Self {
             alpha: 0.0.into(),
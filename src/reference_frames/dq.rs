@@ -13,8 +13,9 @@
 //    See the License for the specific language governing permissions and
 //    limitations under the License.
 
+use crate::number::Float;
 use crate::trig::rotate;
-use crate::trig::{Cos, Sin};
+use crate::trig::Trig;
 use core::ops::{Add, Mul, Neg, Sub};
 
 /// Balanced rotating reference frame
@@ -110,28 +111,23 @@ impl<T: Sub<Output = T>> Sub<Dq<T>> for Dq0<T> {
     type Output = Dq0<T>;
 }
 
-impl<
-        T: Mul<Sin, Output = T>
-            + Mul<Cos, Output = T>
-            + Sub<Output = T>
-            + Add<Output = T>
-            + Copy
-            + From<f32>,
-    > Dq<T>
-{
+impl<T: Sub<Output = T> + Add<Output = T> + Copy + From<Float>> Dq<T> {
     pub fn zero() -> Self {
         Self {
             d: 0.0.into(),
             q: 0.0.into(),
         }
     }
-    pub fn rotate(&self, cos: Cos, sin: Sin) -> Dq<T> {
+    pub fn rotate<C: Trig>(&self, cos: C, sin: C::Sin) -> Dq<T>
+    where
+        T: Mul<C, Output = T> + Mul<C::Sin, Output = T>,
+    {
         let (d, q) = rotate(self.d, self.q, cos, sin);
         Dq { d, q }
     }
 }
 
-impl<T: From<f32>> Dq0<T> {
+impl<T: From<Float>> Dq0<T> {
     pub fn zero() -> Self {
         Self {
             d: 0.0.into(),
@@ -13,10 +13,9 @@
 //    See the License for the specific language governing permissions and
 //    limitations under the License.
 
-use crate::constants::{ONE_HALF, ONE_THIRD, SQRT_3_OVER_2, SQRT_3_OVER_3, TWO_THIRDS};
-use crate::number::Num;
+use crate::number::{Float, Num};
 use crate::reference_frames::{Abc, AlphaBeta, AlphaBeta0, Dq, Dq0, Polar};
-use crate::trig::{shift_left_120, shift_right_120, Cos, Sin};
+use crate::trig::{atan2, cos_sin, Trig};
 
 impl<T: Num> From<Polar<T>> for Abc<T> {
     fn from(polar: Polar<T>) -> Self {
@@ -24,10 +23,32 @@ impl<T: Num> From<Polar<T>> for Abc<T> {
     }
 }
 
+impl<T: Num> From<Dq<T>> for Polar<T> {
+    fn from(dq: Dq<T>) -> Self {
+        let theta = atan2(dq.q.into(), dq.d.into());
+
+        // project the vector onto its own angle to get the amplitude
+        let (cos, sin) = cos_sin(theta);
+        let amplitude = (dq.d * cos) + (dq.q * sin);
+
+        Self { amplitude, theta }
+    }
+}
+
+impl<T: Num> From<Polar<T>> for Dq<T> {
+    fn from(polar: Polar<T>) -> Self {
+        let (cos, sin) = cos_sin(polar.theta);
+        let d = polar.amplitude * cos;
+        let q = polar.amplitude * sin;
+        Self { d, q }
+    }
+}
+
 impl<T: Num> From<Abc<T>> for AlphaBeta<T> {
     fn from(abc: Abc<T>) -> Self {
-        let alpha = (abc.a * TWO_THIRDS) - (abc.b * ONE_THIRD) - (abc.c * ONE_THIRD);
-        let beta = (abc.b * SQRT_3_OVER_3) - (abc.c * SQRT_3_OVER_3);
+        let alpha =
+            abc.a.scale(T::TWO_THIRDS) - abc.b.scale(T::ONE_THIRD) - abc.c.scale(T::ONE_THIRD);
+        let beta = abc.b.scale(T::SQRT_3_OVER_3) - abc.c.scale(T::SQRT_3_OVER_3);
 
         Self { alpha, beta }
     }
@@ -36,17 +57,18 @@ impl<T: Num> From<Abc<T>> for AlphaBeta<T> {
 impl<T: Num> From<AlphaBeta<T>> for Abc<T> {
     fn from(alpha_beta: AlphaBeta<T>) -> Self {
         let a = alpha_beta.alpha;
-        let b = -(alpha_beta.alpha * ONE_HALF) + alpha_beta.beta * SQRT_3_OVER_2;
-        let c = -alpha_beta.alpha * ONE_HALF - alpha_beta.beta * SQRT_3_OVER_2;
+        let b = -alpha_beta.alpha.scale(T::ONE_HALF) + alpha_beta.beta.scale(T::SQRT_3_OVER_2);
+        let c = -alpha_beta.alpha.scale(T::ONE_HALF) - alpha_beta.beta.scale(T::SQRT_3_OVER_2);
         Self { a, b, c }
     }
 }
 
 impl<T: Num> From<Abc<T>> for AlphaBeta0<T> {
     fn from(abc: Abc<T>) -> Self {
-        let alpha = (abc.a * TWO_THIRDS) - (abc.b * ONE_THIRD) - (abc.c * ONE_THIRD);
-        let beta = (abc.b * SQRT_3_OVER_3) - (abc.c * SQRT_3_OVER_3);
-        let zero = (abc.a + abc.b + abc.c) * ONE_THIRD;
+        let alpha =
+            abc.a.scale(T::TWO_THIRDS) - abc.b.scale(T::ONE_THIRD) - abc.c.scale(T::ONE_THIRD);
+        let beta = abc.b.scale(T::SQRT_3_OVER_3) - abc.c.scale(T::SQRT_3_OVER_3);
+        let zero = (abc.a + abc.b + abc.c).scale(T::ONE_THIRD);
 
         Self { alpha, beta, zero }
     }
@@ -74,16 +96,20 @@ impl<T> From<AlphaBeta0<T>> for AlphaBeta<T> {
 impl<T: Num> From<AlphaBeta0<T>> for Abc<T> {
     fn from(alpha_beta_0: AlphaBeta0<T>) -> Self {
         let a = alpha_beta_0.alpha + alpha_beta_0.zero;
-        let b =
-            -alpha_beta_0.alpha * ONE_HALF + alpha_beta_0.beta * SQRT_3_OVER_2 + alpha_beta_0.zero;
-        let c =
-            -alpha_beta_0.alpha * ONE_HALF - alpha_beta_0.beta * SQRT_3_OVER_2 + alpha_beta_0.zero;
+        let b = -alpha_beta_0.alpha.scale(T::ONE_HALF)
+            + alpha_beta_0.beta.scale(T::SQRT_3_OVER_2)
+            + alpha_beta_0.zero;
+        let c = -alpha_beta_0.alpha.scale(T::ONE_HALF) - alpha_beta_0.beta.scale(T::SQRT_3_OVER_2)
+            + alpha_beta_0.zero;
         Self { a, b, c }
     }
 }
 
 impl<T: Num> AlphaBeta<T> {
-    pub fn to_dq0(&self, cos: Cos, sin: Sin) -> Dq0<T> {
+    pub fn to_dq0<C: Trig>(&self, cos: C, sin: C::Sin) -> Dq0<T>
+    where
+        T: Num<C>,
+    {
         let d = (self.alpha * sin) - (self.beta * cos);
         let q = (self.alpha * cos) + (self.beta * sin);
 
@@ -94,7 +120,10 @@ impl<T: Num> AlphaBeta<T> {
         }
     }
 
-    pub fn to_dq(&self, cos: Cos, sin: Sin) -> Dq<T> {
+    pub fn to_dq<C: Trig>(&self, cos: C, sin: C::Sin) -> Dq<T>
+    where
+        T: Num<C>,
+    {
         let d = (self.alpha * sin) - (self.beta * cos);
         let q = (self.alpha * cos) + (self.beta * sin);
 
@@ -103,7 +132,10 @@ impl<T: Num> AlphaBeta<T> {
 }
 
 impl<T: Num> AlphaBeta0<T> {
-    pub fn to_dq0(&self, cos: Cos, sin: Sin) -> Dq0<T> {
+    pub fn to_dq0<C: Trig>(&self, cos: C, sin: C::Sin) -> Dq0<T>
+    where
+        T: Num<C>,
+    {
         let d = (self.alpha * sin) - (self.beta * cos);
         let q = (self.alpha * cos) + (self.beta * sin);
 
@@ -114,7 +146,10 @@ impl<T: Num> AlphaBeta0<T> {
         }
     }
 
-    pub fn to_dq(&self, cos: Cos, sin: Sin) -> Dq<T> {
+    pub fn to_dq<C: Trig>(&self, cos: C, sin: C::Sin) -> Dq<T>
+    where
+        T: Num<C>,
+    {
         let d = (self.alpha * sin) - (self.beta * cos);
         let q = (self.alpha * cos) + (self.beta * sin);
 
@@ -123,35 +158,51 @@ impl<T: Num> AlphaBeta0<T> {
 }
 
 impl<T: Num> Abc<T> {
-    pub fn to_dq(&self, cos: Cos, sin: Sin) -> Dq<T> {
+    pub fn to_dq<C: Trig>(&self, cos: C, sin: C::Sin) -> Dq<T>
+    where
+        T: Num<C>,
+    {
         /* sin and cos with 120 degree offsets */
-        let (cos_m, sin_m) = shift_left_120(cos, sin);
-        let (cos_p, sin_p) = shift_right_120(cos, sin);
+        let (cos_m, sin_m) = cos.shift_left_120(sin);
+        let (cos_p, sin_p) = cos.shift_right_120(sin);
 
-        let d = ((self.a * sin) + (self.b * sin_m) + (self.c * sin_p)) * TWO_THIRDS;
-        let q = ((self.a * cos) + (self.b * cos_m) + (self.c * cos_p)) * TWO_THIRDS;
+        // scale each term before summing, so fixed point sums don't saturate above 2/3 pu
+        let d = (self.a * sin).scale(T::TWO_THIRDS)
+            + (self.b * sin_m).scale(T::TWO_THIRDS)
+            + (self.c * sin_p).scale(T::TWO_THIRDS);
+        let q = (self.a * cos).scale(T::TWO_THIRDS)
+            + (self.b * cos_m).scale(T::TWO_THIRDS)
+            + (self.c * cos_p).scale(T::TWO_THIRDS);
 
         Dq { d, q }
     }
 
-    pub fn to_dq0(&self, cos: Cos, sin: Sin) -> Dq0<T> {
+    pub fn to_dq0<C: Trig>(&self, cos: C, sin: C::Sin) -> Dq0<T>
+    where
+        T: Num<C>,
+    {
         /* sin and cos with 120 degree offsets */
-        let (cos_m, sin_m) = shift_left_120(cos, sin);
-        let (cos_p, sin_p) = shift_right_120(cos, sin);
-
-        let d = ((self.a * sin) + (self.b * sin_m) + (self.c * sin_p)) * TWO_THIRDS;
-        let q = ((self.a * cos) + (self.b * cos_m) + (self.c * cos_p)) * TWO_THIRDS;
-        let zero = (self.a + self.b + self.c) * ONE_THIRD;
+        let (cos_m, sin_m) = cos.shift_left_120(sin);
+        let (cos_p, sin_p) = cos.shift_right_120(sin);
+
+        let d = (self.a * sin).scale(T::TWO_THIRDS)
+            + (self.b * sin_m).scale(T::TWO_THIRDS)
+            + (self.c * sin_p).scale(T::TWO_THIRDS);
+        let q = (self.a * cos).scale(T::TWO_THIRDS)
+            + (self.b * cos_m).scale(T::TWO_THIRDS)
+            + (self.c * cos_p).scale(T::TWO_THIRDS);
+        let zero =
+            self.a.scale(T::ONE_THIRD) + self.b.scale(T::ONE_THIRD) + self.c.scale(T::ONE_THIRD);
         Dq0 { d, q, zero }
     }
 }
 
-impl<T: Num> From<Abc<T>> for f32
+impl<T: Num> From<Abc<T>> for Float
 where
-    f32: From<T>,
+    Float: From<T>,
 {
     fn from(abc: Abc<T>) -> Self {
-        ((abc.a + abc.b + abc.c) * ONE_THIRD).into()
+        (abc.a.scale(T::ONE_THIRD) + abc.b.scale(T::ONE_THIRD) + abc.c.scale(T::ONE_THIRD)).into()
     }
 }
 
@@ -172,10 +223,13 @@ impl<T> From<Dq0<T>> for Dq<T> {
 }
 
 impl<T: Num> Dq<T> {
-    pub fn to_abc(&self, cos: Cos, sin: Sin) -> Abc<T> {
+    pub fn to_abc<C: Trig>(&self, cos: C, sin: C::Sin) -> Abc<T>
+    where
+        T: Num<C>,
+    {
         /* sin and cos with 120 degree offsets */
-        let (cos_m, sin_m) = shift_left_120(cos, sin);
-        let (cos_p, sin_p) = shift_right_120(cos, sin);
+        let (cos_m, sin_m) = cos.shift_left_120(sin);
+        let (cos_p, sin_p) = cos.shift_right_120(sin);
 
         let a = (self.d * sin) + (self.q * cos);
         let b = (self.d * sin_m) + (self.q * cos_m);
@@ -184,14 +238,20 @@ impl<T: Num> Dq<T> {
         Abc { a, b, c }
     }
 
-    pub fn to_alpha_beta(&self, cos: Cos, sin: Sin) -> AlphaBeta<T> {
+    pub fn to_alpha_beta<C: Trig>(&self, cos: C, sin: C::Sin) -> AlphaBeta<T>
+    where
+        T: Num<C>,
+    {
         let alpha = (self.d * sin) + (self.q * cos);
         let beta = (self.q * sin) - (self.d * cos);
 
         AlphaBeta { alpha, beta }
     }
 
-    pub fn to_alpha_beta_0(&self, cos: Cos, sin: Sin) -> AlphaBeta0<T> {
+    pub fn to_alpha_beta_0<C: Trig>(&self, cos: C, sin: C::Sin) -> AlphaBeta0<T>
+    where
+        T: Num<C>,
+    {
         let alpha = (self.d * sin) + (self.q * cos);
         let beta = (self.q * sin) - (self.d * cos);
 
@@ -204,7 +264,10 @@ impl<T: Num> Dq<T> {
 }
 
 impl<T: Num> Dq0<T> {
-    pub fn to_abc(&self, cos: Cos, sin: Sin) -> Abc<T> {
+    pub fn to_abc<C: Trig>(&self, cos: C, sin: C::Sin) -> Abc<T>
+    where
+        T: Num<C>,
+    {
         Dq {
             d: self.d,
             q: self.q,
@@ -213,14 +276,20 @@ impl<T: Num> Dq0<T> {
             + self.zero
     }
 
-    pub fn to_alpha_beta(&self, cos: Cos, sin: Sin) -> AlphaBeta<T> {
+    pub fn to_alpha_beta<C: Trig>(&self, cos: C, sin: C::Sin) -> AlphaBeta<T>
+    where
+        T: Num<C>,
+    {
         let alpha = (self.d * sin) + (self.q * cos);
         let beta = (self.q * sin) - (self.d * cos);
 
         AlphaBeta { alpha, beta }
     }
 
-    pub fn to_alpha_beta_0(&self, cos: Cos, sin: Sin) -> AlphaBeta0<T> {
+    pub fn to_alpha_beta_0<C: Trig>(&self, cos: C, sin: C::Sin) -> AlphaBeta0<T>
+    where
+        T: Num<C>,
+    {
         let alpha = (self.d * sin) + (self.q * cos);
         let beta = (self.q * sin) - (self.d * cos);
 
@@ -233,10 +302,10 @@ impl<T: Num> Dq0<T> {
 }
 
 #[cfg(test)]
+#[allow(clippy::excessive_precision, clippy::approx_constant)]
 mod tests {
 
     use super::*;
-    use crate::trig::cos_sin;
     use crate::trig::Theta;
     use approx::assert_abs_diff_eq;
 
@@ -290,7 +359,7 @@ mod tests {
 
     #[test]
     fn dq0_to_abc() {
-        let dq0: Dq0<f32> = Dq0 {
+        let dq0: Dq0<Float> = Dq0 {
             d: 1.0,
             q: 2.0,
             zero: 3.0,
@@ -340,7 +409,7 @@ mod tests {
             alpha: 1.0,
             beta: 2.0,
         };
-        let alpha_beta0 = AlphaBeta0::<f32>::from(alpha_beta);
+        let alpha_beta0 = AlphaBeta0::<Float>::from(alpha_beta);
 
         assert_abs_diff_eq!(alpha_beta0.alpha, alpha_beta.alpha, epsilon = 0.0001);
         assert_abs_diff_eq!(alpha_beta0.beta, alpha_beta.beta, epsilon = 0.0001);
@@ -482,4 +551,35 @@ mod tests {
         assert_abs_diff_eq!(dq.d, dq0.d, epsilon = 0.0001);
         assert_abs_diff_eq!(dq.q, dq0.q, epsilon = 0.0001);
     }
+
+    #[test]
+    fn dq_to_polar() {
+        let dq = Dq { d: 1.0, q: 1.0 };
+        let polar = Polar::from(dq);
+        assert_abs_diff_eq!(polar.amplitude, 1.4142135, epsilon = 0.0001);
+        assert_abs_diff_eq!(polar.theta.to_degrees(), 45.0, epsilon = 0.001);
+
+        let dq = Dq { d: -2.0, q: 0.0 };
+        let polar = Polar::from(dq);
+        assert_abs_diff_eq!(polar.amplitude, 2.0, epsilon = 0.0001);
+        assert_abs_diff_eq!(polar.theta.to_degrees().abs(), 180.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn polar_to_dq() {
+        let polar = Polar {
+            amplitude: 2.0,
+            theta: Theta::from_degrees(30.0),
+        };
+        let dq = Dq::from(polar);
+
+        // the phase of the polar is relative to the rotating frame
+        let theta = Theta::from_degrees(40.0);
+        let (cos, sin) = cos_sin(theta);
+        let abc = dq.to_abc(cos, sin);
+        let expected = Abc::from_polar(2.0, Theta::from_degrees(70.0));
+        assert_abs_diff_eq!(abc.a, expected.a, epsilon = 0.0001);
+        assert_abs_diff_eq!(abc.b, expected.b, epsilon = 0.0001);
+        assert_abs_diff_eq!(abc.c, expected.c, epsilon = 0.0001);
+    }
 }
@@ -0,0 +1,92 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use super::Theta;
+use crate::newtypes::Frequency;
+use crate::number::Float;
+
+/// Integrates a frequency into an angle, sample by sample
+///
+/// The step is calculated in f64 regardless of the precision of [Float], and rounded to the nearest count of a [Theta],
+/// so the angle only drifts by that rounding (at most half a count per sample) rather than by the precision of an f32.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PhaseAccumulator {
+    theta: Theta,
+    ts: Float,
+}
+
+impl PhaseAccumulator {
+    /// Creates an accumulator starting at zero, for a sample period (in seconds)
+    pub fn new(ts: Float) -> Self {
+        Self {
+            theta: Theta::from(0),
+            ts,
+        }
+    }
+
+    /// The angle for this sample
+    pub fn theta(&self) -> Theta {
+        self.theta
+    }
+
+    /// Returns the angle for this sample, and advances it by a frequency to the next sample
+    pub fn update(&mut self, frequency: Frequency) -> Theta {
+        let theta = self.theta;
+        #[allow(clippy::unnecessary_cast)]
+        let step = (Float::from(frequency) as f64) * (self.ts as f64) * 4294967296.0;
+        self.theta += libm::Libm::<f64>::round(step) as i64 as i32;
+        theta
+    }
+
+    /// Sets the angle for this sample
+    pub fn set(&mut self, theta: Theta) {
+        self.theta = theta;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn accumulate() {
+        // a quarter of a 50Hz cycle at 10kHz
+        let mut phase = PhaseAccumulator::new(1e-4);
+        for k in 0..50 {
+            let expected = 360.0 * 50.0 * (k as Float) * 1e-4;
+            let theta = phase.update(Frequency::from(50.0));
+            assert_abs_diff_eq!(theta.to_degrees(), expected, epsilon = 1e-3);
+        }
+        assert_abs_diff_eq!(phase.theta().to_degrees(), 90.0, epsilon = 1e-3);
+
+        // and wraps back to zero after a whole number of cycles
+        for _ in 0..1950 {
+            phase.update(Frequency::from(50.0));
+        }
+        assert_abs_diff_eq!(phase.theta().to_degrees(), 0.0, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn exact_step() {
+        // 1Hz at 4096Hz is exactly 2^20 counts per sample, so a cycle lands exactly back on zero
+        let mut phase = PhaseAccumulator::new(1.0 / 4096.0);
+        for _ in 0..4096 {
+            phase.update(Frequency::from(1.0));
+        }
+        assert_eq!(phase.theta(), Theta::from(0));
+    }
+}
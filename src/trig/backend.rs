@@ -0,0 +1,223 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::constants::PI;
+use crate::number::Float;
+use crate::trig::{Cos, Sin, Theta};
+
+/// An implementation of the trig functions
+///
+/// [cos_sin](crate::trig::cos_sin) and [atan2](crate::trig::atan2) use [Idsp] by default, the `trig-libm` and
+/// `trig-table` features select [Libm] and [Table] instead.  If both features are enabled, [Libm] takes precedence.
+pub trait Backend {
+    /// Calculates cos and sin from theta
+    fn cos_sin(theta: Theta) -> (Cos, Sin);
+
+    /// Calculates the four quadrant arctangent of y/x as a theta
+    fn atan2(y: Float, x: Float) -> Theta;
+}
+
+/// Fixed-point trig from the [idsp](https://crates.io/crates/idsp) crate (accurate to about 1e-5)
+pub struct Idsp;
+
+/// Floating point trig from the [libm](https://crates.io/crates/libm) crate
+pub struct Libm;
+
+/// Table lookup, interpolated with a short polynomial, accurate to the precision of [Float]
+pub struct Table;
+
+impl Backend for Idsp {
+    fn cos_sin(theta: Theta) -> (Cos, Sin) {
+        let (cos, sin) = idsp::cossin(theta.into());
+        (cos.into(), sin.into())
+    }
+
+    fn atan2(y: Float, x: Float) -> Theta {
+        // scale the vector to the full i32 range for the fixed-point arctangent
+        let norm = Float::max(Float::abs(x), Float::abs(y));
+        if norm == 0.0 {
+            return Theta::from(0);
+        }
+        let scale = 2147483647.0 / norm;
+
+        idsp::atan2((y * scale) as i32, (x * scale) as i32).into()
+    }
+}
+
+impl Backend for Libm {
+    fn cos_sin(theta: Theta) -> (Cos, Sin) {
+        let (sin, cos) = libm::Libm::<Float>::sincos(theta.to_radians());
+        (cos.into(), sin.into())
+    }
+
+    fn atan2(y: Float, x: Float) -> Theta {
+        Theta::from_radians(libm::Libm::<Float>::atan2(y, x))
+    }
+}
+
+const TABLE_BITS: u32 = 8;
+const TABLE_LEN: usize = 1 << TABLE_BITS;
+
+// cos and sin of x, for |x| <= pi, from their taylor series
+const fn taylor(x: f64) -> (f64, f64) {
+    let mut cos = 0.0;
+    let mut sin = 0.0;
+    // x^n/n!
+    let mut term = 1.0;
+    let mut n = 0;
+    while n < 40 {
+        let sign = if (n / 2) % 2 == 0 { 1.0 } else { -1.0 };
+        if n % 2 == 0 {
+            cos += sign * term;
+        } else {
+            sin += sign * term;
+        }
+        n += 1;
+        term = term * x / (n as f64);
+    }
+    (cos, sin)
+}
+
+// cos and sin of TABLE_LEN points around the circle, calculated at compile time
+const fn table() -> [(Float, Float); TABLE_LEN] {
+    let mut table = [(0.0, 0.0); TABLE_LEN];
+    let mut i = 0;
+    while i < TABLE_LEN {
+        // index i is the angle 2*pi*i/TABLE_LEN, wrapped to [-pi, pi)
+        let x = 2.0 * core::f64::consts::PI * (i as f64) / (TABLE_LEN as f64);
+        let x = if x >= core::f64::consts::PI {
+            x - 2.0 * core::f64::consts::PI
+        } else {
+            x
+        };
+        let (cos, sin) = taylor(x);
+        table[i] = (cos as Float, sin as Float);
+        i += 1;
+    }
+    table
+}
+
+static TABLE: [(Float, Float); TABLE_LEN] = table();
+
+impl Backend for Table {
+    fn cos_sin(theta: Theta) -> (Cos, Sin) {
+        // the upper bits of theta index the table, the rest are a small residual angle
+        let theta = i32::from(theta) as u32;
+        let (cos_a, sin_a) = TABLE[(theta >> (32 - TABLE_BITS)) as usize];
+        let residual = theta & (u32::MAX >> TABLE_BITS);
+        let b = (residual as Float) * (PI / 2147483648.0);
+
+        // cos and sin of the residual angle (less than 2pi/TABLE_LEN) from short taylor series
+        let b2 = b * b;
+        let cos_b = 1.0 - b2 * (0.5 - b2 * (1.0 / 24.0 - b2 * (1.0 / 720.0)));
+        let sin_b = b * (1.0 - b2 * (1.0 / 6.0 - b2 * (1.0 / 120.0)));
+
+        // rotate the table entry by the residual angle
+        let cos = cos_a * cos_b - sin_a * sin_b;
+        let sin = sin_a * cos_b + cos_a * sin_b;
+        (cos.into(), sin.into())
+    }
+
+    fn atan2(y: Float, x: Float) -> Theta {
+        // refine the fixed-point estimate by rotating the vector onto it, which leaves a
+        // small residual angle whose arctangent is accurately given by a short taylor series
+        let estimate = Idsp::atan2(y, x);
+        let (cos, sin) = Self::cos_sin(estimate);
+        let (cos, sin) = (Float::from(cos), Float::from(sin));
+        let xr = x * cos + y * sin;
+        let yr = y * cos - x * sin;
+        if xr == 0.0 {
+            return estimate;
+        }
+        let r = yr / xr;
+        let residual = r * (1.0 - r * r * (1.0 / 3.0));
+
+        estimate + Theta::from_radians(residual)
+    }
+}
+
+#[cfg(not(any(feature = "trig-libm", feature = "trig-table")))]
+pub(crate) type Selected = Idsp;
+
+#[cfg(feature = "trig-libm")]
+pub(crate) type Selected = Libm;
+
+#[cfg(all(feature = "trig-table", not(feature = "trig-libm")))]
+pub(crate) type Selected = Table;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // largest error of a backend's cos and sin against the std f64 implementations
+    #[allow(clippy::unnecessary_cast)]
+    fn cos_sin_error<B: Backend>() -> f64 {
+        let mut error: f64 = 0.0;
+        for n in 0..10000i32 {
+            let theta = Theta::from(i32::MIN.wrapping_add(n.wrapping_mul(429496)));
+            let radians = (i32::from(theta) as f64) * core::f64::consts::PI / 2147483648.0;
+            let (cos, sin) = B::cos_sin(theta);
+            error = error.max((Float::from(cos) as f64 - radians.cos()).abs());
+            error = error.max((Float::from(sin) as f64 - radians.sin()).abs());
+        }
+        error
+    }
+
+    // largest error of a backend's atan2 against the std f64 implementation, in radians
+    #[allow(clippy::unnecessary_cast)]
+    fn atan2_error<B: Backend>() -> f64 {
+        let mut error: f64 = 0.0;
+        for n in 0..3600 {
+            let angle = (n as f64) * core::f64::consts::PI / 1800.0;
+            let magnitude = 0.001 + (n as f64);
+            let (x, y) = (magnitude * angle.cos(), magnitude * angle.sin());
+            let theta = B::atan2(y as Float, x as Float);
+            let expected = (y as Float as f64).atan2(x as Float as f64);
+            let difference =
+                (i32::from(theta) as f64) * core::f64::consts::PI / 2147483648.0 - expected;
+            // wrap the difference to [-pi, pi]
+            let difference = (difference + core::f64::consts::PI)
+                .rem_euclid(2.0 * core::f64::consts::PI)
+                - core::f64::consts::PI;
+            error = error.max(difference.abs());
+        }
+        error
+    }
+
+    // tolerance for the floating point backends
+    #[cfg(not(feature = "f64"))]
+    const EPSILON: f64 = 1e-6;
+    #[cfg(feature = "f64")]
+    const EPSILON: f64 = 5e-9;
+
+    #[test]
+    fn accuracy() {
+        assert!(cos_sin_error::<Idsp>() < 2e-5);
+        assert!(cos_sin_error::<Libm>() < EPSILON);
+        assert!(cos_sin_error::<Table>() < EPSILON);
+
+        assert!(atan2_error::<Idsp>() < 2e-5);
+        assert!(atan2_error::<Libm>() < EPSILON);
+        assert!(atan2_error::<Table>() < EPSILON);
+    }
+
+    #[test]
+    fn zero_vector() {
+        assert_eq!(Idsp::atan2(0.0, 0.0), Theta::from(0));
+        assert_eq!(Libm::atan2(0.0, 0.0), Theta::from(0));
+        assert_eq!(Table::atan2(0.0, 0.0), Theta::from(0));
+    }
+}
@@ -19,45 +19,69 @@ Trigometric functions and data types for processing ac power signals.
 
 The sin, cos, and arctan functions are wrappers around the [idsp](https://crates.io/crates/idsp) crate implementations, which are implemented with optimized fixed-point arithmetic for resource constrained platforms (i.e. microcontrollers).
 
+# Backends
+
+The idsp functions are accurate to about 1e-5, which is not enough for very low distortion measurements.  The
+`trig-libm` feature switches to the floating point [libm](https://crates.io/crates/libm) implementations, and the
+`trig-table` feature switches to a table lookup with polynomial interpolation, which is accurate to the precision of
+the float type without the cost of libm.  All the backends are also available directly through the [Backend] trait.
+
+```rust
+use ac_power::number::Float;
+use ac_power::trig::{Backend, Libm, Table, Theta};
+use approx::assert_abs_diff_eq;
+
+let theta = Theta::from_degrees(30.0);
+let (cos, sin) = Table::cos_sin(theta);
+assert_abs_diff_eq!(Float::from(sin), 0.5, epsilon = 1e-6);
+
+let theta = Libm::atan2(1.0, 1.0);
+assert_abs_diff_eq!(theta.to_degrees(), 45.0, epsilon = 1e-4);
+```
+
 # Newtypes
 
-This module defines three new data types which are wrappers around i32 and f32 prmitives
+This module defines three new data types which are wrappers around i32 and [Float] prmitives
 
 1. [Theta(i32)](crate::trig::Theta) - Representation of a phase between -π and +π radians
-2. [Sin(f32)](crate::trig::Sin)- Representation of a sin(theta)
-3. [Cos(f32)](crate::trig::Cos) - Representation of a cos(theta)
+2. [Sin(Float)](crate::trig::Sin)- Representation of a sin(theta)
+3. [Cos(Float)](crate::trig::Cos) - Representation of a cos(theta)
 
 Each newtype contains constructors for instantiating on instance from a variable
 
 ```rust
+use ac_power::constants::PI;
 use ac_power::trig::{Theta, Sin, Cos};
 
 let theta = Theta::from_degrees(90.0);
-let theta = Theta::from_radians(core::f32::consts::PI/2.0);
+let theta = Theta::from_radians(PI/2.0);
 let sin = Sin::from_degrees(90.0);
-let cos = Cos::from_radians(core::f32::consts::PI/2.0);
+let cos = Cos::from_radians(PI/2.0);
 ```
 
 They also include conversions for easily converting to and from the native data types they wrap.
 
 ```rust
+use ac_power::number::Float;
 use ac_power::trig::{Theta, Sin, Cos};
 
 let theta: Theta = 536870912.into();
 let sin = Sin::from(0.6);
-let sin_as_float: f32 = sin.into();
+let sin_as_float: Float = sin.into();
 ```
 
 `Sin` and `Cos` types support multiplication to other numeric types, always returning the other type.
 
 ```rust
+use ac_power::constants::FRAC_1_SQRT_2;
+use ac_power::number::Float;
 use ac_power::trig::Sin;
 use ac_power::Voltage;
 use approx::assert_abs_diff_eq;
 
 let sin = Sin::from_degrees(45.0);
 let v: Voltage = Voltage::from(1.0) * sin;
-assert_abs_diff_eq!(f32::from(v), 0.707, epsilon = 0.0001);
+assert_abs_diff_eq!(Float::from(v), FRAC_1_SQRT_2, epsilon = 0.0001);
 ```
 
 The `Theta` data-type supports a wrapping add assign.
@@ -71,25 +95,60 @@ theta += Theta::from_degrees(2.0);
 assert_abs_diff_eq!(theta.to_degrees(), -179.0, epsilon = 0.0001);
 ```
 
+A [PhaseAccumulator] uses this to integrate a frequency into a `Theta`, sample by sample.
+
 # Functions
 
-The trig modules contains 5 functions which are useful for ac power processing
+The trig modules contains 6 functions which are useful for ac power processing
 
 1. [cos_sin] - Calculate cos and sin from theta simultaneously.
-2. [rotate] - Function for rotating a vector using Ptolemy's theorem
-3. [shift_right_120] - Function for rotating a vector clockwise by 120 degrees
-4. [shift_left_120] - Function for rotating a vector counter-clockwise by 120 degrees
-5. [chebyshev] - calculate sin(Nx) and cos(Nx) using chebyshev method
+2. [atan2] - Calculate theta from a (y, x) vector
+3. [rotate] - Function for rotating a vector using Ptolemy's theorem
+4. [shift_right_120] - Function for rotating a vector clockwise by 120 degrees
+5. [shift_left_120] - Function for rotating a vector counter-clockwise by 120 degrees
+6. [chebyshev] - calculate sin(Nx) and cos(Nx) using chebyshev method
+
+# Representations
+
+The reference frame transforms are generic over the [Trig] trait, which is implemented for the [Cos]/[Sin] newtypes,
+and for the [Q31](crate::fixed::Q31) fixed-point type, so transforms can also run without floating point (see
+[fixed](crate::fixed)).
 
 */
 
+mod accumulator;
+mod backend;
 mod newtypes;
+use crate::number::Float;
+pub use accumulator::PhaseAccumulator;
+pub use backend::{Backend, Idsp, Libm, Table};
 use core::ops::{Add, Mul, Sub};
 pub use newtypes::{Cos, Sin, Theta};
 
 use crate::constants::{ONE_HALF, SQRT_3_OVER_2};
 
-use idsp;
+/// A representation of cos(theta), with its matching representation of sin(theta), that can be used to rotate vectors
+pub trait Trig: Copy {
+    type Sin: Copy;
+
+    /// Shifts a cos/sin pair 120 degrees left (-2pi/3)
+    fn shift_left_120(self, sin: Self::Sin) -> (Self, Self::Sin);
+
+    /// Shifts a cos/sin pair 120 degrees right (+2pi/3)
+    fn shift_right_120(self, sin: Self::Sin) -> (Self, Self::Sin);
+}
+
+impl Trig for Cos {
+    type Sin = Sin;
+
+    fn shift_left_120(self, sin: Sin) -> (Cos, Sin) {
+        shift_left_120(self, sin)
+    }
+
+    fn shift_right_120(self, sin: Sin) -> (Cos, Sin) {
+        shift_right_120(self, sin)
+    }
+}
 
 /// Calculates sin and cos from theta
 ///
@@ -102,11 +161,22 @@ use idsp;
 /// let (cos, sin) = cos_sin(theta);
 /// ```
 pub fn cos_sin(theta: Theta) -> (Cos, Sin) {
-    // use the idsp library cos/sin function
-    let (cos, sin) = idsp::cossin(theta.into());
+    backend::Selected::cos_sin(theta)
+}
 
-    // convert to the Cos and Sin newtypes
-    (cos.into(), sin.into())
+/// Calculates the four quadrant arctangent of y/x as a theta
+///
+/// # Examples
+///
+/// ```
+/// use ac_power::trig::atan2;
+/// use approx::assert_abs_diff_eq;
+///
+/// let theta = atan2(1.0, 1.0);
+/// assert_abs_diff_eq!(theta.to_degrees(), 45.0, epsilon = 0.001);
+/// ```
+pub fn atan2(y: Float, x: Float) -> Theta {
+    backend::Selected::atan2(y, x)
 }
 
 /// Use Ptolemy's theorem to rotate a vector
@@ -122,12 +192,13 @@ pub fn cos_sin(theta: Theta) -> (Cos, Sin) {
 /// let (xr, yr) = rotate(x, y, cos, sin);
 /// ```
 pub fn rotate<
-    T: Copy + Mul<Sin, Output = T> + Mul<Cos, Output = T> + Sub<Output = T> + Add<Output = T>,
+    T: Copy + Mul<C, Output = T> + Mul<C::Sin, Output = T> + Sub<Output = T> + Add<Output = T>,
+    C: Trig,
 >(
     x: T,
     y: T,
-    cos: Cos,
-    sin: Sin,
+    cos: C,
+    sin: C::Sin,
 ) -> (T, T) {
     let xr = x * cos - y * sin;
     let yr = x * sin + y * cos;
@@ -149,10 +220,10 @@ pub fn rotate<
 /// ```
 pub fn shift_right_120(cos: Cos, sin: Sin) -> (Cos, Sin) {
     let (cosr, sinr) = rotate(
-        f32::from(cos),
-        f32::from(sin),
-        (-ONE_HALF).into(),
-        SQRT_3_OVER_2.into(),
+        Float::from(cos),
+        Float::from(sin),
+        Cos::from(-ONE_HALF),
+        Sin::from(SQRT_3_OVER_2),
     );
     (cosr.into(), sinr.into())
 }
@@ -171,10 +242,10 @@ pub fn shift_right_120(cos: Cos, sin: Sin) -> (Cos, Sin) {
 /// ```
 pub fn shift_left_120(cos: Cos, sin: Sin) -> (Cos, Sin) {
     let (cosr, sinr) = rotate(
-        f32::from(cos),
-        f32::from(sin),
-        (-ONE_HALF).into(),
-        (-SQRT_3_OVER_2).into(),
+        Float::from(cos),
+        Float::from(sin),
+        Cos::from(-ONE_HALF),
+        Sin::from(-SQRT_3_OVER_2),
     );
     (cosr.into(), sinr.into())
 }
@@ -184,9 +255,10 @@ pub fn shift_left_120(cos: Cos, sin: Sin) -> (Cos, Sin) {
 /// # Examples
 ///
 /// ```
+/// use ac_power::number::Float;
 /// use ac_power::trig::{chebyshev, Theta, cos_sin, Sin, Cos};
 ///
-/// let radians: f32 = 1.2;
+/// let radians: Float = 1.2;
 /// let theta = Theta::from_radians(radians);
 /// let (cos0, sin0) = (Cos::from(1.0), Sin::from(0.0));
 /// let (cos1, sin1) = cos_sin(theta);
@@ -194,8 +266,8 @@ pub fn shift_left_120(cos: Cos, sin: Sin) -> (Cos, Sin) {
 /// let (cos3, sin3) = chebyshev(cos1, cos2, sin2, cos1, sin1);
 /// ```
 pub fn chebyshev(cos: Cos, cos1: Cos, sin1: Sin, cos2: Cos, sin2: Sin) -> (Cos, Sin) {
-    let cosn = 2.0 * (cos * cos1) - f32::from(cos2);
-    let sinn = 2.0 * (cos * sin1) - f32::from(sin2);
+    let cosn = 2.0 * (cos * cos1) - Float::from(cos2);
+    let sinn = 2.0 * (cos * sin1) - Float::from(sin2);
     (cosn.into(), sinn.into())
 }
 
@@ -205,32 +277,46 @@ mod tests {
     use super::*;
     use approx::assert_abs_diff_eq;
 
-    use std::f32::consts::PI;
+    use crate::constants::PI;
 
     #[test]
     fn test_cos_sin() {
-        let radians: f32 = 1.2;
+        let radians: Float = 1.2;
         let theta = Theta::from_radians(radians);
         let (cos, sin) = cos_sin(theta);
 
-        assert_abs_diff_eq!(f32::from(sin), radians.sin(), epsilon = 0.0001);
-        assert_abs_diff_eq!(f32::from(cos), radians.cos(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(sin), radians.sin(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(cos), radians.cos(), epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_atan2() {
+        for degrees in [-179.0, -135.0, -90.0, -30.0, 0.0, 10.0, 90.0, 120.0, 179.0] {
+            let radians = degrees_to_radians(degrees);
+            let theta = atan2(3.0 * radians.sin(), 3.0 * radians.cos());
+            assert_abs_diff_eq!(theta.to_degrees(), degrees, epsilon = 0.001);
+        }
+        assert_eq!(i32::from(atan2(0.0, 0.0)), 0);
+    }
+
+    fn degrees_to_radians(degrees: Float) -> Float {
+        2.0 * PI * (degrees / 360.0)
     }
 
     #[test]
     fn shift_left() {
-        let radians: f32 = 1.2;
+        let radians: Float = 1.2;
         let theta = Theta::from_radians(radians);
         let (cos, sin) = cos_sin(theta);
         let (cos_shifted, sin_shifted) = shift_left_120(cos, sin);
 
         assert_abs_diff_eq!(
-            f32::from(sin_shifted),
+            Float::from(sin_shifted),
             (radians - 2.0 * PI / 3.0).sin(),
             epsilon = 0.0001
         );
         assert_abs_diff_eq!(
-            f32::from(cos_shifted),
+            Float::from(cos_shifted),
             (radians - 2.0 * PI / 3.0).cos(),
             epsilon = 0.0001
         );
@@ -238,18 +324,18 @@ mod tests {
 
     #[test]
     fn shift_right() {
-        let radians: f32 = 1.2;
+        let radians: Float = 1.2;
         let theta = Theta::from_radians(radians);
         let (cos, sin) = cos_sin(theta);
         let (cos_shifted, sin_shifted) = shift_right_120(cos, sin);
 
         assert_abs_diff_eq!(
-            f32::from(sin_shifted),
+            Float::from(sin_shifted),
             (radians + 2.0 * PI / 3.0).sin(),
             epsilon = 0.0001
         );
         assert_abs_diff_eq!(
-            f32::from(cos_shifted),
+            Float::from(cos_shifted),
             (radians + 2.0 * PI / 3.0).cos(),
             epsilon = 0.0001
         );
@@ -257,16 +343,16 @@ mod tests {
 
     #[test]
     fn chebyshev_works() {
-        let radians: f32 = 1.2;
+        let radians: Float = 1.2;
         let theta = Theta::from_radians(radians);
         let (cos0, sin0) = (Cos::from(1.0), Sin::from(0.0));
         let (cos1, sin1) = cos_sin(theta);
         let (cos2, sin2) = chebyshev(cos1, cos1, sin1, cos0, sin0);
         let (cos3, sin3) = chebyshev(cos1, cos2, sin2, cos1, sin1);
 
-        assert_abs_diff_eq!(f32::from(sin2), (2.0 * radians).sin(), epsilon = 0.0001);
-        assert_abs_diff_eq!(f32::from(cos2), (2.0 * radians).cos(), epsilon = 0.0001);
-        assert_abs_diff_eq!(f32::from(sin3), (3.0 * radians).sin(), epsilon = 0.0001);
-        assert_abs_diff_eq!(f32::from(cos3), (3.0 * radians).cos(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(sin2), (2.0 * radians).sin(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(cos2), (2.0 * radians).cos(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(sin3), (3.0 * radians).sin(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(cos3), (3.0 * radians).cos(), epsilon = 0.0001);
     }
 }
@@ -13,6 +13,7 @@
 //    See the License for the specific language governing permissions and
 //    limitations under the License.
 
+use crate::number::Float;
 use crate::trig::cos_sin;
 use crate::trig::Sin;
 use crate::trig::Theta;
@@ -22,7 +23,7 @@ use derive_more::{From, Into};
 
 /// Cos of an angle
 #[derive(Debug, Copy, Clone, PartialEq, From, Into)]
-pub struct Cos(f32);
+pub struct Cos(Float);
 
 impl Cos {
     /// Calculates cos from theta
@@ -49,7 +50,7 @@ impl Cos {
     ///
     /// let cos = Cos::from_degrees(45.0);
     /// ```
-    pub fn from_degrees(degrees: f32) -> Self {
+    pub fn from_degrees(degrees: Float) -> Self {
         let theta = Theta::from_degrees(degrees);
         Self::from_theta(theta)
     }
@@ -59,11 +60,12 @@ impl Cos {
     /// # Examples
     ///
     /// ```
+    /// use ac_power::constants::PI;
     /// use ac_power::trig::Cos;
     ///
-    /// let cos = Cos::from_radians(core::f32::consts::PI/4.0);
+    /// let cos = Cos::from_radians(PI/4.0);
     /// ```
-    pub fn from_radians(radians: f32) -> Self {
+    pub fn from_radians(radians: Float) -> Self {
         let theta = Theta::from_radians(radians);
         Self::from_theta(theta)
     }
@@ -78,22 +80,22 @@ impl Neg for Cos {
 
 impl From<i32> for Cos {
     fn from(item: i32) -> Self {
-        Self((item as f32) / 2147483648.)
+        Self((item as Float) / 2147483648.)
     }
 }
 
 impl Mul<Cos> for Cos {
-    fn mul(self, other: Cos) -> f32 {
+    fn mul(self, other: Cos) -> Float {
         self.0 * other.0
     }
-    type Output = f32;
+    type Output = Float;
 }
 
 impl Mul<Sin> for Cos {
-    fn mul(self, rhs: Sin) -> f32 {
+    fn mul(self, rhs: Sin) -> Float {
         self.0 * rhs
     }
-    type Output = f32;
+    type Output = Float;
 }
 
 #[cfg(test)]
@@ -102,30 +104,30 @@ mod tests {
     use super::*;
     use approx::assert_abs_diff_eq;
 
-    use std::f32::consts::PI;
+    use crate::constants::PI;
 
-    fn linspace(x0: f32, x1: f32, length: usize) -> Vec<f32> {
-        let dx = (x1 - x0) / ((length - 1) as f32);
-        let mut xs: Vec<f32> = vec![x0];
+    fn linspace(x0: Float, x1: Float, length: usize) -> Vec<Float> {
+        let dx = (x1 - x0) / ((length - 1) as Float);
+        let mut xs: Vec<Float> = vec![x0];
         for index in 1..length {
             xs.push(xs[index - 1] + dx);
         }
         xs
     }
 
-    fn degrees_to_radians(degrees: f32) -> f32 {
+    fn degrees_to_radians(degrees: Float) -> Float {
         2.0 * PI * (degrees / 360.0)
     }
 
-    fn check_from_degrees(degrees: f32) {
+    fn check_from_degrees(degrees: Float) {
         let cos = Cos::from_degrees(degrees);
         let radians = degrees_to_radians(degrees);
-        assert_abs_diff_eq!(f32::from(cos), radians.cos(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(cos), radians.cos(), epsilon = 0.0001);
     }
 
-    fn check_from_radians(radians: f32) {
+    fn check_from_radians(radians: Float) {
         let cos = Cos::from_radians(radians);
-        assert_abs_diff_eq!(f32::from(cos), radians.cos(), epsilon = 0.0001);
+        assert_abs_diff_eq!(Float::from(cos), radians.cos(), epsilon = 0.0001);
     }
 
     #[test]
@@ -145,7 +147,7 @@ mod tests {
     fn conversions() {
         let x = 0.707;
         let cos = Cos::from(x);
-        assert_abs_diff_eq!(x, f32::from(cos), epsilon = 0.0001);
+        assert_abs_diff_eq!(x, Float::from(cos), epsilon = 0.0001);
     }
 
     #[test]
@@ -17,36 +17,37 @@ mod cos;
 mod sin;
 mod theta;
 
+use crate::number::Float;
 use core::ops::Mul;
 pub use cos::Cos;
 pub use sin::Sin;
 pub use theta::Theta;
 
-// impliment the trig multiplies for f32, our base primitive
-impl Mul<f32> for Sin {
-    fn mul(self, rhs: f32) -> f32 {
-        f32::from(self) * rhs
+// impliment the trig multiplies for Float, our base primitive
+impl Mul<Float> for Sin {
+    fn mul(self, rhs: Float) -> Float {
+        Float::from(self) * rhs
     }
-    type Output = f32;
+    type Output = Float;
 }
 
-impl Mul<Sin> for f32 {
-    fn mul(self, rhs: Sin) -> f32 {
-        self * f32::from(rhs)
+impl Mul<Sin> for Float {
+    fn mul(self, rhs: Sin) -> Float {
+        self * Float::from(rhs)
     }
-    type Output = f32;
+    type Output = Float;
 }
 
-impl Mul<f32> for Cos {
-    fn mul(self, rhs: f32) -> f32 {
-        f32::from(self) * rhs
+impl Mul<Float> for Cos {
+    fn mul(self, rhs: Float) -> Float {
+        Float::from(self) * rhs
     }
-    type Output = f32;
+    type Output = Float;
 }
 
-impl Mul<Cos> for f32 {
-    fn mul(self, rhs: Cos) -> f32 {
-        self * f32::from(rhs)
+impl Mul<Cos> for Float {
+    fn mul(self, rhs: Cos) -> Float {
+        self * Float::from(rhs)
     }
-    type Output = f32;
+    type Output = Float;
 }
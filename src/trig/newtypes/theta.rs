@@ -14,17 +14,21 @@
 //    limitations under the License.
 
 use crate::constants::PI;
+use crate::number::Float;
 
 use core::convert::From;
 use core::fmt;
-use core::ops::AddAssign;
+use core::ops::{Add, AddAssign, Sub};
+
+// a full circle is 2^32 (u32::MAX rounds to this in f32, but not in f64)
+const FULL_CIRCLE: Float = 4294967296.0;
 
 /// Representation of an angle between -π and π radians
 #[derive(Copy, Clone, PartialEq)]
 pub struct Theta(i32);
 
 impl Theta {
-    pub fn from_degrees(mut degrees: f32) -> Self {
+    pub fn from_degrees(mut degrees: Float) -> Self {
         while degrees > 180.0 {
             degrees -= 360.0;
         }
@@ -32,15 +36,15 @@ impl Theta {
             degrees += 360.0
         }
 
-        let theta = ((degrees / 360.0) * (u32::MAX as f32)) as i32;
+        let theta = ((degrees / 360.0) * FULL_CIRCLE) as i32;
         theta.into()
     }
 
-    pub fn to_degrees(&self) -> f32 {
-        360.0 * (self.0 as f32) / (u32::MAX as f32)
+    pub fn to_degrees(&self) -> Float {
+        360.0 * (self.0 as Float) / FULL_CIRCLE
     }
 
-    pub fn from_radians(mut radians: f32) -> Self {
+    pub fn from_radians(mut radians: Float) -> Self {
         while radians > PI {
             radians -= 2.0 * PI;
         }
@@ -48,12 +52,12 @@ impl Theta {
             radians += 2.0 * PI;
         }
 
-        let theta = ((radians / (2.0 * PI)) * (u32::MAX as f32)) as i32;
+        let theta = ((radians / (2.0 * PI)) * FULL_CIRCLE) as i32;
         theta.into()
     }
 
-    pub fn to_radians(&self) -> f32 {
-        (2.0 * PI) * (self.0 as f32) / (u32::MAX as f32)
+    pub fn to_radians(&self) -> Float {
+        (2.0 * PI) * (self.0 as Float) / FULL_CIRCLE
     }
 }
 
@@ -69,6 +73,20 @@ impl AddAssign<Theta> for Theta {
     }
 }
 
+impl Add<Theta> for Theta {
+    fn add(self, rhs: Theta) -> Theta {
+        Self(self.0.wrapping_add(rhs.0))
+    }
+    type Output = Theta;
+}
+
+impl Sub<Theta> for Theta {
+    fn sub(self, rhs: Theta) -> Theta {
+        Self(self.0.wrapping_sub(rhs.0))
+    }
+    type Output = Theta;
+}
+
 impl From<i32> for Theta {
     fn from(item: i32) -> Self {
         Self(item)
@@ -100,4 +118,13 @@ mod tests {
         let theta = Theta::from_radians(0.5 * PI);
         assert_eq!(i32::from(theta), 1073741824);
     }
+
+    #[test]
+    fn wrapping_arithmetic() {
+        let theta = Theta::from_degrees(170.0) + Theta::from_degrees(20.0);
+        assert!((theta.to_degrees() + 170.0).abs() < 0.0001);
+
+        let theta = Theta::from_degrees(-170.0) - Theta::from_degrees(20.0);
+        assert!((theta.to_degrees() - 170.0).abs() < 0.0001);
+    }
 }
@@ -0,0 +1,587 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+/*!
+A simple three-phase waveform generator
+
+Harmonics are stored as [Dq] vectors indexed by harmonic order (index 0 is the fundamental),
+but are usually easier to configure by order, magnitude and phase, as they appear on a spec sheet.
+
+```rust
+use ac_power::number::Float;
+use ac_power::wavegen::{Magnitude, Sequence, Waveform};
+use ac_power::trig::Theta;
+
+// 230V fundamental with a 4% 5th harmonic at 30 degrees
+let waveform: Waveform<Float, 7> = Waveform::new()
+    .with_harmonic(1, Sequence::Positive, Magnitude::Absolute(230.0), Theta::from_degrees(0.0))
+    .with_harmonic(5, Sequence::Negative, Magnitude::Percent(4.0), Theta::from_degrees(30.0));
+
+let abc = waveform.calculate(Theta::from_degrees(90.0));
+```
+
+Waveforms matching the compliance test spectra of IEEE 519 and IEC 61000-2-4 can be created with
+[Waveform::ieee_519] and [Waveform::iec_61000_2_4].
+
+A [Scenario] wraps a waveform with a sample clock and scheduled [Event]s (sags, swells, phase jumps,
+frequency steps and ramps, interruptions and dc offsets), producing an iterator of `Abc` samples.
+
+Measurement artifacts (seeded white and pink noise, quantization, gain and offset errors, and
+sampling jitter) can be added to the samples with an [Adc] model.
+*/
+
+mod noise;
+mod scenario;
+pub use noise::{Adc, PinkNoise, Quantizer, Rng, WhiteNoise};
+pub use scenario::{Event, EventKind, Scenario};
+
+use crate::number::{Float, Num};
+use crate::trig::{chebyshev, cos_sin, Cos, Sin, Theta};
+use crate::{Abc, Dq, Polar};
+use libm::Libm;
+
+/// The symmetrical sequence of a three-phase component
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Sequence {
+    Positive,
+    Negative,
+    Zero,
+}
+
+impl Sequence {
+    /// The sequence a harmonic order naturally takes in a balanced system
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ac_power::wavegen::Sequence;
+    ///
+    /// assert_eq!(Sequence::natural(5), Sequence::Negative);
+    /// assert_eq!(Sequence::natural(7), Sequence::Positive);
+    /// assert_eq!(Sequence::natural(9), Sequence::Zero);
+    /// ```
+    pub fn natural(order: usize) -> Self {
+        match order % 3 {
+            0 => Sequence::Zero,
+            1 => Sequence::Positive,
+            _ => Sequence::Negative,
+        }
+    }
+}
+
+/// Magnitude of a harmonic component
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Magnitude<T> {
+    /// An absolute amplitude
+    Absolute(T),
+    /// A percentage of the positive sequence fundamental amplitude
+    Percent(Float),
+}
+
+/// IEEE 519 bus voltage categories (Table 1)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Ieee519Bus {
+    /// V ≤ 1.0 kV
+    LowVoltage,
+    /// 1 kV < V ≤ 69 kV
+    MediumVoltage,
+    /// 69 kV < V ≤ 161 kV
+    HighVoltage,
+    /// V > 161 kV
+    ExtraHighVoltage,
+}
+
+impl Ieee519Bus {
+    /// Individual harmonic and total harmonic distortion limits (in %)
+    pub fn limits(&self) -> (Float, Float) {
+        match self {
+            Ieee519Bus::LowVoltage => (5.0, 8.0),
+            Ieee519Bus::MediumVoltage => (3.0, 5.0),
+            Ieee519Bus::HighVoltage => (1.5, 2.5),
+            Ieee519Bus::ExtraHighVoltage => (1.0, 1.5),
+        }
+    }
+}
+
+/// IEC 61000-2-4 electromagnetic environment classes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Iec61000Class {
+    Class1,
+    Class2,
+    Class3,
+}
+
+impl Iec61000Class {
+    /// Harmonic voltage compatibility level (in %) for a harmonic order (Table 2)
+    pub fn compatibility_level(&self, order: usize) -> Float {
+        let h = order as Float;
+        let (c1, c2, c3) = match order {
+            1 => (100.0, 100.0, 100.0),
+            2 => (2.0, 2.0, 3.0),
+            3 => (3.0, 5.0, 6.0),
+            4 => (1.0, 1.0, 1.5),
+            5 => (3.0, 6.0, 8.0),
+            6 | 8 => (0.5, 0.5, 1.0),
+            7 => (3.0, 5.0, 7.0),
+            9 => (1.5, 1.5, 2.5),
+            11 => (3.0, 3.5, 5.0),
+            13 => (3.0, 3.0, 4.5),
+            15 => (0.3, 0.4, 2.0),
+            17 => (2.0, 2.0, 4.0),
+            21 => (0.2, 0.3, 1.75),
+            _ if order.is_multiple_of(2) => {
+                let level = 0.25 * 10.0 / h + 0.25;
+                (level, level, 1.0)
+            }
+            _ if order.is_multiple_of(3) => (0.2, 0.2, 1.0),
+            _ => {
+                let level = 2.27 * 17.0 / h - 0.27;
+                (level, level, 4.5 * 17.0 / h - 0.5)
+            }
+        };
+
+        match self {
+            Iec61000Class::Class1 => c1,
+            Iec61000Class::Class2 => c2,
+            Iec61000Class::Class3 => c3,
+        }
+    }
+}
+
+// phase a of a positive or zero sequence vector is d*sin(nx) + q*cos(nx), but the negative
+// sequence is rotated with -sin(nx), so its d axis is inverted
+fn sequence_to_dq<T: Num>(polar: Polar<T>, sequence: Sequence) -> Dq<T> {
+    let dq = Dq::from(polar);
+    match sequence {
+        Sequence::Negative => Dq { d: -dq.d, q: dq.q },
+        _ => dq,
+    }
+}
+
+fn dq_to_sequence<T: Num>(dq: Dq<T>, sequence: Sequence) -> Polar<T> {
+    match sequence {
+        Sequence::Negative => Polar::from(Dq { d: -dq.d, q: dq.q }),
+        _ => Polar::from(dq),
+    }
+}
+
+fn sequence_to_abc<T: Num>(dq: Dq<T>, sequence: Sequence, cos: Cos, sin: Sin) -> Abc<T> {
+    match sequence {
+        Sequence::Positive => dq.to_abc(cos, sin),
+        Sequence::Negative => dq.to_abc(cos, -sin),
+        Sequence::Zero => Abc::zero() + dq.d * sin + dq.q * cos,
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Waveform<T, const N: usize> {
+    pub positive: [Dq<T>; N],
+    pub negative: [Dq<T>; N],
+    pub zero: Dq<T>,
+    // zero sequence harmonics above the fundamental, indexed by order like the positive and negative
+    // sequences (index 0 is unused, the fundamental is `zero`)
+    zero_harmonics: [Dq<T>; N],
+}
+
+impl<T: Num, const N: usize> Default for Waveform<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Num, const N: usize> Waveform<T, N> {
+    pub fn new() -> Self {
+        Self {
+            positive: [Dq::zero(); N],
+            negative: [Dq::zero(); N],
+            zero: Dq::zero(),
+            zero_harmonics: [Dq::zero(); N],
+        }
+    }
+
+    /// Creates a waveform with the IEEE 519 voltage distortion limits for a bus category
+    ///
+    /// The characteristic harmonics (6k±1) are filled at the individual harmonic limit until the
+    /// total harmonic distortion limit is reached.
+    pub fn ieee_519(fundamental: T, bus: Ieee519Bus) -> Self {
+        let (individual, total) = bus.limits();
+        let mut waveform = Self::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(fundamental),
+            Theta::from(0),
+        );
+
+        let mut budget = total * total;
+        for order in (5..=N).filter(|h| h % 6 == 1 || h % 6 == 5) {
+            let percent = Float::min(individual, Libm::<Float>::sqrt(budget));
+            budget -= percent * percent;
+            waveform.set_harmonic(
+                order,
+                Sequence::natural(order),
+                Magnitude::Percent(percent),
+                Theta::from(0),
+            );
+
+            // the last harmonic takes whatever is left of the budget
+            if percent < individual {
+                break;
+            }
+        }
+
+        waveform
+    }
+
+    /// Creates a waveform with every harmonic (up to N) at its IEC 61000-2-4 compatibility level
+    pub fn iec_61000_2_4(fundamental: T, class: Iec61000Class) -> Self {
+        let mut waveform = Self::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(fundamental),
+            Theta::from(0),
+        );
+
+        for order in 2..=N {
+            waveform.set_harmonic(
+                order,
+                Sequence::natural(order),
+                Magnitude::Percent(class.compatibility_level(order)),
+                Theta::from(0),
+            );
+        }
+
+        waveform
+    }
+
+    /// Sets the magnitude and phase of a harmonic
+    ///
+    /// The phase is relative to the fundamental, such that phase a of the harmonic is
+    /// `magnitude * sin(order * theta + phase)`.  Percentage magnitudes are relative to the
+    /// positive sequence fundamental, so it should be set first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is 0 or greater than `N`.
+    pub fn set_harmonic(
+        &mut self,
+        order: usize,
+        sequence: Sequence,
+        magnitude: Magnitude<T>,
+        phase: Theta,
+    ) {
+        assert!(order > 0 && order <= N, "harmonic order out of range");
+
+        let amplitude = match magnitude {
+            Magnitude::Absolute(amplitude) => amplitude,
+            Magnitude::Percent(percent) => self.fundamental() * (0.01 * percent),
+        };
+        let dq = sequence_to_dq(
+            Polar {
+                amplitude,
+                theta: phase,
+            },
+            sequence,
+        );
+
+        match sequence {
+            Sequence::Positive => self.positive[order - 1] = dq,
+            Sequence::Negative => self.negative[order - 1] = dq,
+            Sequence::Zero if order == 1 => self.zero = dq,
+            Sequence::Zero => self.zero_harmonics[order - 1] = dq,
+        }
+    }
+
+    /// Builder version of [Waveform::set_harmonic]
+    pub fn with_harmonic(
+        mut self,
+        order: usize,
+        sequence: Sequence,
+        magnitude: Magnitude<T>,
+        phase: Theta,
+    ) -> Self {
+        self.set_harmonic(order, sequence, magnitude, phase);
+        self
+    }
+
+    /// Returns the magnitude and phase of a harmonic
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is 0 or greater than `N`.
+    pub fn harmonic(&self, order: usize, sequence: Sequence) -> Polar<T> {
+        assert!(order > 0 && order <= N, "harmonic order out of range");
+
+        let dq = match sequence {
+            Sequence::Positive => self.positive[order - 1],
+            Sequence::Negative => self.negative[order - 1],
+            Sequence::Zero if order == 1 => self.zero,
+            Sequence::Zero => self.zero_harmonics[order - 1],
+        };
+        dq_to_sequence(dq, sequence)
+    }
+
+    /// Amplitude of the positive sequence fundamental
+    pub fn fundamental(&self) -> T {
+        match self.positive.first() {
+            Some(dq) => Polar::from(*dq).amplitude,
+            None => 0.0.into(),
+        }
+    }
+
+    pub fn calculate(&self, theta: Theta) -> Abc<T> {
+        let (cos, sin) = cos_sin(theta);
+
+        let mut abc = sequence_to_abc(self.zero, Sequence::Zero, cos, sin);
+
+        // add the harmonics
+        let (mut cosn1, mut sinn1) = (Cos::from(1.0), Sin::from(0.0));
+        let (mut cosn, mut sinn) = (cos, sin);
+        for ((pos, neg), zero) in self
+            .positive
+            .iter()
+            .zip(self.negative.iter())
+            .zip(self.zero_harmonics.iter())
+        {
+            abc += sequence_to_abc(*pos, Sequence::Positive, cosn, sinn);
+            abc += sequence_to_abc(*neg, Sequence::Negative, cosn, sinn);
+            abc += sequence_to_abc(*zero, Sequence::Zero, cosn, sinn);
+
+            // use chebychev function to calculate cos, sin of next harmonic
+            let cosn2 = cosn1;
+            let sinn2 = sinn1;
+            cosn1 = cosn;
+            sinn1 = sinn;
+            (cosn, sinn) = chebyshev(cos, cosn1, sinn1, cosn2, sinn2);
+        }
+
+        abc
+    }
+}
+
+/// A three-phase component at a non-integer multiple of the fundamental
+///
+/// Interharmonics are not periodic with the fundamental, so they track their own phase, which is
+/// advanced with the phase step of the fundamental.
+///
+/// # Examples
+///
+/// ```
+/// use ac_power::number::Float;
+/// use ac_power::wavegen::{Interharmonic, Sequence, Waveform};
+/// use ac_power::trig::Theta;
+///
+/// let waveform: Waveform<Float, 1> = Waveform::new();
+/// let mut interharmonic = Interharmonic::new(2.5, Sequence::Positive, 5.0, Theta::from(0));
+///
+/// let mut theta = Theta::from(0);
+/// let step = Theta::from_degrees(1.0);
+/// for _ in 0..100 {
+///     theta += step;
+///     interharmonic.update(step);
+///     let abc = waveform.calculate(theta) + interharmonic.calculate();
+/// }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Interharmonic<T> {
+    pub order: Float,
+    pub sequence: Sequence,
+    pub dq: Dq<T>,
+    theta: Theta,
+}
+
+impl<T: Num> Interharmonic<T> {
+    pub fn new(order: Float, sequence: Sequence, amplitude: T, phase: Theta) -> Self {
+        let dq = sequence_to_dq(
+            Polar {
+                amplitude,
+                theta: phase,
+            },
+            sequence,
+        );
+        Self {
+            order,
+            sequence,
+            dq,
+            theta: Theta::from(0),
+        }
+    }
+
+    /// Advances the interharmonic phase by a step of the fundamental phase
+    pub fn update(&mut self, step: Theta) {
+        // f64 keeps the fractional phase step accurate, the i64 to i32 cast wraps the phase
+        #[allow(clippy::unnecessary_cast)]
+        let step = (i32::from(step) as f64) * (self.order as f64);
+        self.theta += step as i64 as i32;
+    }
+
+    pub fn calculate(&self) -> Abc<T> {
+        let (cos, sin) = cos_sin(self.theta);
+        sequence_to_abc(self.dq, self.sequence, cos, sin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    use crate::constants::PI;
+
+    fn thd<const N: usize>(waveform: &Waveform<Float, N>) -> Float {
+        let mut sum = 0.0;
+        for order in 2..=N {
+            for sequence in [Sequence::Positive, Sequence::Negative, Sequence::Zero] {
+                let amplitude = waveform.harmonic(order, sequence).amplitude;
+                sum += amplitude * amplitude;
+            }
+        }
+        100.0 * sum.sqrt() / waveform.fundamental()
+    }
+
+    #[test]
+    fn harmonic_by_order() {
+        let waveform: Waveform<Float, 7> = Waveform::new()
+            .with_harmonic(
+                1,
+                Sequence::Positive,
+                Magnitude::Absolute(100.0),
+                Theta::from_degrees(0.0),
+            )
+            .with_harmonic(
+                5,
+                Sequence::Negative,
+                Magnitude::Percent(4.0),
+                Theta::from_degrees(30.0),
+            );
+
+        let fifth = waveform.harmonic(5, Sequence::Negative);
+        assert_abs_diff_eq!(fifth.amplitude, 4.0, epsilon = 0.001);
+        assert_abs_diff_eq!(fifth.theta.to_degrees(), 30.0, epsilon = 0.01);
+
+        for degrees in [0.0, 15.0, 45.0, 100.0, -170.0] {
+            let x = 2.0 * PI * degrees / 360.0;
+            let abc = waveform.calculate(Theta::from_degrees(degrees));
+            let shift = 2.0 * PI / 3.0;
+            let fifth = |x: Float| 4.0 * (5.0 * x + PI / 6.0).sin();
+            assert_abs_diff_eq!(abc.a, 100.0 * x.sin() + fifth(x), epsilon = 0.01);
+            assert_abs_diff_eq!(
+                abc.b,
+                100.0 * (x - shift).sin() + fifth(x - shift),
+                epsilon = 0.01
+            );
+            assert_abs_diff_eq!(
+                abc.c,
+                100.0 * (x + shift).sin() + fifth(x + shift),
+                epsilon = 0.01
+            );
+        }
+    }
+
+    #[test]
+    fn zero_sequence() {
+        let waveform: Waveform<Float, 3> = Waveform::new().with_harmonic(
+            3,
+            Sequence::Zero,
+            Magnitude::Absolute(10.0),
+            Theta::from_degrees(-45.0),
+        );
+
+        let x: Float = 0.3;
+        let abc = waveform.calculate(Theta::from_radians(x));
+        let expected = 10.0 * (3.0 * x - PI / 4.0).sin();
+        assert_abs_diff_eq!(abc.a, expected, epsilon = 0.001);
+        assert_abs_diff_eq!(abc.b, expected, epsilon = 0.001);
+        assert_abs_diff_eq!(abc.c, expected, epsilon = 0.001);
+    }
+
+    #[test]
+    fn fundamental_zero_sequence() {
+        // the fundamental zero sequence is the public zero field
+        let waveform: Waveform<Float, 3> = Waveform::new().with_harmonic(
+            1,
+            Sequence::Zero,
+            Magnitude::Absolute(2.0),
+            Theta::from_degrees(90.0),
+        );
+        assert_abs_diff_eq!(waveform.zero.d, 0.0, epsilon = 0.001);
+        assert_abs_diff_eq!(waveform.zero.q, 2.0, epsilon = 0.001);
+        assert_abs_diff_eq!(
+            waveform.harmonic(1, Sequence::Zero).amplitude,
+            2.0,
+            epsilon = 0.001
+        );
+
+        let x: Float = 0.3;
+        let abc = waveform.calculate(Theta::from_radians(x));
+        assert_abs_diff_eq!(abc.a, 2.0 * x.cos(), epsilon = 0.001);
+        assert_abs_diff_eq!(abc.b, 2.0 * x.cos(), epsilon = 0.001);
+        assert_abs_diff_eq!(abc.c, 2.0 * x.cos(), epsilon = 0.001);
+    }
+
+    #[test]
+    fn interharmonic() {
+        let mut interharmonic =
+            Interharmonic::new(2.5, Sequence::Positive, 5.0, Theta::from_degrees(10.0));
+
+        let step = Theta::from_degrees(3.0);
+        for n in 1..500 {
+            interharmonic.update(step);
+            let x = 2.5 * 2.0 * PI * (3.0 * n as Float) / 360.0 + 2.0 * PI * 10.0 / 360.0;
+            let abc = interharmonic.calculate();
+            assert_abs_diff_eq!(abc.a, 5.0 * x.sin(), epsilon = 0.001);
+            assert_abs_diff_eq!(abc.b, 5.0 * (x - 2.0 * PI / 3.0).sin(), epsilon = 0.001);
+        }
+    }
+
+    #[test]
+    fn ieee_519() {
+        let waveform: Waveform<Float, 25> = Waveform::ieee_519(100.0, Ieee519Bus::LowVoltage);
+        assert_abs_diff_eq!(thd(&waveform), 8.0, epsilon = 0.01);
+        assert_abs_diff_eq!(
+            waveform.harmonic(5, Sequence::Negative).amplitude,
+            5.0,
+            epsilon = 0.001
+        );
+
+        let waveform: Waveform<Float, 25> = Waveform::ieee_519(100.0, Ieee519Bus::HighVoltage);
+        assert_abs_diff_eq!(thd(&waveform), 2.5, epsilon = 0.01);
+    }
+
+    #[test]
+    fn iec_61000_2_4() {
+        let waveform: Waveform<Float, 13> = Waveform::iec_61000_2_4(100.0, Iec61000Class::Class2);
+        assert_abs_diff_eq!(
+            waveform.harmonic(5, Sequence::Negative).amplitude,
+            6.0,
+            epsilon = 0.001
+        );
+        assert_abs_diff_eq!(
+            waveform.harmonic(3, Sequence::Zero).amplitude,
+            5.0,
+            epsilon = 0.001
+        );
+        assert_abs_diff_eq!(
+            waveform.harmonic(2, Sequence::Negative).amplitude,
+            2.0,
+            epsilon = 0.001
+        );
+        assert_abs_diff_eq!(
+            Iec61000Class::Class1.compatibility_level(19),
+            1.76,
+            epsilon = 0.01
+        );
+    }
+}
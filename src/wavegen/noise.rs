@@ -0,0 +1,424 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::number::{Float, Num};
+use crate::trig::{cos_sin, Theta};
+use crate::wavegen::Waveform;
+use crate::Abc;
+use libm::Libm;
+
+// normalizes the pink noise filter output to unit variance
+const PINK_GAIN: Float = 0.33568;
+
+/// A small, seeded xorshift pseudo random number generator
+#[derive(Debug, Copy, Clone)]
+pub struct Rng(u32);
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // scramble the seed so that nearby seeds give unrelated sequences (and zero is valid)
+        let mut state = seed.wrapping_mul(0x9E3779B9) ^ 0x6A09E667;
+        if state == 0 {
+            state = 0x6A09E667;
+        }
+        Self(state)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Uniformly distributed number in [0, 1)
+    pub fn uniform(&mut self) -> Float {
+        ((self.next_u32() >> 8) as Float) / 16777216.0
+    }
+}
+
+/// Seeded white gaussian noise (Box-Muller method)
+#[derive(Debug, Copy, Clone)]
+pub struct WhiteNoise {
+    pub sigma: Float,
+    rng: Rng,
+    spare: Option<Float>,
+}
+
+impl WhiteNoise {
+    pub fn new(sigma: Float, seed: u32) -> Self {
+        Self {
+            sigma,
+            rng: Rng::new(seed),
+            spare: None,
+        }
+    }
+
+    pub fn update(&mut self) -> Float {
+        if let Some(spare) = self.spare.take() {
+            return self.sigma * spare;
+        }
+
+        // the angle is uniform over the full circle, so a random theta is all that's needed
+        let u = 1.0 - self.rng.uniform();
+        let r = Libm::<Float>::sqrt(-2.0 * Libm::<Float>::log(u));
+        let (cos, sin) = cos_sin(Theta::from(self.rng.next_u32() as i32));
+
+        self.spare = Some(r * sin);
+        self.sigma * (r * cos)
+    }
+}
+
+/// Seeded pink (1/f) noise, using Paul Kellet's economy filter of white noise
+#[derive(Debug, Copy, Clone)]
+pub struct PinkNoise {
+    white: WhiteNoise,
+    state: [Float; 3],
+}
+
+impl PinkNoise {
+    pub fn new(sigma: Float, seed: u32) -> Self {
+        Self {
+            white: WhiteNoise::new(sigma * PINK_GAIN, seed),
+            state: [0.0; 3],
+        }
+    }
+
+    pub fn update(&mut self) -> Float {
+        let white = self.white.update();
+        self.state[0] = 0.99765 * self.state[0] + white * 0.0990460;
+        self.state[1] = 0.96300 * self.state[1] + white * 0.2965164;
+        self.state[2] = 0.57000 * self.state[2] + white * 1.0526913;
+        self.state[0] + self.state[1] + self.state[2] + white * 0.1848
+    }
+}
+
+/// An ADC quantizer with a bipolar full-scale range
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quantizer {
+    pub bits: u32,
+    pub full_scale: Float,
+}
+
+impl Quantizer {
+    /// Creates a quantizer with a resolution in bits, for inputs from `-full_scale` to `full_scale`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is 0 or greater than 63.
+    pub fn new(bits: u32, full_scale: Float) -> Self {
+        assert!(bits > 0 && bits < 64, "quantizer resolution out of range");
+        Self { bits, full_scale }
+    }
+
+    /// The size of one code
+    pub fn lsb(&self) -> Float {
+        2.0 * self.full_scale / ((1u64 << self.bits) as Float)
+    }
+
+    /// Rounds to the nearest code, saturating at the ends of the range
+    pub fn quantize(&self, x: Float) -> Float {
+        let lsb = self.lsb();
+        let max = ((1u64 << (self.bits - 1)) - 1) as Float;
+        let code = Libm::<Float>::round(x / lsb).clamp(-max - 1.0, max);
+        code * lsb
+    }
+}
+
+/// Model of a three channel ADC measuring a [Waveform]
+///
+/// The artifacts are applied in the order: sampling jitter, gain and offset errors, white and pink
+/// noise, then quantization.  All the random sources are seeded, so the output is deterministic.
+///
+/// # Examples
+///
+/// ```
+/// use ac_power::number::Float;
+/// use ac_power::wavegen::{Adc, Magnitude, Sequence, Waveform};
+/// use ac_power::trig::Theta;
+/// use ac_power::Abc;
+///
+/// let waveform: Waveform<Float, 1> = Waveform::new().with_harmonic(
+///     1,
+///     Sequence::Positive,
+///     Magnitude::Absolute(325.0),
+///     Theta::from(0),
+/// );
+///
+/// let mut adc = Adc::new(42)
+///     .with_white_noise(0.5)
+///     .with_gain_error(Abc { a: 1.01, b: 1.0, c: 0.995 })
+///     .with_jitter(50e-9, 50.0)
+///     .with_quantization(12, 400.0);
+///
+/// let abc = adc.sample(&waveform, Theta::from_degrees(30.0));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Adc<T> {
+    pub gain: Abc<Float>,
+    pub offset: Abc<T>,
+    pub quantizer: Option<Quantizer>,
+    white: Option<[WhiteNoise; 3]>,
+    pink: Option<[PinkNoise; 3]>,
+    jitter: Option<WhiteNoise>,
+    seed: u32,
+}
+
+impl<T: Num> Adc<T> {
+    /// Creates an ideal ADC, with the seed used for all subsequently configured random sources
+    pub fn new(seed: u32) -> Self {
+        Self {
+            gain: Abc {
+                a: 1.0,
+                b: 1.0,
+                c: 1.0,
+            },
+            offset: Abc::zero(),
+            quantizer: None,
+            white: None,
+            pink: None,
+            jitter: None,
+            seed,
+        }
+    }
+
+    // each random source gets its own seed so they are uncorrelated
+    fn next_seed(&mut self) -> u32 {
+        self.seed = Rng::new(self.seed).next_u32();
+        self.seed
+    }
+
+    /// Adds white gaussian noise with a standard deviation of sigma to each channel
+    pub fn with_white_noise(mut self, sigma: Float) -> Self {
+        self.white = Some([
+            WhiteNoise::new(sigma, self.next_seed()),
+            WhiteNoise::new(sigma, self.next_seed()),
+            WhiteNoise::new(sigma, self.next_seed()),
+        ]);
+        self
+    }
+
+    /// Adds pink noise with a standard deviation of sigma to each channel
+    pub fn with_pink_noise(mut self, sigma: Float) -> Self {
+        self.pink = Some([
+            PinkNoise::new(sigma, self.next_seed()),
+            PinkNoise::new(sigma, self.next_seed()),
+            PinkNoise::new(sigma, self.next_seed()),
+        ]);
+        self
+    }
+
+    /// Quantizes each channel
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is 0 or greater than 63.
+    pub fn with_quantization(mut self, bits: u32, full_scale: Float) -> Self {
+        self.quantizer = Some(Quantizer::new(bits, full_scale));
+        self
+    }
+
+    /// Scales each channel
+    pub fn with_gain_error(mut self, gain: Abc<Float>) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Offsets each channel
+    pub fn with_offset_error(mut self, offset: Abc<T>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Adds sampling jitter with an rms of `rms` seconds to a waveform at `frequency` Hz
+    pub fn with_jitter(mut self, rms: Float, frequency: Float) -> Self {
+        let sigma = rms * frequency * 4294967296.0;
+        self.jitter = Some(WhiteNoise::new(sigma, self.next_seed()));
+        self
+    }
+
+    /// Samples a waveform at theta, including all the measurement artifacts
+    pub fn sample<const N: usize>(&mut self, waveform: &Waveform<T, N>, theta: Theta) -> Abc<T> {
+        let theta = match self.jitter.as_mut() {
+            Some(noise) => theta + Theta::from(noise.update() as i32),
+            None => theta,
+        };
+        self.apply(waveform.calculate(theta))
+    }
+
+    /// Applies the amplitude artifacts (everything except jitter) to a sample
+    pub fn apply(&mut self, abc: Abc<T>) -> Abc<T> {
+        let mut abc = Abc {
+            a: abc.a * self.gain.a,
+            b: abc.b * self.gain.b,
+            c: abc.c * self.gain.c,
+        } + self.offset;
+
+        if let Some(white) = self.white.as_mut() {
+            abc.a += white[0].update().into();
+            abc.b += white[1].update().into();
+            abc.c += white[2].update().into();
+        }
+
+        if let Some(pink) = self.pink.as_mut() {
+            abc.a += pink[0].update().into();
+            abc.b += pink[1].update().into();
+            abc.c += pink[2].update().into();
+        }
+
+        match self.quantizer {
+            Some(quantizer) => Abc {
+                a: quantizer.quantize(abc.a.into()).into(),
+                b: quantizer.quantize(abc.b.into()).into(),
+                c: quantizer.quantize(abc.c.into()).into(),
+            },
+            None => abc,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::wavegen::{Magnitude, Sequence};
+    use approx::assert_abs_diff_eq;
+
+    fn statistics(xs: &[Float]) -> (Float, Float) {
+        let mean = xs.iter().sum::<Float>() / (xs.len() as Float);
+        let variance =
+            xs.iter().map(|x| (x - mean) * (x - mean)).sum::<Float>() / (xs.len() as Float);
+        (mean, variance.sqrt())
+    }
+
+    // correlation between neighbouring samples
+    fn lag_correlation(xs: &[Float]) -> Float {
+        let (mean, sigma) = statistics(xs);
+        let sum: Float = xs.windows(2).map(|w| (w[0] - mean) * (w[1] - mean)).sum();
+        sum / ((xs.len() - 1) as Float) / (sigma * sigma)
+    }
+
+    #[test]
+    fn uniform() {
+        let mut rng = Rng::new(0);
+        let xs: Vec<Float> = (0..100000).map(|_| rng.uniform()).collect();
+        assert!(xs.iter().all(|x| (0.0..1.0).contains(x)));
+        let (mean, sigma) = statistics(&xs);
+        assert_abs_diff_eq!(mean, 0.5, epsilon = 0.01);
+        assert_abs_diff_eq!(sigma, ((1.0 as Float) / 12.0).sqrt(), epsilon = 0.01);
+    }
+
+    #[test]
+    fn white_noise() {
+        let mut noise = WhiteNoise::new(2.0, 7);
+        let xs: Vec<Float> = (0..100000).map(|_| noise.update()).collect();
+        let (mean, sigma) = statistics(&xs);
+        assert_abs_diff_eq!(mean, 0.0, epsilon = 0.05);
+        assert_abs_diff_eq!(sigma, 2.0, epsilon = 0.05);
+        assert_abs_diff_eq!(lag_correlation(&xs), 0.0, epsilon = 0.02);
+
+        // seeded noise is repeatable
+        let mut noise = WhiteNoise::new(2.0, 7);
+        assert!(xs[0..100].iter().all(|x| *x == noise.update()));
+    }
+
+    #[test]
+    fn pink_noise() {
+        let mut noise = PinkNoise::new(1.0, 3);
+        let xs: Vec<Float> = (0..200000).map(|_| noise.update()).collect();
+        let (_, sigma) = statistics(&xs[10000..]);
+        assert_abs_diff_eq!(sigma, 1.0, epsilon = 0.1);
+
+        // pink noise is dominated by low frequencies, so neighbouring samples are correlated
+        assert!(lag_correlation(&xs[10000..]) > 0.8);
+    }
+
+    #[test]
+    fn quantizer() {
+        let quantizer = Quantizer::new(8, 1.0);
+        assert_abs_diff_eq!(quantizer.lsb(), 1.0 / 128.0);
+        assert_abs_diff_eq!(quantizer.quantize(0.3), 38.0 / 128.0);
+        assert_abs_diff_eq!(quantizer.quantize(-0.3), -38.0 / 128.0);
+        assert_abs_diff_eq!(quantizer.quantize(2.0), 127.0 / 128.0);
+        assert_abs_diff_eq!(quantizer.quantize(-2.0), -1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn quantizer_without_bits() {
+        Quantizer::new(0, 1.0);
+    }
+
+    #[test]
+    fn adc() {
+        let waveform: Waveform<Float, 1> = Waveform::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(100.0),
+            Theta::from(0),
+        );
+
+        // an ideal adc is transparent
+        let mut adc = Adc::new(1);
+        let theta = Theta::from_degrees(60.0);
+        assert_eq!(adc.sample(&waveform, theta), waveform.calculate(theta));
+
+        let mut adc = Adc::new(1)
+            .with_gain_error(Abc {
+                a: 1.1,
+                b: 1.0,
+                c: 0.9,
+            })
+            .with_offset_error(Abc {
+                a: 0.0,
+                b: 1.0,
+                c: 0.0,
+            })
+            .with_quantization(16, 200.0);
+        let abc = adc.sample(&waveform, theta);
+        let expected = waveform.calculate(theta);
+        let lsb = 400.0 / 65536.0;
+        assert_abs_diff_eq!(abc.a, 1.1 * expected.a, epsilon = lsb);
+        assert_abs_diff_eq!(abc.b, expected.b + 1.0, epsilon = lsb);
+        assert_abs_diff_eq!(abc.c, 0.9 * expected.c, epsilon = lsb);
+
+        // the channels get independent noise
+        let mut adc = Adc::new(1).with_white_noise(1.0);
+        let abc: Abc<Float> = adc.apply(Abc::zero());
+        assert!(abc.a != abc.b && abc.b != abc.c);
+    }
+
+    #[test]
+    fn jitter() {
+        let waveform: Waveform<Float, 1> = Waveform::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(1.0),
+            Theta::from(0),
+        );
+
+        // at the zero crossing the error is the slope times the timing error
+        let mut adc = Adc::new(5).with_jitter(1e-6, 50.0);
+        let errors: Vec<Float> = (0..10000)
+            .map(|_| adc.sample(&waveform, Theta::from(0)).a)
+            .collect();
+        let (mean, sigma) = statistics(&errors);
+        let slope = 2.0 * crate::constants::PI * 50.0;
+        assert_abs_diff_eq!(mean, 0.0, epsilon = 1e-5);
+        assert_abs_diff_eq!(sigma, slope * 1e-6, epsilon = 1e-5);
+    }
+}
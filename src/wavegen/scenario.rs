@@ -0,0 +1,316 @@
+// Copyright 2023 Enphase Energy, Inc and Universal Interoperability for
+// Grid-Forming Inverters (UNIFI) Consortium.
+//
+//    Licensed under the Apache License, Version 2.0 (the "License");
+//    you may not use this file except in compliance with the License.
+//    You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+//    Unless required by applicable law or agreed to in writing, software
+//    distributed under the License is distributed on an "AS IS" BASIS,
+//    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//    See the License for the specific language governing permissions and
+//    limitations under the License.
+
+use crate::newtypes::Frequency;
+use crate::number::{Float, Num};
+use crate::trig::{PhaseAccumulator, Theta};
+use crate::wavegen::Waveform;
+use crate::Abc;
+
+/// A disturbance applied to a [Scenario]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EventKind<T> {
+    /// Scales the amplitude of each phase (i.e. a sag if < 1.0, or a swell if > 1.0)
+    Amplitude(Abc<Float>),
+    /// Shifts the phase of the waveform
+    PhaseJump(Theta),
+    /// Steps the frequency (in Hz)
+    FrequencyStep(Float),
+    /// Ramps the frequency (in Hz/s), holding the final frequency once the event ends
+    FrequencyRamp(Float),
+    /// Drops all three phases to zero
+    Interruption,
+    /// Adds a dc offset to each phase
+    DcOffset(Abc<T>),
+}
+
+/// A disturbance which is active from `start` for `duration` seconds (or forever if `None`)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Event<T> {
+    pub start: Float,
+    pub duration: Option<Float>,
+    pub kind: EventKind<T>,
+}
+
+impl<T> Event<T> {
+    pub fn new(start: Float, duration: Option<Float>, kind: EventKind<T>) -> Self {
+        Self {
+            start,
+            duration,
+            kind,
+        }
+    }
+}
+
+/// A sampled [Waveform] with scheduled events, for testing ride-through and HIL replay
+///
+/// # Examples
+///
+/// ```
+/// use ac_power::number::Float;
+/// use ac_power::wavegen::{Event, EventKind, Magnitude, Scenario, Sequence, Waveform};
+/// use ac_power::trig::Theta;
+/// use ac_power::Abc;
+///
+/// let waveform: Waveform<Float, 1> = Waveform::new().with_harmonic(
+///     1,
+///     Sequence::Positive,
+///     Magnitude::Absolute(1.0),
+///     Theta::from(0),
+/// );
+///
+/// // a 50% sag on phase a at 100ms for 3 cycles, then a 0.5Hz/s frequency ramp
+/// let events = [
+///     Event::new(0.1, Some(0.05), EventKind::Amplitude(Abc { a: 0.5, b: 1.0, c: 1.0 })),
+///     Event::new(0.2, Some(1.0), EventKind::FrequencyRamp(0.5)),
+/// ];
+///
+/// let scenario = Scenario::new(waveform, 60.0, 1.0 / 6000.0, &events);
+/// for abc in scenario.take(12000) {
+///     // feed the samples to the code under test
+/// }
+/// ```
+pub struct Scenario<'a, T, const N: usize> {
+    waveform: Waveform<T, N>,
+    events: &'a [Event<T>],
+    frequency: Float,
+    ts: Float,
+    sample: u32,
+    phase: PhaseAccumulator,
+}
+
+impl<'a, T: Num, const N: usize> Scenario<'a, T, N> {
+    pub fn new(
+        waveform: Waveform<T, N>,
+        frequency: Float,
+        ts: Float,
+        events: &'a [Event<T>],
+    ) -> Self {
+        Self {
+            waveform,
+            events,
+            frequency,
+            ts,
+            sample: 0,
+            phase: PhaseAccumulator::new(ts),
+        }
+    }
+
+    /// Time of the next sample
+    pub fn time(&self) -> Float {
+        (self.sample as Float) * self.ts
+    }
+
+    /// Phase of the fundamental for the next sample (excluding phase jumps)
+    pub fn theta(&self) -> Theta {
+        self.phase.theta()
+    }
+}
+
+impl<T: Num, const N: usize> Iterator for Scenario<'_, T, N> {
+    type Item = Abc<T>;
+
+    fn next(&mut self) -> Option<Abc<T>> {
+        let t = self.time();
+
+        // accumulate the effect of all the events
+        let mut gain = Abc {
+            a: 1.0,
+            b: 1.0,
+            c: 1.0,
+        };
+        let mut offset = Abc::zero();
+        let mut phase = Theta::from(0);
+        let mut frequency = self.frequency;
+        for event in self.events.iter().filter(|event| t >= event.start) {
+            let elapsed = t - event.start;
+            let active = event.duration.is_none_or(|duration| elapsed < duration);
+
+            match event.kind {
+                EventKind::Amplitude(scale) if active => {
+                    gain.a *= scale.a;
+                    gain.b *= scale.b;
+                    gain.c *= scale.c;
+                }
+                EventKind::PhaseJump(jump) if active => phase += jump,
+                EventKind::FrequencyStep(step) if active => frequency += step,
+                EventKind::FrequencyRamp(rocof) => {
+                    let elapsed = event.duration.map_or(elapsed, |d| Float::min(elapsed, d));
+                    frequency += rocof * elapsed;
+                }
+                EventKind::Interruption if active => gain = Abc::zero(),
+                EventKind::DcOffset(dc) if active => offset += dc,
+                _ => {}
+            }
+        }
+
+        let abc = self.waveform.calculate(self.phase.theta() + phase);
+        let abc = Abc {
+            a: abc.a * gain.a,
+            b: abc.b * gain.b,
+            c: abc.c * gain.c,
+        } + offset;
+
+        // integrate the frequency to get the phase of the next sample
+        self.phase.update(Frequency::from(frequency));
+        self.sample += 1;
+
+        Some(abc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::wavegen::{Magnitude, Sequence};
+    use approx::assert_abs_diff_eq;
+
+    const TS: Float = 1.0 / 6000.0;
+
+    fn unity() -> Waveform<Float, 1> {
+        Waveform::new().with_harmonic(
+            1,
+            Sequence::Positive,
+            Magnitude::Absolute(1.0),
+            Theta::from(0),
+        )
+    }
+
+    // peak of each phase over a window of samples
+    fn peaks(samples: &[Abc<Float>]) -> Abc<Float> {
+        samples.iter().fold(Abc::zero(), |peak, abc| Abc {
+            a: peak.a.max(abc.a.abs()),
+            b: peak.b.max(abc.b.abs()),
+            c: peak.c.max(abc.c.abs()),
+        })
+    }
+
+    #[test]
+    fn sag_and_swell() {
+        let events = [
+            Event::new(
+                0.1,
+                Some(0.1),
+                EventKind::Amplitude(Abc {
+                    a: 0.5,
+                    b: 1.0,
+                    c: 1.0,
+                }),
+            ),
+            Event::new(
+                0.15,
+                Some(0.1),
+                EventKind::Amplitude(Abc {
+                    a: 1.0,
+                    b: 1.0,
+                    c: 1.2,
+                }),
+            ),
+        ];
+        let samples: Vec<Abc<Float>> = Scenario::new(unity(), 60.0, TS, &events)
+            .take(1800)
+            .collect();
+
+        // stay clear of the event boundaries, where the sample time is subject to rounding
+        let before = peaks(&samples[0..590]);
+        let sag = peaks(&samples[610..890]);
+        let both = peaks(&samples[910..1190]);
+        let swell = peaks(&samples[1210..1490]);
+        let after = peaks(&samples[1510..1800]);
+
+        assert_abs_diff_eq!(before.a, 1.0, epsilon = 0.001);
+        assert_abs_diff_eq!(sag.a, 0.5, epsilon = 0.001);
+        assert_abs_diff_eq!(sag.c, 1.0, epsilon = 0.001);
+        assert_abs_diff_eq!(both.a, 0.5, epsilon = 0.001);
+        assert_abs_diff_eq!(both.c, 1.2, epsilon = 0.001);
+        assert_abs_diff_eq!(swell.a, 1.0, epsilon = 0.001);
+        assert_abs_diff_eq!(swell.c, 1.2, epsilon = 0.001);
+        assert_abs_diff_eq!(after.c, 1.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn interruption_and_offset() {
+        let events = [
+            Event::new(0.0, Some(0.05), EventKind::Interruption),
+            Event::new(
+                0.0,
+                None,
+                EventKind::DcOffset(Abc {
+                    a: 0.1,
+                    b: 0.0,
+                    c: -0.1,
+                }),
+            ),
+        ];
+        let samples: Vec<Abc<Float>> = Scenario::new(unity(), 50.0, TS, &events)
+            .take(600)
+            .collect();
+
+        for abc in samples[0..290].iter() {
+            assert_abs_diff_eq!(abc.a, 0.1, epsilon = 0.0001);
+            assert_abs_diff_eq!(abc.b, 0.0, epsilon = 0.0001);
+            assert_abs_diff_eq!(abc.c, -0.1, epsilon = 0.0001);
+        }
+
+        // average over whole cycles is just the offset
+        let mean = samples[360..600].iter().fold(0.0, |sum, abc| sum + abc.a) / 240.0;
+        assert_abs_diff_eq!(mean, 0.1, epsilon = 0.001);
+    }
+
+    #[test]
+    fn phase_jump() {
+        let events = [Event::new(
+            0.01,
+            None,
+            EventKind::PhaseJump(Theta::from_degrees(30.0)),
+        )];
+        let mut scenario = Scenario::new(unity(), 60.0, TS, &events);
+        for _ in 0..100 {
+            let jump = if scenario.time() >= 0.01 { 30.0 } else { 0.0 };
+            let theta = scenario.theta() + Theta::from_degrees(jump);
+            let expected = Abc::from_polar(1.0, theta);
+            let abc = scenario.next().unwrap();
+            assert_abs_diff_eq!(abc.a, expected.a, epsilon = 0.0001);
+            assert_abs_diff_eq!(abc.b, expected.b, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn frequency_step_and_ramp() {
+        let events = [
+            Event::new(0.1, Some(0.1), EventKind::FrequencyStep(1.0)),
+            Event::new(0.3, Some(0.2), EventKind::FrequencyRamp(2.5)),
+        ];
+        let mut scenario = Scenario::new(unity(), 60.0, TS, &events);
+
+        // measure the frequency from the phase step at time t
+        fn frequency(scenario: &mut Scenario<Float, 1>, t: Float) -> Float {
+            while scenario.time() < t {
+                scenario.next();
+            }
+            let theta = scenario.theta();
+            scenario.next();
+            let step = i32::from(scenario.theta() - theta);
+            (step as Float) / 4294967296.0 / TS
+        }
+
+        assert_abs_diff_eq!(frequency(&mut scenario, 0.05), 60.0, epsilon = 0.001);
+        assert_abs_diff_eq!(frequency(&mut scenario, 0.15), 61.0, epsilon = 0.001);
+        assert_abs_diff_eq!(frequency(&mut scenario, 0.25), 60.0, epsilon = 0.001);
+        assert_abs_diff_eq!(frequency(&mut scenario, 0.4), 60.25, epsilon = 0.001);
+        assert_abs_diff_eq!(frequency(&mut scenario, 0.6), 60.5, epsilon = 0.001);
+    }
+}